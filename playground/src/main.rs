@@ -239,7 +239,14 @@ async fn main() -> Result<()> {
         )))?;
     }
 
-    srv.add_service(PlaygroundServer::new(pg))
+    // Lets clients like `spaceman health` know the playground is up
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
+    health_reporter
+        .set_serving::<PlaygroundServer<PlaygroundImpl>>()
+        .await;
+
+    srv.add_service(health_service)
+    .add_service(PlaygroundServer::new(pg))
     .serve(addr)
     .await?;
     Ok(())
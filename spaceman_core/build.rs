@@ -0,0 +1,43 @@
+use std::env;
+use std::path::PathBuf;
+
+use prost::Message;
+
+/// Protobuf files, relative to `proto`, that are compiled into the descriptor set that ships with
+/// the crate. The well-known types under `google/protobuf` come with the compiler, the rest of
+/// `google` is vendored from googleapis.
+const BUNDLED_PROTOS: &[&str] = &[
+    "grpc/health/v1/health.proto",
+    "google/protobuf/any.proto",
+    "google/protobuf/api.proto",
+    "google/protobuf/descriptor.proto",
+    "google/protobuf/duration.proto",
+    "google/protobuf/empty.proto",
+    "google/protobuf/field_mask.proto",
+    "google/protobuf/source_context.proto",
+    "google/protobuf/struct.proto",
+    "google/protobuf/timestamp.proto",
+    "google/protobuf/type.proto",
+    "google/protobuf/wrappers.proto",
+    "google/api/annotations.proto",
+    "google/api/field_behavior.proto",
+    "google/api/http.proto",
+    "google/rpc/code.proto",
+    "google/rpc/error_details.proto",
+    "google/rpc/status.proto",
+    "google/type/date.proto",
+    "google/type/latlng.proto",
+    "google/type/money.proto",
+    "google/type/timeofday.proto",
+];
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto");
+
+    let file_descriptor_set = protox::compile(BUNDLED_PROTOS, ["proto"])?;
+
+    let out_path = PathBuf::from(env::var("OUT_DIR")?).join("bundled.desc");
+    std::fs::write(out_path, file_descriptor_set.encode_to_vec())?;
+
+    Ok(())
+}
@@ -0,0 +1,180 @@
+//! JSON conversion of messages that names the type when a `google.protobuf.Any` can't be resolved.
+//!
+//! `Any` values are resolved against the pool of the message, which has every loaded descriptor
+//! and the bundled ones, but `prost_reflect` doesn't say which type was missing when that fails.
+
+use anyhow::{anyhow, Result};
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, MessageDescriptor, ReflectMessage, SerializeOptions, Value,
+};
+use serde::Serializer;
+
+const ANY: &str = "google.protobuf.Any";
+
+/// Parses `json` as a message of type `desc`.
+pub fn message_from_json(
+    desc: MessageDescriptor,
+    json: serde_json::Value,
+) -> Result<DynamicMessage> {
+    match DynamicMessage::deserialize(desc.clone(), &json) {
+        Ok(msg) => Ok(msg),
+        Err(err) => match unresolved_json_type(desc.parent_pool(), &json) {
+            Some(type_url) => Err(unresolved_error(type_url)),
+            None => Err(err.into()),
+        },
+    }
+}
+
+/// Serializes `msg` with `serializer`, like `DynamicMessage::serialize_with_options`.
+pub fn message_to_json<S>(
+    msg: &DynamicMessage,
+    serializer: S,
+    options: &SerializeOptions,
+) -> Result<S::Ok>
+where
+    S: Serializer,
+    S::Error: Send + Sync + 'static,
+{
+    match msg.serialize_with_options(serializer, options) {
+        Ok(ok) => Ok(ok),
+        Err(err) => match unresolved_message_type(msg.descriptor().parent_pool(), msg) {
+            Some(type_url) => Err(unresolved_error(&type_url)),
+            None => Err(err.into()),
+        },
+    }
+}
+
+fn unresolved_error(type_url: &str) -> anyhow::Error {
+    anyhow!("unknown type in `@type`: {type_url}, it isn't in the loaded descriptors")
+}
+
+fn is_resolved(pool: &DescriptorPool, type_url: &str) -> bool {
+    let name = type_url.rsplit_once('/').map_or(type_url, |(_, name)| name);
+    pool.get_message_by_name(name).is_some()
+}
+
+// First `@type` of `json`, depth first, that names a message missing from `pool`
+fn unresolved_json_type<'a>(pool: &DescriptorPool, json: &'a serde_json::Value) -> Option<&'a str> {
+    match json {
+        serde_json::Value::Object(object) => {
+            if let Some(serde_json::Value::String(type_url)) = object.get("@type") {
+                if !is_resolved(pool, type_url) {
+                    return Some(type_url);
+                }
+            }
+            object
+                .values()
+                .find_map(|value| unresolved_json_type(pool, value))
+        }
+        serde_json::Value::Array(array) => array
+            .iter()
+            .find_map(|value| unresolved_json_type(pool, value)),
+        _ => None,
+    }
+}
+
+// First `type_url` of an `Any` in `msg`, depth first, that names a message missing from `pool`
+fn unresolved_message_type(pool: &DescriptorPool, msg: &DynamicMessage) -> Option<String> {
+    if msg.descriptor().full_name() == ANY {
+        let type_url = msg.get_field_by_name("type_url")?;
+        let type_url = type_url.as_str()?;
+        return (!is_resolved(pool, type_url)).then(|| type_url.to_string());
+    }
+    msg.descriptor()
+        .fields()
+        .filter(|field| msg.has_field(field))
+        .find_map(|field| unresolved_value_type(pool, &msg.get_field(&field)))
+}
+
+fn unresolved_value_type(pool: &DescriptorPool, value: &Value) -> Option<String> {
+    match value {
+        Value::Message(msg) => unresolved_message_type(pool, msg),
+        Value::List(values) => values
+            .iter()
+            .find_map(|value| unresolved_value_type(pool, value)),
+        Value::Map(map) => map
+            .values()
+            .find_map(|value| unresolved_value_type(pool, value)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::Repo;
+
+    const PROTO: &str = r#"
+        syntax = "proto3";
+        package test;
+        import "google/protobuf/any.proto";
+        message Inner { string name = 1; int32 count = 2; }
+        message Envelope {
+            google.protobuf.Any payload = 1;
+            repeated google.protobuf.Any items = 2;
+        }
+    "#;
+
+    fn to_json(msg: &DynamicMessage) -> Result<serde_json::Value> {
+        message_to_json(msg, serde_json::value::Serializer, &SerializeOptions::new())
+    }
+
+    #[test]
+    fn packed_any_round_trips() {
+        let repo = Repo::from_protos(&[("test.proto", PROTO)]);
+        let json = json!({
+            "payload": {"@type": "type.googleapis.com/test.Inner", "name": "a", "count": 3},
+            "items": [{"@type": "type.googleapis.com/test.Inner", "name": "b"}],
+        });
+        let envelope =
+            message_from_json(repo.find_message("test.Envelope").unwrap(), json.clone()).unwrap();
+
+        let payload = envelope.get_field_by_name("payload").unwrap();
+        let payload = payload.as_message().unwrap();
+        let value = payload.get_field_by_name("value").unwrap();
+        let inner = DynamicMessage::decode(
+            repo.find_message("test.Inner").unwrap(),
+            value.as_bytes().unwrap().clone(),
+        )
+        .unwrap();
+        assert_eq!(inner.get_field_by_name("name").unwrap().as_str(), Some("a"));
+
+        assert_eq!(to_json(&envelope).unwrap(), json);
+    }
+
+    #[test]
+    fn unknown_type_url_from_json() {
+        let repo = Repo::from_protos(&[("test.proto", PROTO)]);
+        let json = json!({
+            "items": [{"@type": "type.googleapis.com/test.Missing", "name": "b"}],
+        });
+        let err = message_from_json(repo.find_message("test.Envelope").unwrap(), json).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown type in `@type`: type.googleapis.com/test.Missing, it isn't in the loaded \
+             descriptors"
+        );
+    }
+
+    #[test]
+    fn unknown_type_url_to_json() {
+        let repo = Repo::from_protos(&[("test.proto", PROTO)]);
+        let any_desc = repo.find_message("google.protobuf.Any").unwrap();
+        let mut any = DynamicMessage::new(any_desc);
+        any.set_field_by_name(
+            "type_url",
+            Value::String("type.googleapis.com/test.Missing".to_string()),
+        );
+        let mut envelope = DynamicMessage::new(repo.find_message("test.Envelope").unwrap());
+        envelope.set_field_by_name("items", Value::List(vec![Value::Message(any)]));
+
+        let err = to_json(&envelope).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown type in `@type`: type.googleapis.com/test.Missing, it isn't in the loaded \
+             descriptors"
+        );
+    }
+}
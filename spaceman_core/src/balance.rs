@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Context, Result};
+
+use spaceman_types::endpoint::LbPolicy;
+
+/// Prefix of targets that must be resolved to all their addresses, as in gRPC's naming scheme.
+const DNS_SCHEME: &str = "dns:///";
+
+/// Index of the next address to start from for each round-robin balanced authority. It outlives
+/// connections so that consecutive calls land on different addresses.
+static ROUND_ROBIN: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+
+/// Parsed form of an `Endpoint::authority`.
+#[derive(Clone, Debug)]
+pub struct Target {
+    authority: String,
+    hosts: Vec<String>,
+}
+
+impl Target {
+    pub fn parse(authority: &str) -> Result<Self> {
+        let hosts: Vec<_> = authority
+            .strip_prefix(DNS_SCHEME)
+            .unwrap_or(authority)
+            .split(',')
+            .map(str::trim)
+            .filter(|host| !host.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        if hosts.is_empty() {
+            return Err(anyhow!("no address in authority '{}'", authority));
+        }
+
+        Ok(Self {
+            authority: authority.to_string(),
+            hosts,
+        })
+    }
+
+    /// The authority that requests are addressed to and that the server's certificate is checked
+    /// against. Always the first of the listed hosts.
+    pub fn origin(&self) -> &str {
+        &self.hosts[0]
+    }
+
+    /// Lists the `host:port` pairs to connect to, in the order they should be tried. Host names
+    /// are resolved to all of their addresses unless `resolve` is false, which is the case when
+    /// the proxy is in charge of resolving them instead.
+    pub async fn candidates(
+        &self,
+        policy: LbPolicy,
+        default_port: u16,
+        resolve: bool,
+    ) -> Result<Vec<String>> {
+        let mut candidates = Vec::new();
+        for host in &self.hosts {
+            let host = if has_port(host) {
+                host.clone()
+            } else {
+                format!("{host}:{default_port}")
+            };
+
+            if !resolve {
+                candidates.push(host);
+                continue;
+            }
+
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host(host.as_str())
+                .await
+                .with_context(|| format!("resolving '{}'", host))?
+                .collect();
+            for addr in addrs {
+                let addr = addr.to_string();
+                if !candidates.contains(&addr) {
+                    candidates.push(addr);
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            return Err(anyhow!("'{}' resolved to no address", self.authority));
+        }
+
+        if policy == LbPolicy::RoundRobin {
+            let mut next = ROUND_ROBIN.lock().expect("previous holder panicked");
+            let next = next.entry(self.authority.clone()).or_default();
+            let start = *next % candidates.len();
+            *next = start + 1;
+            candidates.rotate_left(start);
+        }
+
+        Ok(candidates)
+    }
+}
+
+fn has_port(host: &str) -> bool {
+    if let Some(rest) = host.strip_prefix('[') {
+        rest.contains("]:")
+    } else {
+        // More than one colon means this is a bare IPv6 address
+        matches!(host.matches(':').count(), 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn candidates(authority: &str, policy: LbPolicy, resolve: bool) -> Vec<String> {
+        Target::parse(authority)
+            .unwrap()
+            .candidates(policy, 443, resolve)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_lists_hosts() {
+        let target = Target::parse("dns:///a.example.com, b.example.com:8443,,").unwrap();
+        assert_eq!(target.hosts, ["a.example.com", "b.example.com:8443"]);
+        assert_eq!(target.origin(), "a.example.com");
+
+        let target = Target::parse("localhost:50051").unwrap();
+        assert_eq!(target.hosts, ["localhost:50051"]);
+    }
+
+    #[test]
+    fn parse_needs_a_host() {
+        for authority in ["", "dns:///", " , "] {
+            assert_eq!(
+                Target::parse(authority).unwrap_err().to_string(),
+                format!("no address in authority '{authority}'")
+            );
+        }
+    }
+
+    #[test]
+    fn ports_are_detected() {
+        assert!(has_port("example.com:80"));
+        assert!(has_port("[::1]:80"));
+        assert!(!has_port("example.com"));
+        assert!(!has_port("[::1]"));
+        assert!(!has_port("::1"));
+    }
+
+    #[tokio::test]
+    async fn unresolved_candidates_get_the_default_port() {
+        assert_eq!(
+            candidates(
+                "a.example.com,b.example.com:8443,[::1]",
+                LbPolicy::PickFirst,
+                false
+            )
+            .await,
+            ["a.example.com:443", "b.example.com:8443", "[::1]:443"]
+        );
+    }
+
+    #[tokio::test]
+    async fn resolved_candidates_are_addresses_without_duplicates() {
+        assert_eq!(
+            candidates(
+                "127.0.0.1, 127.0.0.1:443, [::1]:80",
+                LbPolicy::PickFirst,
+                true
+            )
+            .await,
+            ["127.0.0.1:443", "[::1]:80"]
+        );
+    }
+
+    #[tokio::test]
+    async fn pick_first_keeps_the_order() {
+        for _ in 0..3 {
+            assert_eq!(
+                candidates("pick.first.a,pick.first.b", LbPolicy::PickFirst, false).await,
+                ["pick.first.a:443", "pick.first.b:443"]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn round_robin_starts_from_the_next_address() {
+        let authority = "round.robin.a,round.robin.b,round.robin.c";
+        let mut firsts = Vec::new();
+        for _ in 0..4 {
+            let candidates = candidates(authority, LbPolicy::RoundRobin, false).await;
+            assert_eq!(candidates.len(), 3);
+            firsts.push(candidates[0].clone());
+        }
+        assert_eq!(
+            firsts,
+            [
+                "round.robin.a:443",
+                "round.robin.b:443",
+                "round.robin.c:443",
+                "round.robin.a:443"
+            ]
+        );
+    }
+}
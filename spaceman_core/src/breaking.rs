@@ -0,0 +1,656 @@
+//! Detection of changes between two versions of an API that break existing clients or servers.
+
+use prost_reflect::{
+    Cardinality, EnumDescriptor, FieldDescriptor, Kind, MessageDescriptor, ServiceDescriptor,
+};
+
+use spaceman_types::breaking::{BreakingChange, Severity};
+
+use crate::Repo;
+
+/// Changes from `old` to `new` that break the binary encoding or the JSON mapping, or that are
+/// otherwise worth a look. The worst come first.
+pub fn breaking_changes(old: &Repo, new: &Repo) -> Vec<BreakingChange> {
+    let mut changes = Changes(Vec::new());
+
+    for old_service in old.pool().services() {
+        let new_service = new
+            .pool()
+            .services()
+            .find(|service| service.full_name() == old_service.full_name());
+        match new_service {
+            Some(new_service) => changes.service(&old_service, &new_service),
+            None => changes.push(Severity::Error, old_service.full_name(), "service removed"),
+        }
+    }
+
+    for old_message in old.pool().all_messages() {
+        // Map entries are compared through the fields that use them
+        if old_message.is_map_entry() {
+            continue;
+        }
+        match new.pool().get_message_by_name(old_message.full_name()) {
+            Some(new_message) => changes.message(&old_message, &new_message),
+            // Messages nested in one that was removed are taken for granted
+            None if parent_exists(old_message.parent_message(), new) => changes.push(
+                Severity::Info,
+                old_message.full_name(),
+                "message removed, it could still be in an Any",
+            ),
+            None => (),
+        }
+    }
+
+    for old_enum in old.pool().all_enums() {
+        match new.pool().get_enum_by_name(old_enum.full_name()) {
+            Some(new_enum) => changes.enumeration(&old_enum, &new_enum),
+            None if parent_exists(old_enum.parent_message(), new) => {
+                changes.push(Severity::Info, old_enum.full_name(), "enum removed")
+            }
+            None => (),
+        }
+    }
+
+    let mut changes = changes.0;
+    changes.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| a.subject.cmp(&b.subject))
+    });
+    changes
+}
+
+fn parent_exists(parent: Option<MessageDescriptor>, new: &Repo) -> bool {
+    match parent {
+        Some(parent) => new.pool().get_message_by_name(parent.full_name()).is_some(),
+        None => true,
+    }
+}
+
+struct Changes(Vec<BreakingChange>);
+
+impl Changes {
+    fn push(&mut self, severity: Severity, subject: &str, description: impl Into<String>) {
+        self.0.push(BreakingChange {
+            severity,
+            subject: subject.to_string(),
+            description: description.into(),
+        });
+    }
+
+    fn service(&mut self, old: &ServiceDescriptor, new: &ServiceDescriptor) {
+        for old_method in old.methods() {
+            let subject = old_method.full_name();
+            let new_method = match new
+                .methods()
+                .find(|method| method.name() == old_method.name())
+            {
+                Some(new_method) => new_method,
+                None => {
+                    self.push(Severity::Error, subject, "method removed");
+                    continue;
+                }
+            };
+            if old_method.input().full_name() != new_method.input().full_name() {
+                self.push(
+                    Severity::Error,
+                    subject,
+                    format!(
+                        "input changed from {} to {}",
+                        old_method.input().full_name(),
+                        new_method.input().full_name()
+                    ),
+                );
+            }
+            if old_method.output().full_name() != new_method.output().full_name() {
+                self.push(
+                    Severity::Error,
+                    subject,
+                    format!(
+                        "output changed from {} to {}",
+                        old_method.output().full_name(),
+                        new_method.output().full_name()
+                    ),
+                );
+            }
+            if old_method.is_client_streaming() != new_method.is_client_streaming() {
+                self.push(
+                    Severity::Error,
+                    subject,
+                    streaming_change("client", new_method.is_client_streaming()),
+                );
+            }
+            if old_method.is_server_streaming() != new_method.is_server_streaming() {
+                self.push(
+                    Severity::Error,
+                    subject,
+                    streaming_change("server", new_method.is_server_streaming()),
+                );
+            }
+        }
+    }
+
+    fn message(&mut self, old: &MessageDescriptor, new: &MessageDescriptor) {
+        let (reserved_numbers, reserved_names) = message_reserved(new);
+
+        for old_field in old.fields() {
+            let subject = old_field.full_name();
+            let new_field = match new.get_field(old_field.number()) {
+                Some(new_field) => new_field,
+                None => {
+                    if let Some(renumbered) = new.get_field_by_name(old_field.name()) {
+                        self.push(
+                            Severity::Error,
+                            subject,
+                            format!(
+                                "number changed from {} to {}",
+                                old_field.number(),
+                                renumbered.number()
+                            ),
+                        );
+                    } else if in_ranges(&reserved_numbers, old_field.number())
+                        || reserved_names.iter().any(|name| name == old_field.name())
+                    {
+                        self.push(
+                            Severity::Warning,
+                            subject,
+                            "field removed and reserved, JSON still carrying it is rejected",
+                        );
+                    } else {
+                        self.push(
+                            Severity::Error,
+                            subject,
+                            format!(
+                                "field removed without reserving number {}, it could be reused \
+                                 for something else",
+                                old_field.number()
+                            ),
+                        );
+                    }
+                    continue;
+                }
+            };
+            self.field(&old_field, &new_field);
+        }
+
+        // Fields that take what the old message reserved
+        let (old_reserved_numbers, old_reserved_names) = message_reserved(old);
+        for new_field in new.fields() {
+            if old.get_field(new_field.number()).is_some() {
+                continue;
+            }
+            if in_ranges(&old_reserved_numbers, new_field.number()) {
+                self.push(
+                    Severity::Error,
+                    new_field.full_name(),
+                    format!("uses number {}, which was reserved", new_field.number()),
+                );
+            } else if old_reserved_names
+                .iter()
+                .any(|name| name == new_field.name())
+            {
+                self.push(
+                    Severity::Error,
+                    new_field.full_name(),
+                    format!("uses name {}, which was reserved", new_field.name()),
+                );
+            }
+        }
+    }
+
+    fn field(&mut self, old: &FieldDescriptor, new: &FieldDescriptor) {
+        let subject = old.full_name();
+
+        if old.name() != new.name() {
+            self.push(
+                Severity::Warning,
+                subject,
+                format!("renamed to {}", new.name()),
+            );
+        } else if old.json_name() != new.json_name() {
+            self.push(
+                Severity::Warning,
+                subject,
+                format!(
+                    "JSON name changed from {} to {}",
+                    old.json_name(),
+                    new.json_name()
+                ),
+            );
+        }
+
+        if old.is_map() && new.is_map() {
+            let (old_entry, new_entry) = (map_entry(old), map_entry(new));
+            for (what, old_kind, new_kind) in [
+                ("key", old_entry.0, new_entry.0),
+                ("value", old_entry.1, new_entry.1),
+            ] {
+                if let Some((severity, description)) = kind_change(&old_kind, &new_kind) {
+                    self.push(severity, subject, format!("map {what} {description}"));
+                }
+            }
+        } else if (old.cardinality() == Cardinality::Repeated)
+            != (new.cardinality() == Cardinality::Repeated)
+        {
+            let description = if new.cardinality() == Cardinality::Repeated {
+                "made repeated"
+            } else {
+                "no longer repeated"
+            };
+            self.push(Severity::Error, subject, description);
+        } else if let Some((severity, description)) = kind_change(&old.kind(), &new.kind()) {
+            self.push(severity, subject, description);
+        }
+
+        let (old_oneof, new_oneof) = (
+            old.containing_oneof().map(|oneof| oneof.name().to_string()),
+            new.containing_oneof().map(|oneof| oneof.name().to_string()),
+        );
+        if old_oneof != new_oneof {
+            let description = match (old_oneof, new_oneof) {
+                (None, Some(new_oneof)) => format!("moved into oneof {new_oneof}"),
+                (Some(old_oneof), None) => format!("moved out of oneof {old_oneof}"),
+                (Some(old_oneof), Some(new_oneof)) => {
+                    format!("moved from oneof {old_oneof} to {new_oneof}")
+                }
+                (None, None) => unreachable!(),
+            };
+            self.push(Severity::Error, subject, description);
+        } else if old.supports_presence() != new.supports_presence() {
+            let description = if new.supports_presence() {
+                "now tracks presence"
+            } else {
+                "no longer tracks presence"
+            };
+            self.push(Severity::Info, subject, description);
+        }
+    }
+
+    fn enumeration(&mut self, old: &EnumDescriptor, new: &EnumDescriptor) {
+        let (reserved_numbers, reserved_names) = enum_reserved(new);
+
+        for old_value in old.values() {
+            let subject = old_value.full_name();
+            match new.get_value(old_value.number()) {
+                Some(new_value) if new_value.name() != old_value.name() => self.push(
+                    Severity::Warning,
+                    subject,
+                    format!("renamed to {}", new_value.name()),
+                ),
+                Some(_) => (),
+                None if in_ranges(&reserved_numbers, old_value.number())
+                    || reserved_names.iter().any(|name| name == old_value.name()) =>
+                {
+                    self.push(
+                        Severity::Warning,
+                        subject,
+                        "value removed and reserved, JSON still carrying it is rejected",
+                    )
+                }
+                None => self.push(
+                    Severity::Error,
+                    subject,
+                    format!(
+                        "value removed without reserving number {}",
+                        old_value.number()
+                    ),
+                ),
+            }
+        }
+
+        let (old_reserved_numbers, old_reserved_names) = enum_reserved(old);
+        for new_value in new.values() {
+            if old.get_value(new_value.number()).is_some() {
+                continue;
+            }
+            if in_ranges(&old_reserved_numbers, new_value.number()) {
+                self.push(
+                    Severity::Error,
+                    new_value.full_name(),
+                    format!("uses number {}, which was reserved", new_value.number()),
+                );
+            } else if old_reserved_names
+                .iter()
+                .any(|name| name == new_value.name())
+            {
+                self.push(
+                    Severity::Error,
+                    new_value.full_name(),
+                    format!("uses name {}, which was reserved", new_value.name()),
+                );
+            }
+        }
+    }
+}
+
+fn streaming_change(side: &str, is_streaming: bool) -> String {
+    if is_streaming {
+        format!("made {side} streaming")
+    } else {
+        format!("no longer {side} streaming")
+    }
+}
+
+// Description of how the type of a field changed, if it did, and how bad that is
+fn kind_change(old: &Kind, new: &Kind) -> Option<(Severity, String)> {
+    let (old_name, new_name) = (kind_name(old), kind_name(new));
+    if old_name == new_name {
+        return None;
+    }
+    let severity = match (wire_class(old), wire_class(new)) {
+        (Some(old_class), Some(new_class)) if old_class == new_class => Severity::Warning,
+        _ => Severity::Error,
+    };
+    Some((
+        severity,
+        format!("type changed from {old_name} to {new_name}"),
+    ))
+}
+
+fn kind_name(kind: &Kind) -> String {
+    match kind {
+        Kind::Double => "double".to_string(),
+        Kind::Float => "float".to_string(),
+        Kind::Int32 => "int32".to_string(),
+        Kind::Int64 => "int64".to_string(),
+        Kind::Uint32 => "uint32".to_string(),
+        Kind::Uint64 => "uint64".to_string(),
+        Kind::Sint32 => "sint32".to_string(),
+        Kind::Sint64 => "sint64".to_string(),
+        Kind::Fixed32 => "fixed32".to_string(),
+        Kind::Fixed64 => "fixed64".to_string(),
+        Kind::Sfixed32 => "sfixed32".to_string(),
+        Kind::Sfixed64 => "sfixed64".to_string(),
+        Kind::Bool => "bool".to_string(),
+        Kind::String => "string".to_string(),
+        Kind::Bytes => "bytes".to_string(),
+        Kind::Message(message) => message.full_name().to_string(),
+        Kind::Enum(enum_desc) => enum_desc.full_name().to_string(),
+    }
+}
+
+// Kinds in the same class are encoded the same way, so changing between them only affects the
+// JSON mapping. Messages and floating point numbers are in a class of their own.
+fn wire_class(kind: &Kind) -> Option<u8> {
+    match kind {
+        Kind::Int32 | Kind::Int64 | Kind::Uint32 | Kind::Uint64 | Kind::Bool | Kind::Enum(_) => {
+            Some(0)
+        }
+        Kind::Sint32 | Kind::Sint64 => Some(1),
+        Kind::Fixed32 | Kind::Sfixed32 => Some(2),
+        Kind::Fixed64 | Kind::Sfixed64 => Some(3),
+        Kind::String | Kind::Bytes => Some(4),
+        Kind::Double | Kind::Float | Kind::Message(_) => None,
+    }
+}
+
+fn map_entry(field: &FieldDescriptor) -> (Kind, Kind) {
+    match field.kind() {
+        Kind::Message(entry) => (
+            entry.map_entry_key_field().kind(),
+            entry.map_entry_value_field().kind(),
+        ),
+        _ => unreachable!("map fields are messages"),
+    }
+}
+
+// Reserved ranges as inclusive bounds, and reserved names
+fn message_reserved(message: &MessageDescriptor) -> (Vec<(i32, i32)>, Vec<String>) {
+    let proto = message.descriptor_proto();
+    let ranges = proto
+        .reserved_range
+        .iter()
+        // The end of message ranges is exclusive
+        .map(|range| (range.start(), range.end() - 1))
+        .collect();
+    (ranges, proto.reserved_name.clone())
+}
+
+fn enum_reserved(enum_desc: &EnumDescriptor) -> (Vec<(i32, i32)>, Vec<String>) {
+    let proto = enum_desc.enum_descriptor_proto();
+    let ranges = proto
+        .reserved_range
+        .iter()
+        .map(|range| (range.start(), range.end()))
+        .collect();
+    (ranges, proto.reserved_name.clone())
+}
+
+fn in_ranges(ranges: &[(i32, i32)], number: impl Into<i64>) -> bool {
+    let number = number.into();
+    ranges
+        .iter()
+        .any(|(start, end)| (*start as i64..=*end as i64).contains(&number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(old: &str, new: &str) -> Vec<(Severity, String, String)> {
+        let old = Repo::from_protos(&[("test.proto", old)]);
+        let new = Repo::from_protos(&[("test.proto", new)]);
+        breaking_changes(&old, &new)
+            .into_iter()
+            .map(|change| (change.severity, change.subject, change.description))
+            .collect()
+    }
+
+    fn change(severity: Severity, subject: &str, description: &str) -> (Severity, String, String) {
+        (severity, subject.to_string(), description.to_string())
+    }
+
+    #[test]
+    fn identical_protos_have_no_changes() {
+        let proto = r#"
+            syntax = "proto3";
+            package test;
+            message Msg { string name = 1; }
+            service Svc { rpc Call(Msg) returns (Msg); }
+        "#;
+        assert_eq!(changes(proto, proto), []);
+    }
+
+    #[test]
+    fn renumbered_fields() {
+        let old = r#"syntax = "proto3"; package test; message Msg { string name = 1; }"#;
+        let new = r#"syntax = "proto3"; package test; message Msg { string name = 2; }"#;
+        assert_eq!(
+            changes(old, new),
+            [change(
+                Severity::Error,
+                "test.Msg.name",
+                "number changed from 1 to 2"
+            )]
+        );
+    }
+
+    #[test]
+    fn removed_fields() {
+        let old = r#"
+            syntax = "proto3";
+            package test;
+            message Msg { string name = 1; int32 count = 2; }
+        "#;
+        let new = r#"syntax = "proto3"; package test; message Msg { string name = 1; }"#;
+        assert_eq!(
+            changes(old, new),
+            [change(
+                Severity::Error,
+                "test.Msg.count",
+                "field removed without reserving number 2, it could be reused for something else"
+            )]
+        );
+    }
+
+    #[test]
+    fn reserved_fields() {
+        let old = r#"
+            syntax = "proto3";
+            package test;
+            message Msg { string name = 1; int32 count = 2; int32 size = 3; }
+        "#;
+        let new = r#"
+            syntax = "proto3";
+            package test;
+            message Msg { reserved 2; reserved "size"; string name = 1; }
+        "#;
+        let description = "field removed and reserved, JSON still carrying it is rejected";
+        assert_eq!(
+            changes(old, new),
+            [
+                change(Severity::Warning, "test.Msg.count", description),
+                change(Severity::Warning, "test.Msg.size", description),
+            ]
+        );
+    }
+
+    #[test]
+    fn reusing_reserved_fields() {
+        let old = r#"
+            syntax = "proto3";
+            package test;
+            message Msg { reserved 2, 4 to 6; reserved "size"; string name = 1; }
+        "#;
+        let new = r#"
+            syntax = "proto3";
+            package test;
+            message Msg { string name = 1; int32 count = 5; int32 size = 3; }
+        "#;
+        assert_eq!(
+            changes(old, new),
+            [
+                change(
+                    Severity::Error,
+                    "test.Msg.count",
+                    "uses number 5, which was reserved"
+                ),
+                change(
+                    Severity::Error,
+                    "test.Msg.size",
+                    "uses name size, which was reserved"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn type_changes() {
+        let old = r#"
+            syntax = "proto3";
+            package test;
+            message Other {}
+            message Msg {
+                int32 widened = 1;
+                int32 retyped = 2;
+                bytes text = 3;
+                Other nested = 4;
+                repeated string tags = 5;
+                map<string, int32> counts = 6;
+            }
+        "#;
+        let new = r#"
+            syntax = "proto3";
+            package test;
+            message Other {}
+            message Msg {
+                int64 widened = 1;
+                string retyped = 2;
+                string text = 3;
+                Msg nested = 4;
+                string tags = 5;
+                map<string, string> counts = 6;
+            }
+        "#;
+        assert_eq!(
+            changes(old, new),
+            [
+                change(
+                    Severity::Error,
+                    "test.Msg.counts",
+                    "map value type changed from int32 to string"
+                ),
+                change(
+                    Severity::Error,
+                    "test.Msg.nested",
+                    "type changed from test.Other to test.Msg"
+                ),
+                change(
+                    Severity::Error,
+                    "test.Msg.retyped",
+                    "type changed from int32 to string"
+                ),
+                change(Severity::Error, "test.Msg.tags", "no longer repeated"),
+                change(
+                    Severity::Warning,
+                    "test.Msg.text",
+                    "type changed from bytes to string"
+                ),
+                change(
+                    Severity::Warning,
+                    "test.Msg.widened",
+                    "type changed from int32 to int64"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn streaming_changes() {
+        let old = r#"
+            syntax = "proto3";
+            package test;
+            message Msg {}
+            service Svc {
+                rpc Unary(Msg) returns (Msg);
+                rpc ServerStreaming(Msg) returns (stream Msg);
+                rpc Bidi(stream Msg) returns (stream Msg);
+                rpc Removed(Msg) returns (Msg);
+            }
+        "#;
+        let new = r#"
+            syntax = "proto3";
+            package test;
+            message Msg {}
+            service Svc {
+                rpc Unary(stream Msg) returns (Msg);
+                rpc ServerStreaming(Msg) returns (Msg);
+                rpc Bidi(stream Msg) returns (stream Msg);
+            }
+        "#;
+        assert_eq!(
+            changes(old, new),
+            [
+                change(Severity::Error, "test.Svc.Removed", "method removed"),
+                change(
+                    Severity::Error,
+                    "test.Svc.ServerStreaming",
+                    "no longer server streaming"
+                ),
+                change(Severity::Error, "test.Svc.Unary", "made client streaming"),
+            ]
+        );
+    }
+
+    #[test]
+    fn removed_services_and_messages() {
+        let old = r#"
+            syntax = "proto3";
+            package test;
+            message Msg { message Nested {} }
+            service Svc { rpc Call(Msg) returns (Msg); }
+        "#;
+        let new = r#"syntax = "proto3"; package test;"#;
+        assert_eq!(
+            changes(old, new),
+            [
+                change(Severity::Error, "test.Svc", "service removed"),
+                change(
+                    Severity::Info,
+                    "test.Msg",
+                    "message removed, it could still be in an Any"
+                ),
+            ]
+        );
+    }
+}
@@ -1,8 +1,28 @@
+use prost_reflect::prost::bytes::{Buf, Bytes};
 use prost_reflect::prost::Message;
-use prost_reflect::{DynamicMessage, MethodDescriptor};
+use prost_reflect::{DynamicMessage, MethodDescriptor, ReflectMessage};
 use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
 use tonic::Status;
 
+use spaceman_types::wire::UnknownField;
+
+use crate::wire;
+
+/// A message received from the server, along with its encoding.
+#[derive(Clone, Debug)]
+pub struct Received {
+    pub message: DynamicMessage,
+    /// The message as it came over the wire, without the gRPC framing.
+    pub bytes: Bytes,
+}
+
+impl Received {
+    /// Fields that the server sent but the descriptor of the message doesn't define.
+    pub fn unknown_fields(&self) -> Vec<UnknownField> {
+        wire::unknown_fields(&self.message.descriptor(), &self.bytes)
+    }
+}
+
 #[derive(Clone)]
 pub struct DynamicCodec(MethodDescriptor);
 
@@ -15,7 +35,7 @@ impl DynamicCodec {
 
 impl Codec for DynamicCodec {
     type Encode = DynamicMessage;
-    type Decode = DynamicMessage;
+    type Decode = Received;
     type Encoder = Self;
     type Decoder = Self;
 
@@ -40,13 +60,22 @@ impl Encoder for DynamicCodec {
 }
 
 impl Decoder for DynamicCodec {
-    type Item = DynamicMessage;
+    type Item = Received;
     type Error = Status;
 
     fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Self::Item>, Self::Error> {
+        // Kept because the message drops the fields it doesn't know about
+        let bytes = src.copy_to_bytes(src.remaining());
         let mut msg = DynamicMessage::new(self.0.output());
-        msg.merge(src)
-            .map_err(|err| Status::internal(err.to_string()))?;
-        Ok(Some(msg))
+        msg.merge(bytes.clone()).map_err(|err| {
+            Status::internal(format!(
+                "{err}, the message as received:\n{}",
+                wire::dump(&bytes)
+            ))
+        })?;
+        Ok(Some(Received {
+            message: msg,
+            bytes,
+        }))
     }
 }
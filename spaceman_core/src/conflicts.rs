@@ -0,0 +1,530 @@
+//! Merging of descriptor sets that may define the same files, or the same symbols in different
+//! files.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use prost_reflect::prost::Message;
+use prost_reflect::prost_types::{DescriptorProto, FileDescriptorProto, FileDescriptorSet};
+
+/// Source of the files that ship with the crate.
+pub(crate) const BUNDLED_SOURCE: &str = "<bundled>";
+
+/// A file of a descriptor set and the source it was read from.
+#[derive(Clone, Debug)]
+pub(crate) struct LoadedFile {
+    pub(crate) proto: FileDescriptorProto,
+    pub(crate) source: PathBuf,
+    // Whether it ships with the crate rather than being loaded by the user
+    pub(crate) is_bundled: bool,
+}
+
+impl LoadedFile {
+    pub(crate) fn bundled(proto: FileDescriptorProto) -> Self {
+        LoadedFile {
+            proto,
+            source: PathBuf::from(BUNDLED_SOURCE),
+            is_bundled: true,
+        }
+    }
+}
+
+enum Conflict {
+    // Two sources define the same file differently
+    File {
+        name: String,
+        earlier_source: PathBuf,
+        // Definitions that differ between the two
+        symbols: Vec<String>,
+    },
+    // Two different files define the same symbol
+    Symbol {
+        name: String,
+        file: String,
+        earlier_file: String,
+        earlier_source: PathBuf,
+    },
+    // A file declares a package with the same name as a symbol of another file
+    Package {
+        name: String,
+        file: String,
+        source: PathBuf,
+        symbol_file: String,
+        symbol_source: PathBuf,
+    },
+}
+
+/// `files` with those of `set`, which was read from `source`, added. Files that are already
+/// there and identical are skipped. Files that are already there but differ are replaced if
+/// `later_overrides` is set, and otherwise make the whole set be rejected with a report of what
+/// conflicts with what.
+///
+/// Bundled files never conflict: they are replaced by loaded files of the same name, and dropped
+/// if a loaded file defines any of their symbols.
+pub(crate) fn merge(
+    files: &[LoadedFile],
+    set: FileDescriptorSet,
+    source: &Path,
+    later_overrides: bool,
+) -> Result<Vec<LoadedFile>> {
+    let mut merged = files.to_vec();
+    let mut conflicts = Vec::new();
+
+    for proto in set.file {
+        let loaded = LoadedFile {
+            proto,
+            source: source.to_path_buf(),
+            is_bundled: false,
+        };
+        match merged
+            .iter_mut()
+            .find(|file| file.proto.name() == loaded.proto.name())
+        {
+            None => merged.push(loaded),
+            Some(file) if file.proto == loaded.proto => (),
+            Some(file) if file.is_bundled || later_overrides => *file = loaded,
+            Some(file) => conflicts.push(Conflict::File {
+                name: loaded.proto.name().to_string(),
+                earlier_source: file.source.clone(),
+                symbols: differing_symbols(&file.proto, &loaded.proto),
+            }),
+        }
+    }
+
+    drop_shadowed_bundled_files(&mut merged);
+
+    // Files can't be partially replaced, so these are conflicts even when overriding
+    let mut owners: HashMap<String, &LoadedFile> = HashMap::new();
+    for file in &merged {
+        for symbol in symbols(&file.proto).into_keys() {
+            match owners.get(&symbol) {
+                Some(owner) => conflicts.push(Conflict::Symbol {
+                    name: symbol,
+                    file: file.proto.name().to_string(),
+                    earlier_file: owner.proto.name().to_string(),
+                    earlier_source: owner.source.clone(),
+                }),
+                None => {
+                    owners.insert(symbol, file);
+                }
+            }
+        }
+    }
+    let mut clashing_packages = HashSet::new();
+    for file in &merged {
+        for package in packages(file.proto.package()) {
+            if let Some(owner) = owners.get(package) {
+                if clashing_packages.insert(package) {
+                    conflicts.push(Conflict::Package {
+                        name: package.to_string(),
+                        file: file.proto.name().to_string(),
+                        source: file.source.clone(),
+                        symbol_file: owner.proto.name().to_string(),
+                        symbol_source: owner.source.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    if conflicts.is_empty() {
+        Ok(merged)
+    } else {
+        Err(anyhow!(report(source, &conflicts)))
+    }
+}
+
+// Drops the bundled files that define a symbol that a loaded file also defines, and then those
+// that depend on a dropped file
+fn drop_shadowed_bundled_files(files: &mut Vec<LoadedFile>) {
+    let loaded_symbols: HashSet<String> = files
+        .iter()
+        .filter(|file| !file.is_bundled)
+        .flat_map(|file| symbols(&file.proto).into_keys())
+        .collect();
+    files.retain(|file| {
+        !file.is_bundled
+            || symbols(&file.proto)
+                .keys()
+                .all(|symbol| !loaded_symbols.contains(symbol))
+    });
+
+    loop {
+        let names: HashSet<String> = files
+            .iter()
+            .map(|file| file.proto.name().to_string())
+            .collect();
+        let before = files.len();
+        files.retain(|file| {
+            !file.is_bundled
+                || file
+                    .proto
+                    .dependency
+                    .iter()
+                    .all(|dependency| names.contains(dependency))
+        });
+        if files.len() == before {
+            break;
+        }
+    }
+}
+
+fn report(source: &Path, conflicts: &[Conflict]) -> String {
+    let mut report = format!("conflicting definitions in {}:", source.display());
+    for conflict in conflicts {
+        match conflict {
+            Conflict::File {
+                name,
+                earlier_source,
+                symbols,
+            } => {
+                let _ = write!(
+                    report,
+                    "\n  file {name} differs from the one in {}",
+                    earlier_source.display()
+                );
+                if !symbols.is_empty() {
+                    let _ = write!(report, " ({})", symbols.join(", "));
+                }
+            }
+            Conflict::Symbol {
+                name,
+                file,
+                earlier_file,
+                earlier_source,
+            } => {
+                let _ = write!(
+                    report,
+                    "\n  {name} of {file} is already defined by {earlier_file} in {}",
+                    earlier_source.display()
+                );
+            }
+            Conflict::Package {
+                name,
+                file,
+                source,
+                symbol_file,
+                symbol_source,
+            } => {
+                let _ = write!(
+                    report,
+                    "\n  package {name} of {file} in {} is also defined by {symbol_file} in {}",
+                    source.display(),
+                    symbol_source.display()
+                );
+            }
+        }
+    }
+    if conflicts
+        .iter()
+        .any(|conflict| matches!(conflict, Conflict::File { .. }))
+    {
+        report.push_str("\nlet later descriptors override earlier ones to load it anyway");
+    }
+    report
+}
+
+// Full names of the definitions that are in only one of the files, or in both but different
+fn differing_symbols(earlier: &FileDescriptorProto, later: &FileDescriptorProto) -> Vec<String> {
+    let (earlier, later) = (symbols(earlier), symbols(later));
+    let mut differing: Vec<String> = earlier
+        .iter()
+        .filter(|(name, definition)| later.get(*name) != Some(definition))
+        .map(|(name, _)| name.clone())
+        .collect();
+    differing.extend(
+        later
+            .keys()
+            .filter(|name| !earlier.contains_key(*name))
+            .cloned(),
+    );
+    differing.sort();
+    differing
+}
+
+// The package and those it is nested in, e.g. `a`, `a.b` and `a.b.c` for `a.b.c`
+fn packages(package: &str) -> impl Iterator<Item = &str> {
+    package
+        .match_indices('.')
+        .map(move |(idx, _)| &package[..idx])
+        .chain((!package.is_empty()).then_some(package))
+}
+
+// Messages, enums and services of the file by full name, with their encoded definitions
+fn symbols(file: &FileDescriptorProto) -> BTreeMap<String, Vec<u8>> {
+    let mut symbols = BTreeMap::new();
+    let prefix = if file.package().is_empty() {
+        String::new()
+    } else {
+        format!("{}.", file.package())
+    };
+    for message in &file.message_type {
+        message_symbols(&prefix, message, &mut symbols);
+    }
+    for enum_desc in &file.enum_type {
+        symbols.insert(
+            format!("{prefix}{}", enum_desc.name()),
+            enum_desc.encode_to_vec(),
+        );
+    }
+    for service in &file.service {
+        symbols.insert(
+            format!("{prefix}{}", service.name()),
+            service.encode_to_vec(),
+        );
+    }
+    symbols
+}
+
+fn message_symbols(
+    prefix: &str,
+    message: &DescriptorProto,
+    symbols: &mut BTreeMap<String, Vec<u8>>,
+) {
+    let full_name = format!("{prefix}{}", message.name());
+    let nested_prefix = format!("{full_name}.");
+    for nested in &message.nested_type {
+        message_symbols(&nested_prefix, nested, symbols);
+    }
+    for enum_desc in &message.enum_type {
+        symbols.insert(
+            format!("{nested_prefix}{}", enum_desc.name()),
+            enum_desc.encode_to_vec(),
+        );
+    }
+    symbols.insert(full_name, message.encode_to_vec());
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_reflect::prost_types::EnumDescriptorProto;
+
+    use super::*;
+
+    fn file(name: &str, package: &str, messages: &[&str]) -> FileDescriptorProto {
+        FileDescriptorProto {
+            name: Some(name.to_string()),
+            package: Some(package.to_string()),
+            message_type: messages
+                .iter()
+                .map(|message| DescriptorProto {
+                    name: Some(message.to_string()),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn set(files: Vec<FileDescriptorProto>) -> FileDescriptorSet {
+        FileDescriptorSet { file: files }
+    }
+
+    fn names(files: &[LoadedFile]) -> Vec<&str> {
+        files.iter().map(|file| file.proto.name()).collect()
+    }
+
+    #[test]
+    fn merges_distinct_files() {
+        let files = merge(
+            &[],
+            set(vec![file("a.proto", "pkg", &["A"])]),
+            Path::new("a.desc"),
+            false,
+        )
+        .unwrap();
+        let files = merge(
+            &files,
+            set(vec![file("b.proto", "pkg", &["B"])]),
+            Path::new("b.desc"),
+            false,
+        )
+        .unwrap();
+        assert_eq!(names(&files), ["a.proto", "b.proto"]);
+        assert_eq!(files[1].source, Path::new("b.desc"));
+    }
+
+    #[test]
+    fn identical_file_loaded_twice_is_no_conflict() {
+        let a = file("a.proto", "pkg", &["A"]);
+        let files = merge(&[], set(vec![a.clone()]), Path::new("first.desc"), false).unwrap();
+        let files = merge(&files, set(vec![a]), Path::new("second.desc"), false).unwrap();
+        assert_eq!(names(&files), ["a.proto"]);
+        // The file that was there first is kept
+        assert_eq!(files[0].source, Path::new("first.desc"));
+    }
+
+    #[test]
+    fn same_symbol_in_different_files_conflicts() {
+        let files = merge(
+            &[],
+            set(vec![file("a.proto", "pkg", &["A"])]),
+            Path::new("first.desc"),
+            false,
+        )
+        .unwrap();
+        let err = merge(
+            &files,
+            set(vec![file("b.proto", "pkg", &["A"])]),
+            Path::new("second.desc"),
+            false,
+        )
+        .unwrap_err()
+        .to_string();
+        assert_eq!(
+            err,
+            "conflicting definitions in second.desc:\n  pkg.A of b.proto is already defined by a.proto in first.desc"
+        );
+    }
+
+    #[test]
+    fn same_symbol_conflicts_even_when_overriding() {
+        let files = merge(
+            &[],
+            set(vec![file("a.proto", "pkg", &["A"])]),
+            Path::new("first.desc"),
+            true,
+        )
+        .unwrap();
+        let err = merge(
+            &files,
+            set(vec![file("b.proto", "pkg", &["A"])]),
+            Path::new("second.desc"),
+            true,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains("pkg.A of b.proto is already defined by a.proto"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn nested_symbols_conflict() {
+        let mut a = file("a.proto", "pkg", &["A"]);
+        a.message_type[0].enum_type.push(EnumDescriptorProto {
+            name: Some("Kind".to_string()),
+            ..Default::default()
+        });
+        let mut b = file("b.proto", "pkg.A", &[]);
+        b.enum_type.push(EnumDescriptorProto {
+            name: Some("Kind".to_string()),
+            ..Default::default()
+        });
+        let err = merge(&[], set(vec![a, b]), Path::new("both.desc"), false)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("pkg.A.Kind of b.proto is already defined by a.proto"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn different_file_of_the_same_name_conflicts_unless_overriding() {
+        let files = merge(
+            &[],
+            set(vec![file("a.proto", "pkg", &["A", "B"])]),
+            Path::new("first.desc"),
+            false,
+        )
+        .unwrap();
+        let later = set(vec![file("a.proto", "pkg", &["A", "C"])]);
+
+        let err = merge(&files, later.clone(), Path::new("second.desc"), false)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "conflicting definitions in second.desc:\n  file a.proto differs from the one in first.desc (pkg.B, pkg.C)\nlet later descriptors override earlier ones to load it anyway"
+        );
+
+        let files = merge(&files, later, Path::new("second.desc"), true).unwrap();
+        assert_eq!(names(&files), ["a.proto"]);
+        assert_eq!(files[0].source, Path::new("second.desc"));
+    }
+
+    #[test]
+    fn package_clashing_with_message_conflicts() {
+        let files = merge(
+            &[],
+            set(vec![file("a.proto", "foo", &["bar"])]),
+            Path::new("first.desc"),
+            false,
+        )
+        .unwrap();
+        let err = merge(
+            &files,
+            set(vec![file("b.proto", "foo.bar.baz", &["Baz"])]),
+            Path::new("second.desc"),
+            false,
+        )
+        .unwrap_err()
+        .to_string();
+        assert_eq!(
+            err,
+            "conflicting definitions in second.desc:\n  package foo.bar of b.proto in second.desc is also defined by a.proto in first.desc"
+        );
+    }
+
+    #[test]
+    fn package_clashing_with_later_message_conflicts() {
+        let err = merge(
+            &[],
+            set(vec![
+                file("a.proto", "foo.bar", &["Baz"]),
+                file("b.proto", "foo", &["bar"]),
+            ]),
+            Path::new("both.desc"),
+            false,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains("package foo.bar of a.proto in both.desc is also defined by b.proto"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn lists_packages_from_outermost() {
+        assert_eq!(packages("a.b.c").collect::<Vec<_>>(), ["a", "a.b", "a.b.c"]);
+        assert_eq!(packages("a").collect::<Vec<_>>(), ["a"]);
+        assert_eq!(packages("").count(), 0);
+    }
+
+    #[test]
+    fn loaded_files_replace_bundled_ones() {
+        let bundled = vec![
+            LoadedFile::bundled(file(
+                "google/protobuf/empty.proto",
+                "google.protobuf",
+                &["Empty"],
+            )),
+            LoadedFile::bundled(file(
+                "google/protobuf/other.proto",
+                "google.protobuf",
+                &["Other"],
+            )),
+        ];
+        let mut dependent = file("google/api/uses_other.proto", "google.api", &["UsesOther"]);
+        dependent
+            .dependency
+            .push("google/protobuf/other.proto".to_string());
+        let bundled = [bundled, vec![LoadedFile::bundled(dependent)]].concat();
+
+        // Another file defining `google.protobuf.Other` makes the bundled one, and those that
+        // depend on it, go away instead of conflicting
+        let files = merge(
+            &bundled,
+            set(vec![file("mine.proto", "google.protobuf", &["Other"])]),
+            Path::new("mine.desc"),
+            false,
+        )
+        .unwrap();
+        assert_eq!(names(&files), ["google/protobuf/empty.proto", "mine.proto"]);
+    }
+}
@@ -0,0 +1,217 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context as _, Result};
+use http::Uri;
+use hyper::client::connect::{Connected, Connection};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tower::Service;
+
+use spaceman_types::endpoint::{LbPolicy, ProxyOptions};
+
+use crate::balance::Target;
+use crate::proxy;
+
+/// Opens the TCP connections that hyper runs HTTP/2 over, either directly or by tunneling through
+/// a proxy. The host in the URI given by hyper is ignored in favour of the addresses of the
+/// `Target`, which is how calls get balanced across them.
+#[derive(Clone)]
+pub struct Connector {
+    target: Target,
+    lb_policy: LbPolicy,
+    default_port: u16,
+    proxy: Option<ProxyOptions>,
+    nodelay: bool,
+    connect_timeout: Option<Duration>,
+}
+
+impl Connector {
+    pub fn new(
+        target: Target,
+        lb_policy: LbPolicy,
+        default_port: u16,
+        proxy: Option<ProxyOptions>,
+    ) -> Self {
+        Self {
+            target,
+            lb_policy,
+            default_port,
+            proxy,
+            nodelay: false,
+            connect_timeout: None,
+        }
+    }
+
+    /// Whether to disable Nagle's algorithm on new connections.
+    pub fn set_nodelay(&mut self, nodelay: bool) {
+        self.nodelay = nodelay;
+    }
+
+    /// Bound on the time it takes to open each connection, including any proxy handshake. When
+    /// it runs out, the next address is tried.
+    pub fn set_connect_timeout(&mut self, connect_timeout: Option<Duration>) {
+        self.connect_timeout = connect_timeout;
+    }
+
+    async fn connect(&self, candidate: &str) -> Result<TcpStream> {
+        let connect = async {
+            if let Some(proxy) = &self.proxy {
+                proxy::connect(proxy, candidate).await
+            } else {
+                TcpStream::connect(candidate)
+                    .await
+                    .with_context(|| format!("connecting to {}", candidate))
+            }
+        };
+
+        let stream = if let Some(connect_timeout) = self.connect_timeout {
+            tokio::time::timeout(connect_timeout, connect)
+                .await
+                .map_err(|_| anyhow!("timed out connecting to {}", candidate))??
+        } else {
+            connect.await?
+        };
+
+        stream.set_nodelay(self.nodelay)?;
+        Ok(stream)
+    }
+}
+
+impl Service<Uri> for Connector {
+    type Response = PeerStream;
+    type Error = anyhow::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<PeerStream>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _dst: Uri) -> Self::Future {
+        let this = self.clone();
+
+        Box::pin(async move {
+            let candidates = this
+                .target
+                .candidates(this.lb_policy, this.default_port, this.proxy.is_none())
+                .await?;
+
+            let mut last_err = None;
+            for candidate in candidates {
+                let stream = this.connect(&candidate).await;
+
+                match stream {
+                    Ok(stream) => {
+                        return Ok(PeerStream {
+                            stream,
+                            peer: Peer(candidate),
+                        })
+                    }
+                    Err(err) => last_err = Some(err),
+                }
+            }
+
+            Err(last_err.unwrap_or_else(|| anyhow!("no address to connect to")))
+        })
+    }
+}
+
+/// Address that a connection was made to. hyper attaches it to the extensions of every response
+/// received over that connection.
+#[derive(Clone, Debug)]
+struct Peer(String);
+
+/// TCP stream that remembers the address it was opened to, including when it goes through a
+/// proxy, in which case the socket's own peer address would be the proxy's.
+pub struct PeerStream {
+    stream: TcpStream,
+    peer: Peer,
+}
+
+impl Connection for PeerStream {
+    fn connected(&self) -> Connected {
+        self.stream.connected().extra(self.peer.clone())
+    }
+}
+
+impl AsyncRead for PeerStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for PeerStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+/// Per-call slot for the address its response came from. Calls carry it in the extensions of
+/// their requests for `RecordPeer` to fill in, which happens even if the response is an error.
+#[derive(Clone, Default)]
+pub struct PeerSlot(Arc<Mutex<Option<String>>>);
+
+impl PeerSlot {
+    pub fn get(&self) -> Option<String> {
+        self.0.lock().expect("previous holder panicked").clone()
+    }
+}
+
+/// Wraps the HTTP client to fill in the `PeerSlot` of each request once its response arrives.
+#[derive(Clone)]
+pub struct RecordPeer<S> {
+    inner: S,
+}
+
+impl<S> RecordPeer<S> {
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+}
+
+impl<S, B, R> Service<http::Request<B>> for RecordPeer<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<R>>,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<R>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<http::Response<R>, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let slot = req.extensions().get::<PeerSlot>().cloned();
+        let res = self.inner.call(req);
+
+        Box::pin(async move {
+            let res = res.await?;
+            if let (Some(slot), Some(Peer(peer))) = (slot, res.extensions().get::<Peer>()) {
+                *slot.0.lock().expect("previous holder panicked") = Some(peer.clone());
+            }
+            Ok(res)
+        })
+    }
+}
@@ -0,0 +1,660 @@
+//! Writes the files loaded into a `Repo` back out, whole or cut down to what a few services and
+//! methods need, as a descriptor set or as `.proto` sources.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use anyhow::{anyhow, bail, Context, Result};
+use prost_reflect::prost::Message;
+use prost_reflect::prost_types::{DescriptorProto, FileDescriptorProto, FileDescriptorSet};
+use prost_reflect::{DynamicMessage, SerializeOptions, Value};
+
+use spaceman_types::repo::method_full_name;
+
+use crate::Repo;
+
+mod proto;
+
+// Field numbers from descriptor.proto
+const FILE_DEPENDENCY: i32 = 3;
+const FILE_MESSAGE_TYPE: i32 = 4;
+const FILE_ENUM_TYPE: i32 = 5;
+const FILE_SERVICE: i32 = 6;
+const FILE_EXTENSION: i32 = 7;
+const FILE_PUBLIC_DEPENDENCY: i32 = 10;
+const FILE_WEAK_DEPENDENCY: i32 = 11;
+const SERVICE_METHOD: i32 = 2;
+const SET_FILE: u32 = 1;
+// Of files, messages, enums, services and methods alike
+const NAME: u32 = 1;
+const FILE_OPTIONS: u32 = 8;
+
+/// The files of `repo` as a self-contained descriptor set, dependencies first.
+///
+/// When `roots` is empty all loaded files are included, along with the bundled files they import.
+/// Otherwise `roots` are full names of services, methods, messages or enums and the set only has
+/// what they need: the messages and enums they reach through fields, inputs and outputs, and of
+/// services only the methods that were asked for. Messages are kept whole, with all their nested
+/// definitions, and extensions declared at the top level of files are dropped.
+///
+/// The files in the set lack custom options, which `prost_types` can't represent, but
+/// `to_bytes` and `to_json` put them back from the files that were loaded.
+pub fn descriptor_set(repo: &Repo, roots: &[String]) -> Result<FileDescriptorSet> {
+    let files: Vec<FileDescriptorProto> = repo.file_protos().cloned().collect();
+    let files = if roots.is_empty() {
+        files
+    } else {
+        Closure::new(&files).prune(&files, roots)?
+    };
+    let wanted: Vec<&str> = files
+        .iter()
+        .map(FileDescriptorProto::name)
+        .filter(|name| !roots.is_empty() || !repo.is_bundled(name))
+        .collect();
+    Ok(FileDescriptorSet {
+        file: dependencies_first(&files, &wanted),
+    })
+}
+
+/// `set` encoded like the output of `protoc -o`, with the custom options of the files of `repo`
+/// that it was made from.
+pub fn to_bytes(repo: &Repo, set: &FileDescriptorSet) -> Result<Vec<u8>> {
+    Ok(with_options(repo, set)?.encode_to_vec())
+}
+
+/// `set` in the JSON mapping of Protobuf, pretty printed, with the custom options of the files of
+/// `repo` that it was made from.
+pub fn to_json(repo: &Repo, set: &FileDescriptorSet) -> Result<String> {
+    let message = with_options(repo, set)?;
+    let mut json = Vec::new();
+    let mut serializer = serde_json::Serializer::pretty(&mut json);
+    message.serialize_with_options(
+        &mut serializer,
+        &SerializeOptions::new().skip_default_fields(true),
+    )?;
+    Ok(String::from_utf8(json)?)
+}
+
+/// A `.proto` source for each file of `set`, keyed by file name. Comments are carried over if
+/// the descriptors were compiled with `--include_source_info`.
+pub fn to_proto_files(set: &FileDescriptorSet) -> Vec<(String, String)> {
+    set.file
+        .iter()
+        .map(|file| (file.name().to_string(), proto::render(file)))
+        .collect()
+}
+
+// `set` decoded through the pool of `repo`, with the options of each file and of the definitions
+// in it taken from the loaded file of the same name. Messages and enums are never cut down, so
+// they are swapped whole for the loaded ones, and services are too, less the methods that were
+// pruned.
+fn with_options(repo: &Repo, set: &FileDescriptorSet) -> Result<DynamicMessage> {
+    let set_desc = repo
+        .pool()
+        .get_message_by_name("google.protobuf.FileDescriptorSet")
+        .ok_or_else(|| anyhow!("descriptor.proto is missing from the loaded files"))?;
+    let mut message = DynamicMessage::decode(set_desc, &set.encode_to_vec()[..])
+        .context("decoding descriptor set")?;
+    let index = repo.options_index();
+    if let Some(files) = message
+        .get_field_by_number_mut(SET_FILE)
+        .and_then(Value::as_list_mut)
+    {
+        for file in files.iter_mut().filter_map(Value::as_message_mut) {
+            if let Some(loaded) = name_of(file).and_then(|name| index.file(&name)) {
+                restore_options(file, loaded);
+            }
+        }
+    }
+    Ok(message)
+}
+
+fn restore_options(file: &mut DynamicMessage, loaded: &DynamicMessage) {
+    if loaded.has_field_by_number(FILE_OPTIONS) {
+        if let Some(options) = loaded.get_field_by_number(FILE_OPTIONS) {
+            file.set_field_by_number(FILE_OPTIONS, options.into_owned());
+        }
+    }
+    for number in [FILE_MESSAGE_TYPE, FILE_ENUM_TYPE, FILE_SERVICE] {
+        let number = number as u32;
+        let loaded_definitions = match loaded.get_field_by_number(number) {
+            Some(definitions) => definitions.into_owned(),
+            None => continue,
+        };
+        let loaded_definitions = loaded_definitions.as_list().unwrap_or_default();
+        let definitions = match file
+            .get_field_by_number_mut(number)
+            .and_then(Value::as_list_mut)
+        {
+            Some(definitions) => definitions,
+            None => continue,
+        };
+        for definition in definitions.iter_mut() {
+            let name = definition.as_message().and_then(name_of);
+            let loaded_definition = loaded_definitions
+                .iter()
+                .find(|loaded| loaded.as_message().and_then(name_of) == name);
+            let mut loaded_definition = match loaded_definition {
+                Some(loaded_definition) => loaded_definition.clone(),
+                None => continue,
+            };
+            if number == FILE_SERVICE as u32 {
+                let methods = definition
+                    .as_message()
+                    .and_then(|service| service.get_field_by_number(SERVICE_METHOD as u32))
+                    .map(|methods| names_of(&methods))
+                    .unwrap_or_default();
+                if let Some(loaded_methods) = loaded_definition
+                    .as_message_mut()
+                    .and_then(|service| service.get_field_by_number_mut(SERVICE_METHOD as u32))
+                    .and_then(Value::as_list_mut)
+                {
+                    loaded_methods.retain(|method| {
+                        method
+                            .as_message()
+                            .and_then(name_of)
+                            .map_or(false, |name| methods.contains(&name))
+                    });
+                }
+            }
+            *definition = loaded_definition;
+        }
+    }
+}
+
+// The name of a file, message, enum, service or method
+fn name_of(definition: &DynamicMessage) -> Option<String> {
+    definition
+        .get_field_by_number(NAME)
+        .and_then(|name| name.as_str().map(str::to_string))
+}
+
+fn names_of(definitions: &Value) -> Vec<String> {
+    definitions
+        .as_list()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|definition| definition.as_message().and_then(name_of))
+        .collect()
+}
+
+// Where the top-level message or enum that contains a definition lives
+#[derive(Clone)]
+struct Owner {
+    file: usize,
+    // Full name of the top-level definition
+    top_level: String,
+}
+
+// The definitions that `roots` need, at the granularity of top-level messages and enums
+struct Closure {
+    // Every message and enum, nested ones too, by full name
+    owners: HashMap<String, Owner>,
+    // Full names of the top-level messages and enums that are needed
+    types: HashSet<String>,
+    // Full names of the services that are needed, with the names of the methods that are, or
+    // `None` for all of them
+    services: HashMap<String, Option<BTreeSet<String>>>,
+}
+
+impl Closure {
+    fn new(files: &[FileDescriptorProto]) -> Self {
+        let mut owners = HashMap::new();
+        for (index, file) in files.iter().enumerate() {
+            let prefix = package_prefix(file);
+            for message in &file.message_type {
+                let top_level = format!("{prefix}{}", message.name());
+                let owner = Owner {
+                    file: index,
+                    top_level: top_level.clone(),
+                };
+                index_message(&top_level, message, &owner, &mut owners);
+            }
+            for enum_desc in &file.enum_type {
+                let top_level = format!("{prefix}{}", enum_desc.name());
+                owners.insert(
+                    top_level.clone(),
+                    Owner {
+                        file: index,
+                        top_level,
+                    },
+                );
+            }
+        }
+        Closure {
+            owners,
+            types: HashSet::new(),
+            services: HashMap::new(),
+        }
+    }
+
+    // The files with only what `roots` need, those that end up empty left out
+    fn prune(
+        mut self,
+        files: &[FileDescriptorProto],
+        roots: &[String],
+    ) -> Result<Vec<FileDescriptorProto>> {
+        let services: HashMap<String, (usize, usize)> = files
+            .iter()
+            .enumerate()
+            .flat_map(|(file_index, file)| {
+                let prefix = package_prefix(file);
+                file.service
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, service)| {
+                        (format!("{prefix}{}", service.name()), (file_index, index))
+                    })
+            })
+            .collect();
+
+        for root in roots {
+            let root = method_full_name(root);
+            let root = root.as_str();
+            if let Some(&(file, index)) = services.get(root) {
+                self.services.insert(root.to_string(), None);
+                for method in &files[file].service[index].method {
+                    self.add_type(files, method.input_type())?;
+                    self.add_type(files, method.output_type())?;
+                }
+                continue;
+            }
+            if self.owners.contains_key(root) {
+                self.add_type(files, root)?;
+                continue;
+            }
+            let method = root
+                .rsplit_once('.')
+                .and_then(|(service_name, method_name)| {
+                    let &(file, index) = services.get(service_name)?;
+                    let method = files[file].service[index]
+                        .method
+                        .iter()
+                        .find(|method| method.name() == method_name)?;
+                    Some((service_name, method))
+                });
+            let (service_name, method) = match method {
+                Some(method) => method,
+                None => bail!("no service, method, message or enum called {root}"),
+            };
+            if let Some(methods) = self
+                .services
+                .entry(service_name.to_string())
+                .or_insert_with(|| Some(BTreeSet::new()))
+            {
+                methods.insert(method.name().to_string());
+            }
+            self.add_type(files, method.input_type())?;
+            self.add_type(files, method.output_type())?;
+        }
+
+        let mut pruned = Vec::new();
+        for file in files {
+            if let Some(file) = self.prune_file(files, file) {
+                pruned.push(file);
+            }
+        }
+        Ok(pruned)
+    }
+
+    // Marks the top-level definition that contains `type_name` as needed, and with it everything
+    // that its fields refer to
+    fn add_type(&mut self, files: &[FileDescriptorProto], type_name: &str) -> Result<()> {
+        let mut pending = vec![type_name.trim_start_matches('.').to_string()];
+        while let Some(type_name) = pending.pop() {
+            let owner = self
+                .owners
+                .get(&type_name)
+                .ok_or_else(|| anyhow!("no message or enum called {type_name}"))?;
+            if !self.types.insert(owner.top_level.clone()) {
+                continue;
+            }
+            let file = &files[owner.file];
+            let prefix = package_prefix(file);
+            let top_level = owner
+                .top_level
+                .strip_prefix(&prefix)
+                .unwrap_or(&owner.top_level);
+            if let Some(message) = file
+                .message_type
+                .iter()
+                .find(|message| message.name() == top_level)
+            {
+                pending.extend(
+                    referenced_types(message)
+                        .map(|type_name| type_name.trim_start_matches('.').to_string()),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn prune_file(
+        &self,
+        files: &[FileDescriptorProto],
+        file: &FileDescriptorProto,
+    ) -> Option<FileDescriptorProto> {
+        let prefix = package_prefix(file);
+        let is_needed = |name: &str| self.types.contains(&format!("{prefix}{name}"));
+
+        let mut pruned = file.clone();
+        // Old index of each definition that is kept, in the new order
+        let messages = retain_indexed(&mut pruned.message_type, |message| {
+            is_needed(message.name())
+        });
+        let enums = retain_indexed(&mut pruned.enum_type, |enum_desc| {
+            is_needed(enum_desc.name())
+        });
+        let services = retain_indexed(&mut pruned.service, |service| {
+            self.services
+                .contains_key(&format!("{prefix}{}", service.name()))
+        });
+        let mut methods = BTreeMap::new();
+        for (service, old_index) in pruned.service.iter_mut().zip(&services) {
+            if let Some(Some(names)) = self.services.get(&format!("{prefix}{}", service.name())) {
+                let kept =
+                    retain_indexed(&mut service.method, |method| names.contains(method.name()));
+                methods.insert(*old_index, kept);
+            }
+        }
+        if pruned.message_type.is_empty()
+            && pruned.enum_type.is_empty()
+            && pruned.service.is_empty()
+        {
+            return None;
+        }
+        pruned.extension.clear();
+
+        // Only import the files that define what is referenced, which may have been reachable
+        // through a public import before
+        let mut imported = BTreeSet::new();
+        let mut type_names: Vec<&str> = pruned
+            .message_type
+            .iter()
+            .flat_map(referenced_types)
+            .collect();
+        for service in &pruned.service {
+            for method in &service.method {
+                type_names.extend([method.input_type(), method.output_type()]);
+            }
+        }
+        for type_name in type_names {
+            if let Some(owner) = self.owners.get(type_name.trim_start_matches('.')) {
+                let owner_file = files[owner.file].name();
+                if owner_file != file.name() {
+                    imported.insert(owner_file.to_string());
+                }
+            }
+        }
+        pruned
+            .dependency
+            .retain(|dependency| imported.contains(dependency));
+        for dependency in imported {
+            if !pruned.dependency.contains(&dependency) {
+                pruned.dependency.push(dependency);
+            }
+        }
+        pruned.public_dependency.clear();
+        pruned.weak_dependency.clear();
+
+        // Point the locations at the new indices, dropping those of what was removed
+        if let Some(source_code_info) = &mut pruned.source_code_info {
+            let kept = Kept {
+                messages,
+                enums,
+                services,
+                methods,
+            };
+            source_code_info
+                .location
+                .retain_mut(|location| kept.remap(&mut location.path));
+        }
+        Some(pruned)
+    }
+}
+
+// Old indices of the definitions of a file that are kept, in their new order
+struct Kept {
+    messages: Vec<usize>,
+    enums: Vec<usize>,
+    services: Vec<usize>,
+    // By old index of the service, only for those whose methods were picked
+    methods: BTreeMap<usize, Vec<usize>>,
+}
+
+impl Kept {
+    // Rewrites `path` to point at the same definition after pruning, returns false if it was
+    // removed
+    fn remap(&self, path: &mut [i32]) -> bool {
+        let kept = match path.first() {
+            Some(
+                &(FILE_DEPENDENCY | FILE_EXTENSION | FILE_PUBLIC_DEPENDENCY | FILE_WEAK_DEPENDENCY),
+            ) => return false,
+            Some(&FILE_MESSAGE_TYPE) => &self.messages,
+            Some(&FILE_ENUM_TYPE) => &self.enums,
+            Some(&FILE_SERVICE) => &self.services,
+            _ => return true,
+        };
+        let old_index = match path.get(1) {
+            Some(&old_index) => old_index,
+            None => return true,
+        };
+        let new_index = match position(kept, old_index) {
+            Some(new_index) => new_index,
+            None => return false,
+        };
+        path[1] = new_index;
+
+        if path[0] == FILE_SERVICE && path.get(2) == Some(&SERVICE_METHOD) && path.len() > 3 {
+            if let Some(methods) = self.methods.get(&(old_index as usize)) {
+                match position(methods, path[3]) {
+                    Some(new_method) => path[3] = new_method,
+                    None => return false,
+                }
+            }
+        }
+        true
+    }
+}
+
+fn index_message(
+    full_name: &str,
+    message: &DescriptorProto,
+    owner: &Owner,
+    owners: &mut HashMap<String, Owner>,
+) {
+    owners.insert(full_name.to_string(), owner.clone());
+    for nested in &message.nested_type {
+        index_message(
+            &format!("{full_name}.{}", nested.name()),
+            nested,
+            owner,
+            owners,
+        );
+    }
+    for enum_desc in &message.enum_type {
+        owners.insert(format!("{full_name}.{}", enum_desc.name()), owner.clone());
+    }
+}
+
+// Type names that the fields of `message` and its nested messages refer to, extendees included
+fn referenced_types(message: &DescriptorProto) -> Box<dyn Iterator<Item = &str> + '_> {
+    let fields = message
+        .field
+        .iter()
+        .chain(&message.extension)
+        .flat_map(|field| [field.type_name(), field.extendee()])
+        .filter(|type_name| !type_name.is_empty());
+    Box::new(fields.chain(message.nested_type.iter().flat_map(referenced_types)))
+}
+
+fn package_prefix(file: &FileDescriptorProto) -> String {
+    match file.package() {
+        "" => String::new(),
+        package => format!("{package}."),
+    }
+}
+
+// Keeps the items for which `keep` returns true and returns their old indices
+fn retain_indexed<T>(items: &mut Vec<T>, mut keep: impl FnMut(&T) -> bool) -> Vec<usize> {
+    let mut kept = Vec::new();
+    let mut index = 0;
+    items.retain(|item| {
+        let is_kept = keep(item);
+        if is_kept {
+            kept.push(index);
+        }
+        index += 1;
+        is_kept
+    });
+    kept
+}
+
+// New index of what was at `old_index`, if it was kept
+fn position(kept: &[usize], old_index: i32) -> Option<i32> {
+    kept.iter()
+        .position(|&index| index as i32 == old_index)
+        .map(|index| index as i32)
+}
+
+// Orders `files` so that each comes after the files it imports, otherwise keeping their order
+fn dependencies_first(files: &[FileDescriptorProto], wanted: &[&str]) -> Vec<FileDescriptorProto> {
+    fn visit(
+        name: &str,
+        by_name: &HashMap<String, &FileDescriptorProto>,
+        visited: &mut HashSet<String>,
+        ordered: &mut Vec<FileDescriptorProto>,
+    ) {
+        let file = match by_name.get(name) {
+            Some(file) if visited.insert(name.to_string()) => file,
+            _ => return,
+        };
+        for dependency in &file.dependency {
+            visit(dependency, by_name, visited, ordered);
+        }
+        ordered.push((*file).clone());
+    }
+
+    let by_name: HashMap<String, &FileDescriptorProto> = files
+        .iter()
+        .map(|file| (file.name().to_string(), file))
+        .collect();
+    let mut visited = HashSet::new();
+    let mut ordered = Vec::new();
+    for name in wanted {
+        visit(name, &by_name, &mut visited, &mut ordered);
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_reflect::ReflectMessage;
+
+    use super::*;
+
+    const MESSAGE_OPTIONS: u32 = 7;
+    const METHOD_OPTIONS: u32 = 4;
+
+    const PROTO: &str = r#"
+        syntax = "proto3";
+        package test;
+        import "google/protobuf/descriptor.proto";
+        extend google.protobuf.FileOptions { string owner = 50000; }
+        extend google.protobuf.MessageOptions { bool internal = 50001; }
+        extend google.protobuf.MethodOptions { int32 timeout_ms = 50002; }
+        option (owner) = "payments";
+        message Request { option (internal) = true; string id = 1; }
+        message Unused { option (internal) = true; }
+        service Svc {
+            rpc Call(Request) returns (Request) { option (timeout_ms) = 500; }
+            rpc Other(Request) returns (Request) { option (timeout_ms) = 100; }
+        }
+    "#;
+
+    // The file called `name` of an exported set, decoded through the pool of `repo`
+    fn exported_file(repo: &Repo, bytes: &[u8], name: &str) -> DynamicMessage {
+        let set_desc = repo
+            .pool()
+            .get_message_by_name("google.protobuf.FileDescriptorSet")
+            .unwrap();
+        let set = DynamicMessage::decode(set_desc, bytes).unwrap();
+        let files = set.get_field_by_number(SET_FILE).unwrap();
+        let file = files
+            .as_list()
+            .unwrap()
+            .iter()
+            .filter_map(Value::as_message)
+            .find(|file| name_of(file).as_deref() == Some(name))
+            .unwrap();
+        file.clone()
+    }
+
+    fn definitions(message: &DynamicMessage, number: i32) -> Vec<DynamicMessage> {
+        let definitions = message.get_field_by_number(number as u32).unwrap();
+        let definitions = definitions.as_list().unwrap();
+        definitions
+            .iter()
+            .filter_map(Value::as_message)
+            .cloned()
+            .collect()
+    }
+
+    // The value of the custom option `name` in the options of `definition`
+    fn option(definition: &DynamicMessage, options_field: u32, name: &str) -> Option<Value> {
+        let options = definition.get_field_by_number(options_field)?;
+        let options = options.as_message()?;
+        let extension = options
+            .descriptor()
+            .extensions()
+            .find(|extension| extension.full_name() == name)?;
+        options
+            .has_extension(&extension)
+            .then(|| options.get_extension(&extension).into_owned())
+    }
+
+    #[test]
+    fn whole_set_keeps_custom_options() {
+        let repo = Repo::from_protos(&[("test.proto", PROTO)]);
+        let set = descriptor_set(&repo, &[]).unwrap();
+        let bytes = to_bytes(&repo, &set).unwrap();
+        assert_eq!(
+            exported_file(&repo, &bytes, "test.proto"),
+            *repo.options_index().file("test.proto").unwrap()
+        );
+    }
+
+    #[test]
+    fn pruned_set_keeps_custom_options() {
+        let repo = Repo::from_protos(&[("test.proto", PROTO)]);
+        let set = descriptor_set(&repo, &["test.Svc.Call".to_string()]).unwrap();
+        let file = exported_file(&repo, &to_bytes(&repo, &set).unwrap(), "test.proto");
+
+        assert_eq!(
+            option(&file, FILE_OPTIONS, "test.owner"),
+            Some(Value::String("payments".to_string()))
+        );
+        let messages = definitions(&file, FILE_MESSAGE_TYPE);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            option(&messages[0], MESSAGE_OPTIONS, "test.internal"),
+            Some(Value::Bool(true))
+        );
+        let services = definitions(&file, FILE_SERVICE);
+        let methods = definitions(&services[0], SERVICE_METHOD);
+        assert_eq!(methods.len(), 1);
+        assert_eq!(name_of(&methods[0]).as_deref(), Some("Call"));
+        assert_eq!(
+            option(&methods[0], METHOD_OPTIONS, "test.timeout_ms"),
+            Some(Value::I32(500))
+        );
+    }
+
+    #[test]
+    fn json_has_custom_options() {
+        let repo = Repo::from_protos(&[("test.proto", PROTO)]);
+        let set = descriptor_set(&repo, &["test.Svc.Call".to_string()]).unwrap();
+        let json = to_json(&repo, &set).unwrap();
+        assert!(json.contains(r#""[test.owner]": "payments""#), "{json}");
+        assert!(json.contains(r#""[test.timeout_ms]": 500"#), "{json}");
+    }
+}
@@ -0,0 +1,638 @@
+//! Regenerates `.proto` sources out of file descriptors.
+//!
+//! Type names are written fully qualified, with a leading dot, so that they resolve the same no
+//! matter what the file imports.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use prost_reflect::prost_types::field_descriptor_proto::{Label, Type};
+use prost_reflect::prost_types::source_code_info::Location;
+use prost_reflect::prost_types::{
+    DescriptorProto, EnumDescriptorProto, FieldDescriptorProto, FileDescriptorProto,
+    ServiceDescriptorProto,
+};
+
+// Field numbers from descriptor.proto
+const FILE_MESSAGE_TYPE: i32 = 4;
+const FILE_ENUM_TYPE: i32 = 5;
+const FILE_SERVICE: i32 = 6;
+const FILE_EXTENSION: i32 = 7;
+const MESSAGE_FIELD: i32 = 2;
+const MESSAGE_NESTED_TYPE: i32 = 3;
+const MESSAGE_ENUM_TYPE: i32 = 4;
+const MESSAGE_EXTENSION: i32 = 6;
+const MESSAGE_ONEOF_DECL: i32 = 8;
+const ENUM_VALUE: i32 = 2;
+const SERVICE_METHOD: i32 = 2;
+
+// Exclusive end of the ranges that reach the highest field number
+const MAX_FIELD_END: i32 = 536_870_912;
+
+pub(super) fn render(file: &FileDescriptorProto) -> String {
+    let mut printer = Printer {
+        out: String::new(),
+        depth: 0,
+        is_proto3: file.syntax() == "proto3",
+        locations: file
+            .source_code_info
+            .iter()
+            .flat_map(|info| &info.location)
+            .map(|location| (location.path.clone(), location))
+            .collect(),
+    };
+    printer.file(file);
+    printer.out
+}
+
+struct Printer<'a> {
+    out: String,
+    depth: usize,
+    is_proto3: bool,
+    locations: HashMap<Vec<i32>, &'a Location>,
+}
+
+impl Printer<'_> {
+    fn file(&mut self, file: &FileDescriptorProto) {
+        let syntax = match file.syntax() {
+            "" => "proto2",
+            syntax => syntax,
+        };
+        self.line(&format!("syntax = {};", quote(syntax)));
+        if !file.package().is_empty() {
+            self.blank();
+            self.line(&format!("package {};", file.package()));
+        }
+
+        if !file.dependency.is_empty() {
+            self.blank();
+        }
+        for (index, dependency) in file.dependency.iter().enumerate() {
+            let index = index as i32;
+            let modifier = if file.public_dependency.contains(&index) {
+                "public "
+            } else if file.weak_dependency.contains(&index) {
+                "weak "
+            } else {
+                ""
+            };
+            self.line(&format!("import {modifier}{};", quote(dependency)));
+        }
+
+        let options = file.options.as_ref().map(file_options).unwrap_or_default();
+        if !options.is_empty() {
+            self.blank();
+        }
+        for option in options {
+            self.line(&format!("option {option};"));
+        }
+
+        for (index, message) in file.message_type.iter().enumerate() {
+            self.blank();
+            self.message(
+                message,
+                &package_prefix(file),
+                &[FILE_MESSAGE_TYPE, index as i32],
+            );
+        }
+        for (index, enum_desc) in file.enum_type.iter().enumerate() {
+            self.blank();
+            self.enumeration(enum_desc, &[FILE_ENUM_TYPE, index as i32]);
+        }
+        for (index, service) in file.service.iter().enumerate() {
+            self.blank();
+            self.service(service, &[FILE_SERVICE, index as i32]);
+        }
+        self.extensions(&file.extension, &[], FILE_EXTENSION);
+    }
+
+    // `prefix` is the full name of the parent followed by a dot, or empty at the root of a file
+    // without a package
+    fn message(&mut self, message: &DescriptorProto, prefix: &str, path: &[i32]) {
+        let full_name = format!("{prefix}{}", message.name());
+        self.comments(path);
+        self.line(&format!("message {} {{", message.name()));
+        self.depth += 1;
+        self.message_body(message, &full_name, path);
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn message_body(&mut self, message: &DescriptorProto, full_name: &str, path: &[i32]) {
+        if let Some(options) = &message.options {
+            let mut pairs = Vec::new();
+            push_flag(
+                &mut pairs,
+                "message_set_wire_format",
+                options.message_set_wire_format,
+            );
+            push_flag(
+                &mut pairs,
+                "no_standard_descriptor_accessor",
+                options.no_standard_descriptor_accessor,
+            );
+            push_flag(&mut pairs, "deprecated", options.deprecated);
+            for option in pairs {
+                self.line(&format!("option {option};"));
+            }
+        }
+
+        // Map entries and groups are written as part of the field that uses them
+        let inlined_of = |field: &FieldDescriptorProto| {
+            let index = message.nested_type.iter().position(|nested| {
+                field.type_name() == format!(".{full_name}.{}", nested.name())
+            })?;
+            let nested = &message.nested_type[index];
+            (is_map_entry(nested) || field.r#type() == Type::Group)
+                .then(|| (index, nested, child_path(path, MESSAGE_NESTED_TYPE, index)))
+        };
+        let mut inlined = HashSet::new();
+        let mut oneofs_done = HashSet::new();
+        for (index, field) in message.field.iter().enumerate() {
+            let field_path = child_path(path, MESSAGE_FIELD, index);
+            let oneof = field.oneof_index.filter(|_| !field.proto3_optional());
+            match oneof {
+                Some(oneof) => {
+                    if !oneofs_done.insert(oneof) {
+                        continue;
+                    }
+                    let decl = &message.oneof_decl[oneof as usize];
+                    self.comments(&child_path(path, MESSAGE_ONEOF_DECL, oneof as usize));
+                    self.line(&format!("oneof {} {{", decl.name()));
+                    self.depth += 1;
+                    for (index, field) in message.field.iter().enumerate() {
+                        if field.oneof_index == Some(oneof) {
+                            let field_path = child_path(path, MESSAGE_FIELD, index);
+                            let nested = inlined_of(field);
+                            inlined.extend(nested.as_ref().map(|(index, ..)| *index));
+                            self.field(field, nested, true, &field_path);
+                        }
+                    }
+                    self.depth -= 1;
+                    self.line("}");
+                }
+                None => {
+                    let nested = inlined_of(field);
+                    inlined.extend(nested.as_ref().map(|(index, ..)| *index));
+                    self.field(field, nested, false, &field_path);
+                }
+            }
+        }
+
+        for (index, nested) in message.nested_type.iter().enumerate() {
+            if !inlined.contains(&index) {
+                self.message(
+                    nested,
+                    &format!("{full_name}."),
+                    &child_path(path, MESSAGE_NESTED_TYPE, index),
+                );
+            }
+        }
+        for (index, enum_desc) in message.enum_type.iter().enumerate() {
+            self.enumeration(enum_desc, &child_path(path, MESSAGE_ENUM_TYPE, index));
+        }
+        self.extensions(&message.extension, path, MESSAGE_EXTENSION);
+
+        if !message.extension_range.is_empty() {
+            let ranges: Vec<String> = message
+                .extension_range
+                .iter()
+                .map(|range| field_range(range.start(), range.end()))
+                .collect();
+            self.line(&format!("extensions {};", ranges.join(", ")));
+        }
+        if !message.reserved_range.is_empty() {
+            let ranges: Vec<String> = message
+                .reserved_range
+                .iter()
+                .map(|range| field_range(range.start(), range.end()))
+                .collect();
+            self.line(&format!("reserved {};", ranges.join(", ")));
+        }
+        if !message.reserved_name.is_empty() {
+            let names: Vec<String> = message
+                .reserved_name
+                .iter()
+                .map(|name| quote(name))
+                .collect();
+            self.line(&format!("reserved {};", names.join(", ")));
+        }
+    }
+
+    // `nested` is the map entry or group that the field uses, if any, with its index and path
+    fn field(
+        &mut self,
+        field: &FieldDescriptorProto,
+        nested: Option<(usize, &DescriptorProto, Vec<i32>)>,
+        in_oneof: bool,
+        path: &[i32],
+    ) {
+        self.comments(path);
+        let label = if in_oneof { "" } else { self.label(field) };
+        let options = field_options(field);
+        let options = if options.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", options.join(", "))
+        };
+
+        match nested {
+            Some((_, entry, _)) if is_map_entry(entry) => {
+                let type_of = |number| {
+                    entry
+                        .field
+                        .iter()
+                        .find(|field| field.number() == number)
+                        .map(type_name)
+                        .unwrap_or_default()
+                };
+                self.line_with_trailing(
+                    &format!(
+                        "map<{}, {}> {} = {}{options};",
+                        type_of(1),
+                        type_of(2),
+                        field.name(),
+                        field.number()
+                    ),
+                    path,
+                );
+            }
+            Some((_, group, group_path)) if field.r#type() == Type::Group => {
+                self.line(&format!(
+                    "{label}group {} = {}{options} {{",
+                    group.name(),
+                    field.number()
+                ));
+                self.depth += 1;
+                let full_name = field.type_name().trim_start_matches('.').to_string();
+                self.message_body(group, &full_name, &group_path);
+                self.depth -= 1;
+                self.line("}");
+            }
+            _ => {
+                self.line_with_trailing(
+                    &format!(
+                        "{label}{} {} = {}{options};",
+                        type_name(field),
+                        field.name(),
+                        field.number()
+                    ),
+                    path,
+                );
+            }
+        }
+    }
+
+    fn label(&self, field: &FieldDescriptorProto) -> &'static str {
+        match field.label() {
+            Label::Repeated => "repeated ",
+            Label::Required => "required ",
+            Label::Optional if !self.is_proto3 || field.proto3_optional() => "optional ",
+            Label::Optional => "",
+        }
+    }
+
+    // Extensions are grouped by what they extend. `field_number` is that of the extensions in
+    // the parent at `path`
+    fn extensions(&mut self, extensions: &[FieldDescriptorProto], path: &[i32], field_number: i32) {
+        let mut by_extendee: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (index, extension) in extensions.iter().enumerate() {
+            by_extendee
+                .entry(extension.extendee())
+                .or_default()
+                .push(index);
+        }
+        for (extendee, indices) in by_extendee {
+            self.blank();
+            self.line(&format!("extend {extendee} {{"));
+            self.depth += 1;
+            for index in indices {
+                let extension_path = child_path(path, field_number, index);
+                self.field(&extensions[index], None, false, &extension_path);
+            }
+            self.depth -= 1;
+            self.line("}");
+        }
+    }
+
+    fn enumeration(&mut self, enum_desc: &EnumDescriptorProto, path: &[i32]) {
+        self.comments(path);
+        self.line(&format!("enum {} {{", enum_desc.name()));
+        self.depth += 1;
+        if let Some(options) = &enum_desc.options {
+            let mut pairs = Vec::new();
+            push_flag(&mut pairs, "allow_alias", options.allow_alias);
+            push_flag(&mut pairs, "deprecated", options.deprecated);
+            for option in pairs {
+                self.line(&format!("option {option};"));
+            }
+        }
+        for (index, value) in enum_desc.value.iter().enumerate() {
+            let value_path = child_path(path, ENUM_VALUE, index);
+            self.comments(&value_path);
+            let mut options = Vec::new();
+            if let Some(value_options) = &value.options {
+                push_flag(&mut options, "deprecated", value_options.deprecated);
+            }
+            let options = if options.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", options.join(", "))
+            };
+            self.line_with_trailing(
+                &format!("{} = {}{options};", value.name(), value.number()),
+                &value_path,
+            );
+        }
+        // Unlike those of messages, these ranges include their end
+        if !enum_desc.reserved_range.is_empty() {
+            let ranges: Vec<String> = enum_desc
+                .reserved_range
+                .iter()
+                .map(|range| match (range.start(), range.end()) {
+                    (start, end) if start == end => start.to_string(),
+                    (start, i32::MAX) => format!("{start} to max"),
+                    (start, end) => format!("{start} to {end}"),
+                })
+                .collect();
+            self.line(&format!("reserved {};", ranges.join(", ")));
+        }
+        if !enum_desc.reserved_name.is_empty() {
+            let names: Vec<String> = enum_desc
+                .reserved_name
+                .iter()
+                .map(|name| quote(name))
+                .collect();
+            self.line(&format!("reserved {};", names.join(", ")));
+        }
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn service(&mut self, service: &ServiceDescriptorProto, path: &[i32]) {
+        self.comments(path);
+        self.line(&format!("service {} {{", service.name()));
+        self.depth += 1;
+        if let Some(options) = &service.options {
+            let mut pairs = Vec::new();
+            push_flag(&mut pairs, "deprecated", options.deprecated);
+            for option in pairs {
+                self.line(&format!("option {option};"));
+            }
+        }
+        for (index, method) in service.method.iter().enumerate() {
+            let method_path = child_path(path, SERVICE_METHOD, index);
+            self.comments(&method_path);
+            let stream = |is_streaming: bool| if is_streaming { "stream " } else { "" };
+            let signature = format!(
+                "rpc {}({}{}) returns ({}{})",
+                method.name(),
+                stream(method.client_streaming()),
+                method.input_type(),
+                stream(method.server_streaming()),
+                method.output_type()
+            );
+
+            let mut options = Vec::new();
+            if let Some(method_options) = &method.options {
+                push_flag(&mut options, "deprecated", method_options.deprecated);
+                if method_options.idempotency_level.is_some() {
+                    options.push(format!(
+                        "idempotency_level = {}",
+                        method_options.idempotency_level().as_str_name()
+                    ));
+                }
+            }
+            if options.is_empty() {
+                self.line_with_trailing(&format!("{signature};"), &method_path);
+            } else {
+                self.line(&format!("{signature} {{"));
+                self.depth += 1;
+                for option in options {
+                    self.line(&format!("option {option};"));
+                }
+                self.depth -= 1;
+                self.line("}");
+            }
+        }
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn comments(&mut self, path: &[i32]) {
+        let comments = self
+            .locations
+            .get(path)
+            .and_then(|location| location.leading_comments.clone());
+        if let Some(comments) = comments {
+            for line in comments.trim_end_matches('\n').split('\n') {
+                self.line(&format!("//{line}"));
+            }
+        }
+    }
+
+    // Writes `text` followed by the trailing comment of the definition at `path`
+    fn line_with_trailing(&mut self, text: &str, path: &[i32]) {
+        let trailing = self
+            .locations
+            .get(path)
+            .and_then(|location| location.trailing_comments.clone());
+        match trailing
+            .as_deref()
+            .map(|comment| comment.trim_end_matches('\n'))
+        {
+            Some(comment) if !comment.contains('\n') => {
+                self.line(&format!("{text} //{comment}"));
+            }
+            Some(comment) => {
+                self.line(text);
+                for line in comment.split('\n') {
+                    self.line(&format!("//{line}"));
+                }
+            }
+            None => self.line(text),
+        }
+    }
+
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str("  ");
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn blank(&mut self) {
+        self.out.push('\n');
+    }
+}
+
+fn file_options(options: &prost_reflect::prost_types::FileOptions) -> Vec<String> {
+    let mut pairs = Vec::new();
+    let strings = [
+        ("java_package", &options.java_package),
+        ("java_outer_classname", &options.java_outer_classname),
+        ("go_package", &options.go_package),
+        ("objc_class_prefix", &options.objc_class_prefix),
+        ("csharp_namespace", &options.csharp_namespace),
+        ("swift_prefix", &options.swift_prefix),
+        ("php_class_prefix", &options.php_class_prefix),
+        ("php_namespace", &options.php_namespace),
+        ("php_metadata_namespace", &options.php_metadata_namespace),
+        ("ruby_package", &options.ruby_package),
+    ];
+    for (name, value) in strings {
+        if let Some(value) = value {
+            pairs.push(format!("{name} = {}", quote(value)));
+        }
+    }
+    push_flag(
+        &mut pairs,
+        "java_multiple_files",
+        options.java_multiple_files,
+    );
+    push_flag(
+        &mut pairs,
+        "java_string_check_utf8",
+        options.java_string_check_utf8,
+    );
+    push_flag(
+        &mut pairs,
+        "cc_generic_services",
+        options.cc_generic_services,
+    );
+    push_flag(
+        &mut pairs,
+        "java_generic_services",
+        options.java_generic_services,
+    );
+    push_flag(
+        &mut pairs,
+        "py_generic_services",
+        options.py_generic_services,
+    );
+    push_flag(
+        &mut pairs,
+        "php_generic_services",
+        options.php_generic_services,
+    );
+    push_flag(&mut pairs, "cc_enable_arenas", options.cc_enable_arenas);
+    push_flag(&mut pairs, "deprecated", options.deprecated);
+    if options.optimize_for.is_some() {
+        pairs.push(format!(
+            "optimize_for = {}",
+            options.optimize_for().as_str_name()
+        ));
+    }
+    pairs
+}
+
+fn field_options(field: &FieldDescriptorProto) -> Vec<String> {
+    let mut pairs = Vec::new();
+    if let Some(default) = &field.default_value {
+        let default = match field.r#type() {
+            Type::String => quote(default),
+            // Already escaped by the compiler
+            Type::Bytes => format!("\"{default}\""),
+            _ => default.clone(),
+        };
+        pairs.push(format!("default = {default}"));
+    }
+    // Compilers always fill it in, so it's only written when it isn't the default one
+    if field.json_name.is_some() && field.json_name() != default_json_name(field.name()) {
+        pairs.push(format!("json_name = {}", quote(field.json_name())));
+    }
+    if let Some(options) = &field.options {
+        push_flag(&mut pairs, "packed", options.packed);
+        push_flag(&mut pairs, "lazy", options.lazy);
+        push_flag(&mut pairs, "deprecated", options.deprecated);
+    }
+    pairs
+}
+
+fn push_flag(pairs: &mut Vec<String>, name: &str, value: Option<bool>) {
+    if let Some(value) = value {
+        pairs.push(format!("{name} = {value}"));
+    }
+}
+
+fn type_name(field: &FieldDescriptorProto) -> String {
+    match field.r#type() {
+        Type::Double => "double",
+        Type::Float => "float",
+        Type::Int64 => "int64",
+        Type::Uint64 => "uint64",
+        Type::Int32 => "int32",
+        Type::Fixed64 => "fixed64",
+        Type::Fixed32 => "fixed32",
+        Type::Bool => "bool",
+        Type::String => "string",
+        Type::Bytes => "bytes",
+        Type::Uint32 => "uint32",
+        Type::Sfixed32 => "sfixed32",
+        Type::Sfixed64 => "sfixed64",
+        Type::Sint32 => "sint32",
+        Type::Sint64 => "sint64",
+        Type::Group | Type::Message | Type::Enum => field.type_name(),
+    }
+    .to_string()
+}
+
+fn is_map_entry(message: &DescriptorProto) -> bool {
+    message
+        .options
+        .as_ref()
+        .map_or(false, |options| options.map_entry())
+}
+
+// Message ranges exclude their end
+fn field_range(start: i32, end: i32) -> String {
+    match end {
+        MAX_FIELD_END => format!("{start} to max"),
+        end if end == start + 1 => start.to_string(),
+        end => format!("{start} to {}", end - 1),
+    }
+}
+
+// What compilers set `json_name` to when the file doesn't
+fn default_json_name(name: &str) -> String {
+    let mut json_name = String::new();
+    let mut is_upper_next = false;
+    for c in name.chars() {
+        if c == '_' {
+            is_upper_next = true;
+        } else if is_upper_next {
+            json_name.push(c.to_ascii_uppercase());
+            is_upper_next = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn package_prefix(file: &FileDescriptorProto) -> String {
+    match file.package() {
+        "" => String::new(),
+        package => format!("{package}."),
+    }
+}
+
+fn child_path(path: &[i32], field_number: i32, index: usize) -> Vec<i32> {
+    let mut child = path.to_vec();
+    child.extend([field_number, index as i32]);
+    child
+}
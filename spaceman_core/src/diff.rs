@@ -0,0 +1,117 @@
+//! Field by field comparison of messages in their JSON mapping.
+
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+use spaceman_types::diff::Difference;
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Compares two messages, or lists of messages, field by field.
+///
+/// Values in `ignore` are paths of fields to leave out, written like `metadata.updateTime` or
+/// `items[0].id`. A `*` segment matches any single field, key or index, like in `items[*].id`,
+/// while `**` matches any number of them, so `**.updateTime` ignores the field wherever it is.
+pub fn diff(left: &Value, right: &Value, ignore: &[String]) -> Vec<Difference> {
+    let ignore: Vec<Vec<String>> = ignore.iter().map(|path| parse_pattern(path)).collect();
+    let mut differences = Vec::new();
+    walk(
+        &mut Vec::new(),
+        Some(left),
+        Some(right),
+        &ignore,
+        &mut differences,
+    );
+    differences
+}
+
+fn walk(
+    path: &mut Vec<Segment>,
+    left: Option<&Value>,
+    right: Option<&Value>,
+    ignore: &[Vec<String>],
+    differences: &mut Vec<Difference>,
+) {
+    if ignore.iter().any(|pattern| matches(pattern, path)) {
+        return;
+    }
+    match (left, right) {
+        (Some(Value::Object(left)), Some(Value::Object(right))) => {
+            let keys: BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+            for key in keys {
+                path.push(Segment::Key(key.clone()));
+                walk(path, left.get(key), right.get(key), ignore, differences);
+                path.pop();
+            }
+        }
+        (Some(Value::Array(left)), Some(Value::Array(right))) => {
+            for index in 0..left.len().max(right.len()) {
+                path.push(Segment::Index(index));
+                walk(path, left.get(index), right.get(index), ignore, differences);
+                path.pop();
+            }
+        }
+        (left, right) if left == right => (),
+        (left, right) => differences.push(Difference {
+            path: format_path(path),
+            left: left.map(Value::to_string),
+            right: right.map(Value::to_string),
+        }),
+    }
+}
+
+/// Splits a path into segments, treating `a[0]` like `a.0`.
+fn parse_pattern(pattern: &str) -> Vec<String> {
+    pattern
+        .replace('[', ".")
+        .replace(']', "")
+        .split('.')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.trim_matches('"').to_string())
+        .collect()
+}
+
+fn matches(pattern: &[String], path: &[Segment]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (None, None) => true,
+        (Some((first, rest)), _) if first == "**" => {
+            matches(rest, path) || (!path.is_empty() && matches(pattern, &path[1..]))
+        }
+        (Some((first, rest)), Some((segment, path))) => {
+            let matches_segment = match segment {
+                Segment::Key(key) => first == "*" || first == key,
+                Segment::Index(index) => first == "*" || *first == index.to_string(),
+            };
+            matches_segment && matches(rest, path)
+        }
+        _ => false,
+    }
+}
+
+fn format_path(path: &[Segment]) -> String {
+    let mut formatted = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) if is_identifier(key) => {
+                if !formatted.is_empty() {
+                    formatted.push('.');
+                }
+                formatted.push_str(key);
+            }
+            Segment::Key(key) => formatted.push_str(&format!("[{:?}]", key)),
+            Segment::Index(index) => formatted.push_str(&format!("[{}]", index)),
+        }
+    }
+    formatted
+}
+
+fn is_identifier(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -0,0 +1,320 @@
+//! Documentation of the definitions in a `DescriptorPool`, gathered from their comments and
+//! options.
+
+use std::collections::HashMap;
+
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, EnumDescriptor, FieldDescriptor, Kind, MessageDescriptor,
+    MethodDescriptor, ReflectMessage, Value,
+};
+
+use spaceman_types::docs::{
+    DefinitionDocs, Docs, EnumDocs, EnumValueDocs, FieldDocs, MessageDocs, MethodDocs, OptionDocs,
+};
+
+use crate::source_info::{self, SourceInfo};
+
+// Field numbers from descriptor.proto
+const SET_FILE: u32 = 1;
+const FILE_NAME: u32 = 1;
+const MESSAGE_OPTIONS: u32 = 7;
+const FIELD_OPTIONS: u32 = 8;
+const ENUM_OPTIONS: u32 = 3;
+const ENUM_VALUE_OPTIONS: u32 = 3;
+const METHOD_OPTIONS: u32 = 4;
+
+/// File descriptors decoded as dynamic messages, keyed by file name.
+///
+/// `prost_types` drops unknown fields, and with them every custom option, so the raw descriptor
+/// sets are decoded again through the pool, which knows about the extensions that define them.
+#[derive(Clone)]
+pub(crate) struct OptionsIndex {
+    files: HashMap<String, DynamicMessage>,
+}
+
+impl OptionsIndex {
+    pub(crate) fn new(pool: &DescriptorPool, raw_descriptor_sets: &[&[u8]]) -> Self {
+        let mut files = HashMap::new();
+        // A file can only declare custom options by importing descriptor.proto, so without it
+        // there's nothing to look for
+        let set_desc = match pool.get_message_by_name("google.protobuf.FileDescriptorSet") {
+            Some(set_desc) => set_desc,
+            None => return OptionsIndex { files },
+        };
+        for raw in raw_descriptor_sets {
+            let set = match DynamicMessage::decode(set_desc.clone(), *raw) {
+                Ok(set) => set,
+                Err(_) => continue,
+            };
+            let set_files = set
+                .get_field_by_number(SET_FILE)
+                .and_then(|value| value.as_list().map(<[Value]>::to_vec))
+                .unwrap_or_default();
+            for file in set_files {
+                if let Value::Message(file) = file {
+                    let name = file
+                        .get_field_by_number(FILE_NAME)
+                        .and_then(|name| name.as_str().map(str::to_string))
+                        .unwrap_or_default();
+                    files.insert(name, file);
+                }
+            }
+        }
+        OptionsIndex { files }
+    }
+
+    /// The file called `name`, custom options included.
+    pub(crate) fn file(&self, name: &str) -> Option<&DynamicMessage> {
+        self.files.get(name)
+    }
+
+    /// Options of `field`, custom ones included.
+    pub(crate) fn field_options(&self, field: &FieldDescriptor) -> Option<DynamicMessage> {
+        self.options_message(
+            field.parent_message().parent_file().name(),
+            source_info::field_path(field),
+            FIELD_OPTIONS,
+        )
+    }
+
+    /// Custom options of the definition at `path`, whose options are in field `options_field`.
+    fn options(&self, file: &str, path: Option<Vec<i32>>, options_field: u32) -> Vec<OptionDocs> {
+        let options = match self.options_message(file, path, options_field) {
+            Some(options) => options,
+            None => return Vec::new(),
+        };
+        options
+            .descriptor()
+            .extensions()
+            .filter(|extension| options.has_extension(extension))
+            .map(|extension| OptionDocs {
+                name: extension.full_name().to_string(),
+                value: format_value(&options.get_extension(&extension), &extension.kind()),
+            })
+            .collect()
+    }
+
+    fn options_message(
+        &self,
+        file: &str,
+        path: Option<Vec<i32>>,
+        options_field: u32,
+    ) -> Option<DynamicMessage> {
+        let mut message = self.files.get(file)?.clone();
+        for step in path?.chunks(2) {
+            message = message
+                .get_field_by_number(step[0] as u32)
+                .and_then(|list| list.as_list()?.get(step[1] as usize)?.as_message().cloned())?;
+        }
+        message
+            .get_field_by_number(options_field)
+            .and_then(|options| options.as_message().cloned())
+    }
+}
+
+pub(crate) fn method_docs(method: &MethodDescriptor, index: &OptionsIndex) -> Docs {
+    let deprecated = method
+        .method_descriptor_proto()
+        .options
+        .as_ref()
+        .map_or(false, |options| options.deprecated());
+    let file = method.parent_service().parent_file();
+    let options = index.options(
+        file.name(),
+        source_info::method_path(method),
+        METHOD_OPTIONS,
+    );
+    Docs::Method(MethodDocs {
+        name: method.name().to_string(),
+        full_name: method.full_name().to_string(),
+        definition: definition(source_info::method_info(method), deprecated, options),
+        input: method.input().full_name().to_string(),
+        output: method.output().full_name().to_string(),
+        is_client_streaming: method.is_client_streaming(),
+        is_server_streaming: method.is_server_streaming(),
+    })
+}
+
+pub(crate) fn message_docs(desc: &MessageDescriptor, index: &OptionsIndex) -> Docs {
+    let deprecated = desc
+        .descriptor_proto()
+        .options
+        .as_ref()
+        .map_or(false, |options| options.deprecated());
+    let options = index.options(
+        desc.parent_file().name(),
+        source_info::message_path(desc),
+        MESSAGE_OPTIONS,
+    );
+    Docs::Message(MessageDocs {
+        name: desc.name().to_string(),
+        full_name: desc.full_name().to_string(),
+        definition: definition(source_info::message_info(desc), deprecated, options),
+        fields: desc
+            .fields()
+            .map(|field| field_docs(&field, index))
+            .collect(),
+    })
+}
+
+pub(crate) fn enum_docs(desc: &EnumDescriptor, index: &OptionsIndex) -> Docs {
+    let deprecated = desc
+        .enum_descriptor_proto()
+        .options
+        .as_ref()
+        .map_or(false, |options| options.deprecated());
+    let file = desc.parent_file();
+    let options = index.options(file.name(), source_info::enum_path(desc), ENUM_OPTIONS);
+    Docs::Enum(EnumDocs {
+        name: desc.name().to_string(),
+        full_name: desc.full_name().to_string(),
+        definition: definition(source_info::enum_info(desc), deprecated, options),
+        values: desc
+            .values()
+            .map(|value| {
+                let deprecated = value
+                    .enum_value_descriptor_proto()
+                    .options
+                    .as_ref()
+                    .map_or(false, |options| options.deprecated());
+                let options = index.options(
+                    file.name(),
+                    source_info::enum_value_path(&value),
+                    ENUM_VALUE_OPTIONS,
+                );
+                EnumValueDocs {
+                    name: value.name().to_string(),
+                    number: value.number(),
+                    definition: definition(
+                        source_info::enum_value_info(&value),
+                        deprecated,
+                        options,
+                    ),
+                }
+            })
+            .collect(),
+    })
+}
+
+fn field_docs(field: &FieldDescriptor, index: &OptionsIndex) -> FieldDocs {
+    let deprecated = field
+        .field_descriptor_proto()
+        .options
+        .as_ref()
+        .map_or(false, |options| options.deprecated());
+    let options = index.options(
+        field.parent_message().parent_file().name(),
+        source_info::field_path(field),
+        FIELD_OPTIONS,
+    );
+
+    let (mut type_name, type_ref) = if field.is_map() {
+        let entry = match field.kind() {
+            Kind::Message(entry) => entry,
+            _ => unreachable!("map fields are always messages"),
+        };
+        let key = entry.map_entry_key_field().kind();
+        let value = entry.map_entry_value_field().kind();
+        (
+            format!("map<{}, {}>", kind_name(&key), kind_name(&value)),
+            kind_ref(&value),
+        )
+    } else if field.is_list() {
+        let kind = field.kind();
+        (format!("repeated {}", kind_name(&kind)), kind_ref(&kind))
+    } else {
+        let kind = field.kind();
+        (kind_name(&kind), kind_ref(&kind))
+    };
+
+    let is_synthetic = field.field_descriptor_proto().proto3_optional();
+    if is_synthetic {
+        type_name = format!("optional {}", type_name);
+    }
+
+    FieldDocs {
+        name: field.name().to_string(),
+        number: field.number(),
+        type_name,
+        type_ref,
+        oneof: field
+            .containing_oneof()
+            .filter(|_| !is_synthetic)
+            .map(|oneof| oneof.name().to_string()),
+        definition: definition(source_info::field_info(field), deprecated, options),
+    }
+}
+
+fn definition(info: SourceInfo, deprecated: bool, options: Vec<OptionDocs>) -> DefinitionDocs {
+    DefinitionDocs {
+        comments: info.comments(),
+        file: info.file,
+        // Source info lines start at zero, editors start at one
+        line: info.line.map(|line| line + 1),
+        deprecated,
+        options,
+    }
+}
+
+fn kind_name(kind: &Kind) -> String {
+    match kind {
+        Kind::Double => "double".to_string(),
+        Kind::Float => "float".to_string(),
+        Kind::Int32 => "int32".to_string(),
+        Kind::Int64 => "int64".to_string(),
+        Kind::Uint32 => "uint32".to_string(),
+        Kind::Uint64 => "uint64".to_string(),
+        Kind::Sint32 => "sint32".to_string(),
+        Kind::Sint64 => "sint64".to_string(),
+        Kind::Fixed32 => "fixed32".to_string(),
+        Kind::Fixed64 => "fixed64".to_string(),
+        Kind::Sfixed32 => "sfixed32".to_string(),
+        Kind::Sfixed64 => "sfixed64".to_string(),
+        Kind::Bool => "bool".to_string(),
+        Kind::String => "string".to_string(),
+        Kind::Bytes => "bytes".to_string(),
+        Kind::Message(desc) => desc.full_name().to_string(),
+        Kind::Enum(desc) => desc.full_name().to_string(),
+    }
+}
+
+fn kind_ref(kind: &Kind) -> Option<String> {
+    match kind {
+        Kind::Message(desc) => Some(desc.full_name().to_string()),
+        Kind::Enum(desc) => Some(desc.full_name().to_string()),
+        _ => None,
+    }
+}
+
+/// Formats the value of an option roughly like it would be written in a .proto file.
+fn format_value(value: &Value, kind: &Kind) -> String {
+    match value {
+        Value::String(string) => format!("{:?}", string),
+        Value::Bytes(bytes) => format!("\"{}\"", base64::encode(bytes)),
+        Value::EnumNumber(number) => match kind {
+            Kind::Enum(desc) => desc
+                .get_value(*number)
+                .map(|value| value.name().to_string())
+                .unwrap_or_else(|| number.to_string()),
+            _ => number.to_string(),
+        },
+        Value::Message(message) => serde_json::to_string(message).unwrap_or_default(),
+        Value::List(values) => format!(
+            "[{}]",
+            values
+                .iter()
+                .map(|value| format_value(value, kind))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Bool(value) => value.to_string(),
+        Value::I32(value) => value.to_string(),
+        Value::I64(value) => value.to_string(),
+        Value::U32(value) => value.to_string(),
+        Value::U64(value) => value.to_string(),
+        Value::F32(value) => value.to_string(),
+        Value::F64(value) => value.to_string(),
+        // Extensions can't be maps
+        Value::Map(_) => String::new(),
+    }
+}
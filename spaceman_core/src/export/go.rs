@@ -0,0 +1,514 @@
+//! Client snippets using the code that `protoc-gen-go` and `protoc-gen-go-grpc` generate.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use prost_reflect::{
+    DynamicMessage, EnumDescriptor, FieldDescriptor, FileDescriptor, Kind, MapKey, ReflectMessage,
+    Value,
+};
+
+use super::{first_address, Call};
+
+/// Keeps track of the imports the snippet needs.
+#[derive(Default)]
+struct Imports {
+    std: BTreeSet<&'static str>,
+    other: BTreeSet<&'static str>,
+    /// Import path of each generated package, with the name it's imported as.
+    packages: BTreeMap<String, String>,
+}
+
+impl Imports {
+    /// Name that the package generated for `file` is imported as.
+    fn package(&mut self, file: &FileDescriptor) -> String {
+        let (path, name) = go_package(file);
+        if let Some(alias) = self.packages.get(&path) {
+            return alias.clone();
+        }
+        let mut alias = name.clone();
+        let mut n = 1;
+        while self.packages.values().any(|taken| *taken == alias) {
+            n += 1;
+            alias = format!("{}{}", name, n);
+        }
+        self.packages.insert(path, alias.clone());
+        alias
+    }
+
+    fn render(&self) -> Vec<String> {
+        let mut lines = vec!["import (".to_string()];
+        lines.extend(self.std.iter().map(|path| format!("\t{:?}", path)));
+        lines.push(String::new());
+        lines.extend(self.other.iter().map(|path| format!("\t{:?}", path)));
+        if !self.packages.is_empty() {
+            lines.push(String::new());
+            lines.extend(
+                self.packages
+                    .iter()
+                    .map(|(path, alias)| format!("\t{} {:?}", alias, path)),
+            );
+        }
+        lines.push(")".to_string());
+        lines
+    }
+}
+
+pub(super) fn snippet(call: &Call) -> String {
+    let method = call.method;
+    let service = method.parent_service();
+    let mut imports = Imports::default();
+    imports.std.extend(["context", "log"]);
+    imports.other.extend([
+        "google.golang.org/grpc",
+        "google.golang.org/protobuf/encoding/protojson",
+    ]);
+
+    let mut body = Vec::new();
+    match &call.endpoint.tls {
+        None => {
+            imports
+                .other
+                .insert("google.golang.org/grpc/credentials/insecure");
+            body.push("\tcreds := insecure.NewCredentials()".to_string());
+        }
+        Some(tls) if tls.no_check => {
+            imports.std.insert("crypto/tls");
+            imports.other.insert("google.golang.org/grpc/credentials");
+            body.push(
+                "\tcreds := credentials.NewTLS(&tls.Config{InsecureSkipVerify: true})".to_string(),
+            );
+        }
+        Some(tls) => {
+            imports.other.insert("google.golang.org/grpc/credentials");
+            match &tls.ca_cert {
+                Some(ca_cert) => {
+                    body.push(format!(
+                        "\tcreds, err := credentials.NewClientTLSFromFile({}, \"\")",
+                        go_quote(ca_cert.as_bytes())
+                    ));
+                    body.extend(check_err(1));
+                }
+                None => {
+                    body.push("\tcreds := credentials.NewClientTLSFromCert(nil, \"\")".to_string())
+                }
+            }
+        }
+    }
+    body.push(format!(
+        "\tconn, err := grpc.Dial({}, grpc.WithTransportCredentials(creds))",
+        go_quote(first_address(&call.endpoint.authority).as_bytes())
+    ));
+    body.extend(check_err(1));
+    body.push("\tdefer conn.Close()".to_string());
+    body.push(String::new());
+
+    let package = imports.package(&service.parent_file());
+    body.push(format!(
+        "\tclient := {}.New{}Client(conn)",
+        package,
+        go_camel_case(service.name())
+    ));
+    body.push("\tctx := context.Background()".to_string());
+    if !call.metadata.is_empty() {
+        imports.other.insert("google.golang.org/grpc/metadata");
+        let pairs: Vec<String> = call
+            .metadata
+            .iter()
+            .map(|(key, value)| {
+                // gRPC encodes the values of binary keys itself
+                let value = match key.ends_with("-bin") {
+                    true => base64::decode(value).unwrap_or_else(|_| value.as_bytes().to_vec()),
+                    false => value.as_bytes().to_vec(),
+                };
+                format!("{}, {}", go_quote(key.as_bytes()), go_quote(&value))
+            })
+            .collect();
+        body.push(format!(
+            "\tctx = metadata.AppendToOutgoingContext(ctx, {})",
+            pairs.join(", ")
+        ));
+    }
+    body.push(String::new());
+
+    let request = message(call.body, 1, &mut imports);
+    body.push(format!("\trequest := {}", request));
+
+    let name = go_camel_case(method.name());
+    if method.is_client_streaming() {
+        body.push(format!("\tstream, err := client.{}(ctx)", name));
+        body.extend(check_err(1));
+        body.push("\tif err := stream.Send(request); err != nil {".to_string());
+        body.push("\t\tlog.Fatal(err)".to_string());
+        body.push("\t}".to_string());
+        if !method.is_server_streaming() {
+            body.push("\tresponse, err := stream.CloseAndRecv()".to_string());
+        } else {
+            body.push("\tif err := stream.CloseSend(); err != nil {".to_string());
+            body.push("\t\tlog.Fatal(err)".to_string());
+            body.push("\t}".to_string());
+        }
+    } else if method.is_server_streaming() {
+        body.push(format!("\tstream, err := client.{}(ctx, request)", name));
+        body.extend(check_err(1));
+    } else {
+        body.push(format!("\tresponse, err := client.{}(ctx, request)", name));
+    }
+    if method.is_server_streaming() {
+        imports.std.insert("io");
+        body.push("\tfor {".to_string());
+        body.push("\t\tresponse, err := stream.Recv()".to_string());
+        body.push("\t\tif err == io.EOF {".to_string());
+        body.push("\t\t\tbreak".to_string());
+        body.push("\t\t}".to_string());
+        body.extend(check_err(2));
+        body.push("\t\tlog.Println(protojson.Format(response))".to_string());
+        body.push("\t}".to_string());
+    } else {
+        body.extend(check_err(1));
+        body.push("\tlog.Println(protojson.Format(response))".to_string());
+    }
+
+    let mut lines = vec!["package main".to_string(), String::new()];
+    lines.extend(imports.render());
+    lines.push(String::new());
+    lines.push("func main() {".to_string());
+    lines.extend(body);
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+fn check_err(indent: usize) -> Vec<String> {
+    let pad = "\t".repeat(indent);
+    vec![
+        format!("{}if err != nil {{", pad),
+        format!("{}\tlog.Fatal(err)", pad),
+        format!("{}}}", pad),
+    ]
+}
+
+/// A composite literal for `msg`, leaving out unset fields.
+fn message(msg: &DynamicMessage, indent: usize, imports: &mut Imports) -> String {
+    let desc = msg.descriptor();
+    let package = imports.package(&desc.parent_file());
+    let name = type_name(desc.full_name(), desc.parent_file().package_name());
+
+    let pad = "\t".repeat(indent + 1);
+    let mut members = Vec::new();
+    for field in desc.fields() {
+        if !msg.has_field(&field) {
+            continue;
+        }
+        let value = msg.get_field(&field);
+        match real_oneof(&field) {
+            Some(oneof) => members.push(format!(
+                "{}{}: &{}.{}_{}{{{}: {}}},",
+                pad,
+                go_camel_case(&oneof),
+                package,
+                name,
+                go_camel_case(field.name()),
+                go_camel_case(field.name()),
+                single(&field.kind(), &value, indent + 1, imports)
+            )),
+            None => members.push(format!(
+                "{}{}: {},",
+                pad,
+                go_camel_case(field.name()),
+                field_value(&field, &value, indent + 1, imports)
+            )),
+        }
+    }
+
+    if members.is_empty() {
+        format!("&{}.{}{{}}", package, name)
+    } else {
+        format!(
+            "&{}.{}{{\n{}\n{}}}",
+            package,
+            name,
+            members.join("\n"),
+            "\t".repeat(indent)
+        )
+    }
+}
+
+fn field_value(
+    field: &FieldDescriptor,
+    value: &Value,
+    indent: usize,
+    imports: &mut Imports,
+) -> String {
+    if let (true, Kind::Message(entry)) = (field.is_map(), field.kind()) {
+        let (key_field, value_field) = (entry.map_entry_key_field(), entry.map_entry_value_field());
+        let pad = "\t".repeat(indent + 1);
+        let mut entries = Vec::new();
+        for (key, value) in value.as_map().into_iter().flatten() {
+            entries.push(format!(
+                "{}{}: {},",
+                pad,
+                map_key(key),
+                single(&value_field.kind(), value, indent + 1, imports)
+            ));
+        }
+        entries.sort();
+        format!(
+            "map[{}]{}{{\n{}\n{}}}",
+            go_type(&key_field.kind(), imports),
+            go_type(&value_field.kind(), imports),
+            entries.join("\n"),
+            "\t".repeat(indent)
+        )
+    } else if field.is_list() {
+        let pad = "\t".repeat(indent + 1);
+        let mut items = Vec::new();
+        for item in value.as_list().into_iter().flatten() {
+            items.push(format!(
+                "{}{},",
+                pad,
+                single(&field.kind(), item, indent + 1, imports)
+            ));
+        }
+        format!(
+            "[]{}{{\n{}\n{}}}",
+            go_type(&field.kind(), imports),
+            items.join("\n"),
+            "\t".repeat(indent)
+        )
+    } else if field.supports_presence() && !matches!(field.kind(), Kind::Message(_) | Kind::Bytes) {
+        // Optional scalars are pointers
+        let value = single(&field.kind(), value, indent, imports);
+        match field.kind() {
+            Kind::Enum(_) => format!("{}.Enum()", value),
+            kind => {
+                imports.other.insert("google.golang.org/protobuf/proto");
+                format!("proto.{}({})", pointer_helper(&kind), value)
+            }
+        }
+    } else {
+        single(&field.kind(), value, indent, imports)
+    }
+}
+
+fn single(kind: &Kind, value: &Value, indent: usize, imports: &mut Imports) -> String {
+    match value {
+        Value::Bool(value) => value.to_string(),
+        Value::I32(value) => value.to_string(),
+        Value::I64(value) => value.to_string(),
+        Value::U32(value) => value.to_string(),
+        Value::U64(value) => value.to_string(),
+        Value::F32(value) => float(*value as f64, imports),
+        Value::F64(value) => float(*value, imports),
+        Value::String(value) => go_quote(value.as_bytes()),
+        Value::Bytes(value) => format!("[]byte({})", go_quote(value)),
+        Value::EnumNumber(number) => match kind {
+            Kind::Enum(desc) => enum_value(desc, *number, imports),
+            _ => number.to_string(),
+        },
+        Value::Message(msg) => message(msg, indent, imports),
+        Value::List(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| single(kind, item, indent, imports))
+                .collect();
+            format!("[]{}{{{}}}", go_type(kind, imports), items.join(", "))
+        }
+        Value::Map(_) => "nil".to_string(),
+    }
+}
+
+fn float(value: f64, imports: &mut Imports) -> String {
+    if value.is_finite() {
+        return format!("{:?}", value);
+    }
+    imports.std.insert("math");
+    if value.is_nan() {
+        "math.NaN()".to_string()
+    } else if value > 0.0 {
+        "math.Inf(1)".to_string()
+    } else {
+        "math.Inf(-1)".to_string()
+    }
+}
+
+fn map_key(key: &MapKey) -> String {
+    match key {
+        MapKey::Bool(key) => key.to_string(),
+        MapKey::I32(key) => key.to_string(),
+        MapKey::I64(key) => key.to_string(),
+        MapKey::U32(key) => key.to_string(),
+        MapKey::U64(key) => key.to_string(),
+        MapKey::String(key) => go_quote(key.as_bytes()),
+    }
+}
+
+fn enum_value(desc: &EnumDescriptor, number: i32, imports: &mut Imports) -> String {
+    let package = imports.package(&desc.parent_file());
+    let name = type_name(desc.full_name(), desc.parent_file().package_name());
+    match desc.get_value(number) {
+        Some(value) => {
+            // Values are scoped like the enum itself, so they're prefixed with the name of the
+            // enum's parent, or of the enum if it's not nested in a message
+            let prefix = match desc.full_name().rsplit_once('.') {
+                Some((parent, _)) if parent != desc.parent_file().package_name() => {
+                    type_name(parent, desc.parent_file().package_name())
+                }
+                _ => name,
+            };
+            format!("{}.{}_{}", package, prefix, value.name())
+        }
+        None => format!("{}.{}({})", package, name, number),
+    }
+}
+
+fn go_type(kind: &Kind, imports: &mut Imports) -> String {
+    match kind {
+        Kind::Double => "float64".to_string(),
+        Kind::Float => "float32".to_string(),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => "int32".to_string(),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => "int64".to_string(),
+        Kind::Uint32 | Kind::Fixed32 => "uint32".to_string(),
+        Kind::Uint64 | Kind::Fixed64 => "uint64".to_string(),
+        Kind::Bool => "bool".to_string(),
+        Kind::String => "string".to_string(),
+        Kind::Bytes => "[]byte".to_string(),
+        Kind::Enum(desc) => format!(
+            "{}.{}",
+            imports.package(&desc.parent_file()),
+            type_name(desc.full_name(), desc.parent_file().package_name())
+        ),
+        Kind::Message(desc) => format!(
+            "*{}.{}",
+            imports.package(&desc.parent_file()),
+            type_name(desc.full_name(), desc.parent_file().package_name())
+        ),
+    }
+}
+
+/// Function of the `proto` package that returns a pointer to a value of `kind`.
+fn pointer_helper(kind: &Kind) -> &'static str {
+    match kind {
+        Kind::Double => "Float64",
+        Kind::Float => "Float32",
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => "Int32",
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => "Int64",
+        Kind::Uint32 | Kind::Fixed32 => "Uint32",
+        Kind::Uint64 | Kind::Fixed64 => "Uint64",
+        Kind::Bool => "Bool",
+        _ => "String",
+    }
+}
+
+/// The oneof `field` belongs to, unless it's the synthetic one of a proto3 `optional` field.
+fn real_oneof(field: &FieldDescriptor) -> Option<String> {
+    if field.field_descriptor_proto().proto3_optional() {
+        return None;
+    }
+    field
+        .containing_oneof()
+        .map(|oneof| oneof.name().to_string())
+}
+
+/// Import path and name of the Go package generated for `file`, from its `go_package` option.
+/// Files without the option get a package named after the last part of their Protobuf package,
+/// in the directory of the file.
+fn go_package(file: &FileDescriptor) -> (String, String) {
+    let option = file
+        .file_descriptor_proto()
+        .options
+        .as_ref()
+        .and_then(|options| options.go_package.clone());
+    let (path, name) = match option {
+        Some(option) => match option.split_once(';') {
+            Some((path, name)) => (path.to_string(), name.to_string()),
+            None => {
+                let name = option.rsplit('/').next().unwrap_or(&option).to_string();
+                (option, name)
+            }
+        },
+        None => {
+            let path = match file.name().rsplit_once('/') {
+                Some((dir, _)) => dir.to_string(),
+                None => ".".to_string(),
+            };
+            let name = file
+                .package_name()
+                .rsplit('.')
+                .next()
+                .unwrap_or("pb")
+                .to_string();
+            (path, name)
+        }
+    };
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    (
+        path,
+        if name.is_empty() {
+            "pb".to_string()
+        } else {
+            name
+        },
+    )
+}
+
+/// Go name of the message or enum called `full_name`. Nested types are joined with underscores,
+/// like `Outer_Inner`.
+fn type_name(full_name: &str, package: &str) -> String {
+    let relative = full_name
+        .strip_prefix(package)
+        .unwrap_or(full_name)
+        .trim_start_matches('.');
+    go_camel_case(relative)
+}
+
+/// Port of `GoCamelCase` from `google.golang.org/protobuf/internal/strs`, which the generator
+/// uses for every identifier.
+fn go_camel_case(s: &str) -> String {
+    let s = s.as_bytes();
+    let mut out = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        let c = s[i];
+        let next_is_lower = s.get(i + 1).map_or(false, u8::is_ascii_lowercase);
+        if c == b'.' && next_is_lower {
+            // Skip over '.' in ".{{lowercase}}"
+        } else if c == b'.' {
+            out.push(b'_');
+        } else if c == b'_' && (i == 0 || s[i - 1] == b'.') {
+            // Make sure the name starts with a capital letter
+            out.push(b'X');
+        } else if c == b'_' && next_is_lower {
+            // Skip over '_' in "_{{lowercase}}"
+        } else if c.is_ascii_digit() {
+            out.push(c);
+        } else {
+            out.push(c.to_ascii_uppercase());
+            while s.get(i + 1).map_or(false, u8::is_ascii_lowercase) {
+                i += 1;
+                out.push(s[i]);
+            }
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// A Go string literal holding exactly `bytes`.
+fn go_quote(bytes: &[u8]) -> String {
+    let mut out = String::from("\"");
+    for &byte in bytes {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            0x20..=0x7E => out.push(byte as char),
+            _ => out.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,152 @@
+//! Turns a call into a command line, or a small client program, that reproduces it without
+//! Spaceman.
+
+use std::path::Path;
+
+use anyhow::Result;
+use prost_reflect::{DynamicMessage, MethodDescriptor};
+
+use spaceman_types::endpoint::{Endpoint, LbPolicy};
+use spaceman_types::export::ExportFormat;
+
+use crate::sources;
+
+mod go;
+mod rust;
+
+/// Everything that goes into a call, as configured by the user.
+pub struct Call<'a> {
+    pub endpoint: &'a Endpoint,
+    pub method: &'a MethodDescriptor,
+    /// Metadata pairs. Values of keys ending in `-bin` are base64 encoded.
+    pub metadata: &'a [(String, String)],
+    pub body: &'a DynamicMessage,
+    /// Descriptor sources that the method was loaded from, see `sources`. The command lines need
+    /// them to encode the body.
+    pub descriptor_paths: &'a [String],
+}
+
+/// Renders `call` in the given format.
+///
+/// Only the endpoint's authority and TLS options are carried over, proxies and connection tuning
+/// are left out. Calls to more than one server are exported as calls to the first one.
+pub fn export(call: &Call, format: ExportFormat) -> Result<String> {
+    Ok(match format {
+        ExportFormat::Grpcurl => grpcurl(call)?,
+        ExportFormat::Spaceman => spaceman(call)?,
+        ExportFormat::Rust => rust::snippet(call),
+        ExportFormat::Go => go::snippet(call),
+    })
+}
+
+fn grpcurl(call: &Call) -> Result<String> {
+    let mut args = vec!["grpcurl".to_string()];
+    match &call.endpoint.tls {
+        None => args.push("-plaintext".to_string()),
+        Some(tls) => {
+            if tls.no_check {
+                args.push("-insecure".to_string());
+            }
+            if let Some(ca_cert) = &tls.ca_cert {
+                args.push(format!("-cacert {}", shell_quote(ca_cert)));
+            }
+        }
+    }
+    // grpcurl takes files only, directories and globs are expanded to what they stand for
+    for source in call.descriptor_paths {
+        for path in sources::expand(source)? {
+            args.extend(grpcurl_descriptor_args(&path));
+        }
+    }
+    for (key, value) in call.metadata {
+        args.push(format!(
+            "-H {}",
+            shell_quote(&format!("{}: {}", key, value))
+        ));
+    }
+    args.push(format!(
+        "-d {}",
+        shell_quote(&serde_json::to_string(call.body)?)
+    ));
+    // grpcurl doesn't understand resolver schemes
+    let address = first_address(&call.endpoint.authority);
+    args.push(shell_quote(address.trim_start_matches("dns:///")));
+    let full_name = call.method.full_name();
+    let path = match full_name.rsplit_once('.') {
+        Some((service, method)) => format!("{}/{}", service, method),
+        None => full_name.to_string(),
+    };
+    args.push(shell_quote(&path));
+    Ok(args.join(" \\\n  "))
+}
+
+// `-proto` for Protobuf sources, which grpcurl compiles itself, `-protoset` for descriptor sets
+fn grpcurl_descriptor_args(path: &Path) -> Vec<String> {
+    let is_source = path.extension().and_then(|extension| extension.to_str()) == Some("proto");
+    match (is_source, path.parent(), path.file_name()) {
+        (true, Some(dir), Some(file_name)) => {
+            let dir = dir.to_string_lossy();
+            vec![
+                format!(
+                    "-import-path {}",
+                    shell_quote(if dir.is_empty() { "." } else { &dir })
+                ),
+                format!("-proto {}", shell_quote(&file_name.to_string_lossy())),
+            ]
+        }
+        _ => vec![format!(
+            "-protoset {}",
+            shell_quote(&path.to_string_lossy())
+        )],
+    }
+}
+
+fn spaceman(call: &Call) -> Result<String> {
+    let mut args = vec!["spaceman".to_string()];
+    for path in call.descriptor_paths {
+        args.push(format!("--desc {}", shell_quote(path)));
+    }
+    args.push("call".to_string());
+    match &call.endpoint.tls {
+        None => args.push("--insecure".to_string()),
+        Some(tls) => {
+            if tls.no_check {
+                args.push("--tls-nocheck".to_string());
+            }
+            if let Some(ca_cert) = &tls.ca_cert {
+                args.push(format!("--tls-cacert {}", shell_quote(ca_cert)));
+            }
+        }
+    }
+    if call.endpoint.lb_policy == LbPolicy::RoundRobin {
+        args.push("--lb round-robin".to_string());
+    }
+    for (key, value) in call.metadata {
+        args.push(format!("-M {}", shell_quote(&format!("{}:{}", key, value))));
+    }
+    args.push(shell_quote(&call.endpoint.authority));
+    args.push(format!("{} <<'EOF'", shell_quote(call.method.full_name())));
+    // JSON can't have a line made of just `EOF`, so the heredoc can't end early
+    Ok(format!(
+        "{}\n{}\nEOF",
+        args.join(" \\\n  "),
+        serde_json::to_string_pretty(call.body)?
+    ))
+}
+
+/// Calls can go to a comma separated list of servers, snippets only ever talk to the first one.
+fn first_address(authority: &str) -> &str {
+    authority.split(',').next().unwrap_or(authority).trim()
+}
+
+/// Quotes `s` for POSIX shells, unless it's made only of characters that need no quoting.
+fn shell_quote(s: &str) -> String {
+    let is_plain = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c));
+    if is_plain {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', r"'\''"))
+    }
+}
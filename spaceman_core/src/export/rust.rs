@@ -0,0 +1,420 @@
+//! Client snippets using the code that `tonic-build` generates.
+
+use prost_reflect::{
+    DynamicMessage, EnumDescriptor, FieldDescriptor, Kind, MapKey, ReflectMessage, Value,
+};
+
+use super::{first_address, Call};
+
+const WRAPPERS: &[&str] = &[
+    "google.protobuf.DoubleValue",
+    "google.protobuf.FloatValue",
+    "google.protobuf.Int64Value",
+    "google.protobuf.UInt64Value",
+    "google.protobuf.Int32Value",
+    "google.protobuf.UInt32Value",
+    "google.protobuf.BoolValue",
+    "google.protobuf.StringValue",
+    "google.protobuf.BytesValue",
+];
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+pub(super) fn snippet(call: &Call) -> String {
+    let method = call.method;
+    let service = method.parent_service();
+    let package = service.parent_file().package_name().to_string();
+    let client = join_path(vec![
+        package_path(&package),
+        format!("{}_client", snake_case(service.name())),
+        format!("{}Client", upper_camel_case(service.name())),
+    ]);
+
+    let mut lines = vec![
+        "// Assumes the code generated for the .proto files is included in the crate root, like"
+            .to_string(),
+        format!(
+            "// `pub mod {} {{ tonic::include_proto!(\"{}\"); }}`",
+            package_path(&package),
+            package
+        ),
+        "use tonic::transport::Channel;".to_string(),
+        String::new(),
+        "#[tokio::main]".to_string(),
+        "async fn main() -> Result<(), Box<dyn std::error::Error>> {".to_string(),
+    ];
+
+    let scheme = if call.endpoint.tls.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    let address = first_address(&call.endpoint.authority).trim_start_matches("dns:///");
+    lines.push(format!(
+        "    let channel = Channel::from_static({:?})",
+        format!("{}://{}", scheme, address)
+    ));
+    if let Some(tls) = &call.endpoint.tls {
+        if tls.no_check {
+            lines.push(
+                "        // tonic has no way to skip the verification of the server".to_string(),
+            );
+        }
+        match &tls.ca_cert {
+            Some(ca_cert) => {
+                lines.push(
+                    "        .tls_config(tonic::transport::ClientTlsConfig::new().ca_certificate("
+                        .to_string(),
+                );
+                lines.push(format!(
+                    "            tonic::transport::Certificate::from_pem(std::fs::read({:?})?),",
+                    ca_cert
+                ));
+                lines.push("        ))?".to_string());
+            }
+            None => lines
+                .push("        .tls_config(tonic::transport::ClientTlsConfig::new())?".to_string()),
+        }
+    }
+    lines.push("        .connect()".to_string());
+    lines.push("        .await?;".to_string());
+    lines.push(format!("    let mut client = {}::new(channel);", client));
+    lines.push(String::new());
+
+    lines.push(format!("    let message = {};", message(call.body, 1)));
+    if method.is_client_streaming() {
+        lines.push(
+            "    let mut request = tonic::Request::new(tokio_stream::iter(vec![message]));"
+                .to_string(),
+        );
+    } else {
+        lines.push("    let mut request = tonic::Request::new(message);".to_string());
+    }
+    for (key, value) in call.metadata {
+        if key.ends_with("-bin") {
+            let bytes = base64::decode(value).unwrap_or_else(|_| value.as_bytes().to_vec());
+            lines.push(format!(
+                "    request.metadata_mut().insert_bin({:?}, tonic::metadata::MetadataValue::from_bytes(&{:?}));",
+                key, bytes
+            ));
+        } else {
+            lines.push(format!(
+                "    request.metadata_mut().insert({:?}, {:?}.parse()?);",
+                key, value
+            ));
+        }
+    }
+    lines.push(String::new());
+
+    lines.push(format!(
+        "    let response = client.{}(request).await?;",
+        ident(&snake_case(method.name()))
+    ));
+    if method.is_server_streaming() {
+        lines.push("    let mut stream = response.into_inner();".to_string());
+        lines.push("    while let Some(message) = stream.message().await? {".to_string());
+        lines.push("        println!(\"{:#?}\", message);".to_string());
+        lines.push("    }".to_string());
+    } else {
+        lines.push("    println!(\"{:#?}\", response.into_inner());".to_string());
+    }
+    lines.push("    Ok(())".to_string());
+    lines.push("}".to_string());
+
+    lines.join("\n")
+}
+
+/// A struct literal for `msg`, leaving unset fields to `Default::default()`.
+fn message(msg: &DynamicMessage, indent: usize) -> String {
+    let desc = msg.descriptor();
+    // prost maps these to plain Rust types
+    if desc.full_name() == "google.protobuf.Empty" {
+        return "()".to_string();
+    }
+    if WRAPPERS.contains(&desc.full_name()) {
+        let field = desc
+            .get_field_by_name("value")
+            .expect("wrappers have a value field");
+        return single(&field.kind(), &msg.get_field(&field), indent);
+    }
+
+    let pad = "    ".repeat(indent + 1);
+    let mut members = Vec::new();
+    for field in desc.fields() {
+        if !msg.has_field(&field) {
+            continue;
+        }
+        let value = msg.get_field(&field);
+        match real_oneof(&field) {
+            Some(oneof) => members.push(format!(
+                "{}{}: Some({}({})),",
+                pad,
+                ident(&snake_case(&oneof)),
+                join_path(vec![
+                    message_module(desc.full_name(), desc.parent_file().package_name()),
+                    upper_camel_case(&oneof),
+                    upper_camel_case(field.name()),
+                ]),
+                single(&field.kind(), &value, indent + 1)
+            )),
+            None => members.push(format!(
+                "{}{}: {},",
+                pad,
+                ident(&snake_case(field.name())),
+                field_value(&field, &value, indent + 1)
+            )),
+        }
+    }
+
+    let struct_fields = desc
+        .fields()
+        .filter(|field| real_oneof(field).is_none())
+        .count()
+        + desc
+            .oneofs()
+            .filter(|oneof| oneof.fields().any(|field| real_oneof(&field).is_some()))
+            .count();
+    if members.len() < struct_fields {
+        members.push(format!("{}..Default::default()", pad));
+    }
+
+    let path = type_path(desc.full_name(), desc.parent_file().package_name());
+    if members.is_empty() {
+        return format!("{} {{}}", path);
+    }
+    format!(
+        "{} {{\n{}\n{}}}",
+        path,
+        members.join("\n"),
+        "    ".repeat(indent)
+    )
+}
+
+fn field_value(field: &FieldDescriptor, value: &Value, indent: usize) -> String {
+    if let (true, Kind::Message(entry)) = (field.is_map(), field.kind()) {
+        let value_field = entry.map_entry_value_field();
+        let mut entries: Vec<String> = value
+            .as_map()
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| {
+                format!(
+                    "({}, {})",
+                    map_key(key),
+                    single(&value_field.kind(), value, indent + 1)
+                )
+            })
+            .collect();
+        entries.sort();
+        format!("[{}].into_iter().collect()", entries.join(", "))
+    } else if field.is_list() {
+        let items: Vec<String> = value
+            .as_list()
+            .into_iter()
+            .flatten()
+            .map(|item| single(&field.kind(), item, indent))
+            .collect();
+        format!("vec![{}]", items.join(", "))
+    } else if field.supports_presence() {
+        format!("Some({})", single(&field.kind(), value, indent))
+    } else {
+        single(&field.kind(), value, indent)
+    }
+}
+
+fn single(kind: &Kind, value: &Value, indent: usize) -> String {
+    match value {
+        Value::Bool(value) => value.to_string(),
+        Value::I32(value) => value.to_string(),
+        Value::I64(value) => value.to_string(),
+        Value::U32(value) => value.to_string(),
+        Value::U64(value) => value.to_string(),
+        Value::F32(value) if value.is_finite() => format!("{:?}", value),
+        Value::F32(value) => float_constant("f32", *value as f64),
+        Value::F64(value) if value.is_finite() => format!("{:?}", value),
+        Value::F64(value) => float_constant("f64", *value),
+        Value::String(value) => format!("{:?}.to_string()", value),
+        Value::Bytes(value) => format!("vec!{:?}", &value[..]),
+        Value::EnumNumber(number) => match kind {
+            Kind::Enum(desc) => enum_value(desc, *number),
+            _ => number.to_string(),
+        },
+        Value::Message(msg) => message(msg, indent),
+        Value::List(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|item| single(kind, item, indent))
+                .collect();
+            format!("vec![{}]", items.join(", "))
+        }
+        Value::Map(_) => "Default::default()".to_string(),
+    }
+}
+
+fn float_constant(ty: &str, value: f64) -> String {
+    if value.is_nan() {
+        format!("{}::NAN", ty)
+    } else if value > 0.0 {
+        format!("{}::INFINITY", ty)
+    } else {
+        format!("{}::NEG_INFINITY", ty)
+    }
+}
+
+fn map_key(key: &MapKey) -> String {
+    match key {
+        MapKey::Bool(key) => key.to_string(),
+        MapKey::I32(key) => key.to_string(),
+        MapKey::I64(key) => key.to_string(),
+        MapKey::U32(key) => key.to_string(),
+        MapKey::U64(key) => key.to_string(),
+        MapKey::String(key) => format!("{:?}.to_string()", key),
+    }
+}
+
+/// prost stores enums as `i32`, the variant is only there for readability.
+fn enum_value(desc: &EnumDescriptor, number: i32) -> String {
+    match desc.get_value(number) {
+        Some(value) => {
+            let prefix = upper_camel_case(desc.name());
+            let name = upper_camel_case(value.name());
+            let stripped = name.strip_prefix(&prefix).unwrap_or(&name);
+            // "Foo" is not a prefix of "Foobar"
+            let variant = if stripped.starts_with(char::is_uppercase) {
+                stripped
+            } else {
+                &name
+            };
+            format!(
+                "{}::{} as i32",
+                type_path(desc.full_name(), desc.parent_file().package_name()),
+                variant
+            )
+        }
+        None => number.to_string(),
+    }
+}
+
+/// The oneof `field` belongs to, unless it's the synthetic one of a proto3 `optional` field.
+fn real_oneof(field: &FieldDescriptor) -> Option<String> {
+    if field.field_descriptor_proto().proto3_optional() {
+        return None;
+    }
+    field
+        .containing_oneof()
+        .map(|oneof| oneof.name().to_string())
+}
+
+fn package_path(package: &str) -> String {
+    if package == "google.protobuf" {
+        return "::prost_types".to_string();
+    }
+    join_path(
+        package
+            .split('.')
+            .map(|part| ident(&snake_case(part)))
+            .collect(),
+    )
+}
+
+/// Path of the message or enum called `full_name`. Types nested in a message live in a module
+/// named after it.
+fn type_path(full_name: &str, package: &str) -> String {
+    let (parents, name) = split_name(full_name, package);
+    let mut path = vec![package_path(package)];
+    path.extend(parents.iter().map(|parent| ident(&snake_case(parent))));
+    path.push(upper_camel_case(name));
+    join_path(path)
+}
+
+/// Module holding the types nested in the message called `full_name`, where its oneofs are too.
+fn message_module(full_name: &str, package: &str) -> String {
+    let (parents, name) = split_name(full_name, package);
+    let mut path = vec![package_path(package)];
+    path.extend(parents.iter().map(|parent| ident(&snake_case(parent))));
+    path.push(ident(&snake_case(name)));
+    join_path(path)
+}
+
+fn split_name<'a>(full_name: &'a str, package: &str) -> (Vec<&'a str>, &'a str) {
+    let relative = full_name
+        .strip_prefix(package)
+        .unwrap_or(full_name)
+        .trim_start_matches('.');
+    let mut parts: Vec<&str> = relative.split('.').collect();
+    let name = parts.pop().unwrap_or(relative);
+    (parts, name)
+}
+
+fn join_path(segments: Vec<String>) -> String {
+    segments
+        .into_iter()
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn ident(name: &str) -> String {
+    match name {
+        // Can't be raw identifiers
+        "self" | "super" | "crate" | "Self" => format!("{}_", name),
+        _ if KEYWORDS.contains(&name) => format!("r#{}", name),
+        _ => name.to_string(),
+    }
+}
+
+/// Splits an identifier into words the way prost does when converting between cases, so
+/// `HTTPServer_v2` gives `HTTP`, `Server` and `v2`.
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for part in name.split(|c: char| !c.is_alphanumeric()) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (prev, cur) = (chars[i - 1], chars[i]);
+            let next_is_lower = chars.get(i + 1).map_or(false, |c| c.is_lowercase());
+            if cur.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_ascii_digit()
+                    || (prev.is_uppercase() && next_is_lower))
+            {
+                words.push(chars[start..i].iter().collect());
+                start = i;
+            }
+        }
+        if start < chars.len() {
+            words.push(chars[start..].iter().collect());
+        }
+    }
+    words
+}
+
+fn snake_case(name: &str) -> String {
+    words(name)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+fn upper_camel_case(name: &str) -> String {
+    words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
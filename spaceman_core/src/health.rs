@@ -0,0 +1,64 @@
+use anyhow::{anyhow, Context, Result};
+use futures::{Stream, StreamExt};
+use prost_reflect::{DescriptorPool, DynamicMessage, MethodDescriptor, Value};
+use tonic::Request;
+
+use spaceman_types::health::ServingStatus;
+
+use crate::{Conn, BUNDLED_DESCRIPTORS};
+
+const HEALTH_SERVICE: &str = "grpc.health.v1.Health";
+
+/// Asks the server for the serving status of `service`. An empty service name asks about the
+/// server as a whole.
+pub async fn check(conn: &Conn, service: &str) -> Result<ServingStatus> {
+    let md = method("Check")?;
+    let res = conn.unary(&md, make_request(&md, service)).await.result?;
+    serving_status(&res.get_ref().message)
+}
+
+/// Like [`check`] but keeps the call open and yields a new status each time it changes.
+pub async fn watch(
+    conn: &Conn,
+    service: &str,
+) -> Result<impl Stream<Item = Result<ServingStatus>>> {
+    let md = method("Watch")?;
+    let res = conn
+        .server_streaming(&md, make_request(&md, service))
+        .await
+        .result?;
+    Ok(res
+        .into_inner()
+        .map(|msg| serving_status(&msg.context("receiving health update")?.message)))
+}
+
+fn method(name: &str) -> Result<MethodDescriptor> {
+    let pool =
+        DescriptorPool::decode(BUNDLED_DESCRIPTORS).context("decoding bundled descriptors")?;
+    // Bound to a variable so that the iterators borrowing `pool` are dropped before it is
+    let method = pool
+        .services()
+        .find(|service| service.full_name() == HEALTH_SERVICE)
+        .and_then(|service| service.methods().find(|method| method.name() == name))
+        .ok_or_else(|| anyhow!("{HEALTH_SERVICE}.{name} missing from bundled descriptors"));
+    method
+}
+
+fn make_request(md: &MethodDescriptor, service: &str) -> Request<DynamicMessage> {
+    let mut msg = DynamicMessage::new(md.input());
+    msg.set_field_by_name("service", Value::String(service.to_string()));
+    Request::new(msg)
+}
+
+fn serving_status(msg: &DynamicMessage) -> Result<ServingStatus> {
+    let status = msg
+        .get_field_by_name("status")
+        .and_then(|value| value.as_enum_number())
+        .ok_or_else(|| anyhow!("health response has no status"))?;
+    Ok(match status {
+        1 => ServingStatus::Serving,
+        2 => ServingStatus::NotServing,
+        3 => ServingStatus::ServiceUnknown,
+        _ => ServingStatus::Unknown,
+    })
+}
@@ -0,0 +1,576 @@
+//! Reading requests out of grpcurl command lines and Postman collections.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+
+use spaceman_types::endpoint::{ConnectionOptions, Endpoint, TlsOptions};
+use spaceman_types::import::{Import, ImportedRequest};
+
+/// Imports `text` as a Postman collection if it's a JSON object, or as a shell script with
+/// grpcurl invocations otherwise.
+pub fn import(text: &str) -> Result<Import> {
+    if text.trim_start().starts_with('{') {
+        postman(text)
+    } else {
+        grpcurl(text)
+    }
+}
+
+/// grpcurl flags that don't take a value.
+const GRPCURL_SWITCHES: &[&str] = &[
+    "plaintext",
+    "insecure",
+    "v",
+    "vv",
+    "veryverbose",
+    "emit-defaults",
+    "allow-unknown-fields",
+    "format-error",
+    "expand-headers",
+    "msg-template",
+    "use-reflection",
+    "unix",
+    "alts",
+    "help",
+    "version",
+];
+
+/// Imports every grpcurl call in a shell script. Commands other than grpcurl, and grpcurl's
+/// `list` and `describe` verbs, are skipped.
+pub fn grpcurl(script: &str) -> Result<Import> {
+    let mut import = Import::default();
+    let mut warned_about_sources = false;
+
+    for command in shell_commands(script)? {
+        // Skip variable assignments like in `FOO=bar grpcurl ...`
+        let mut words = command
+            .words
+            .iter()
+            .skip_while(|word| word.contains('=') && !word.starts_with('-'));
+        match words.next() {
+            Some(program) if program == "grpcurl" || program.ends_with("/grpcurl") => {}
+            _ => continue,
+        }
+
+        let mut endpoint = Endpoint {
+            tls: Some(TlsOptions::default()),
+            ..Default::default()
+        };
+        let mut connection = ConnectionOptions::default();
+        let mut body = None;
+        let mut metadata = Vec::new();
+        let mut positional = Vec::new();
+
+        let mut words = words.cloned();
+        while let Some(word) = words.next() {
+            let flag = match word.strip_prefix('-') {
+                Some(flag) if !flag.is_empty() => flag.trim_start_matches('-'),
+                _ => {
+                    positional.push(word);
+                    continue;
+                }
+            };
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (flag.to_string(), None),
+            };
+            let value = if GRPCURL_SWITCHES.contains(&name.as_str()) {
+                value
+            } else {
+                match value.or_else(|| words.next()) {
+                    Some(value) => Some(value),
+                    None => return Err(anyhow!("flag `-{}` of grpcurl needs a value", name)),
+                }
+            };
+            // Switches can be turned off with `-flag=false`
+            let is_on = value.as_deref() != Some("false");
+
+            match name.as_str() {
+                "d" => body = value,
+                "H" | "rpc-header" => {
+                    let header = value.unwrap_or_default();
+                    match header.split_once(':') {
+                        Some((key, val)) => {
+                            metadata.push((key.trim().to_string(), val.trim_start().to_string()))
+                        }
+                        None => import
+                            .warnings
+                            .push(format!("header `{}` has no value and was left out", header)),
+                    }
+                }
+                "plaintext" if is_on => endpoint.tls = None,
+                "insecure" if is_on => {
+                    if let Some(tls) = &mut endpoint.tls {
+                        tls.no_check = true;
+                    }
+                }
+                "cacert" => {
+                    if let Some(tls) = &mut endpoint.tls {
+                        tls.ca_cert = value;
+                    }
+                }
+                "protoset" => {
+                    let path = value.unwrap_or_default();
+                    if !import.proto_paths.contains(&path) {
+                        import.proto_paths.push(path);
+                    }
+                }
+                "import-path" | "proto" => {
+                    if !warned_about_sources {
+                        import.warnings.push(
+                            "Spaceman loads descriptor sets, not .proto files. Compile them with \
+                             `protoc --include_imports --descriptor_set_out=FILE` and add FILE \
+                             to the protos"
+                                .to_string(),
+                        );
+                        warned_about_sources = true;
+                    }
+                }
+                "connect-timeout" => connection.connect_timeout_ms = seconds_to_ms(value),
+                "keepalive-time" => connection.keepalive_interval_ms = seconds_to_ms(value),
+                "format" if value.as_deref() != Some("json") => import.warnings.push(
+                    "bodies in grpcurl's text format need to be rewritten as JSON".to_string(),
+                ),
+                "plaintext"
+                | "insecure"
+                | "v"
+                | "vv"
+                | "veryverbose"
+                | "emit-defaults"
+                | "format-error"
+                | "format"
+                | "allow-unknown-fields"
+                | "use-reflection" => {}
+                "reflect-header" => {}
+                other => import.warnings.push(format!(
+                    "flag `-{}` of grpcurl has no equivalent and was left out",
+                    other
+                )),
+            }
+        }
+
+        // Anything after the method comes from redirections and pipes
+        let (address, method) = match &positional[..] {
+            [address, method, ..] if method != "list" && method != "describe" => (address, method),
+            _ => continue,
+        };
+        endpoint.authority = address.clone();
+        if connection != ConnectionOptions::default() {
+            endpoint.connection = Some(connection);
+        }
+
+        // `-d @` reads the body from STDIN, which is usually a heredoc
+        let body = match body.as_deref() {
+            Some("@") if command.stdin.is_none() => {
+                import.warnings.push(format!(
+                    "the body of the call to `{}` is read from STDIN and was left empty",
+                    method
+                ));
+                String::new()
+            }
+            Some("@") => command.stdin.clone().unwrap_or_default(),
+            Some(body) => body.to_string(),
+            None => String::new(),
+        };
+
+        push_request(&mut import, endpoint, method, body, metadata);
+    }
+
+    Ok(import)
+}
+
+/// Imports the gRPC requests of a Postman collection, going through folders too.
+///
+/// Each request is expected to have the method in `methodPath`, the server in `url` and the
+/// message in `body.raw` or in the first entry of `messages`. Metadata is read from `metadata`
+/// or `header`. `{{variables}}` are replaced with the values defined in the collection. A server
+/// is considered to use TLS when its URL starts with `grpcs://` or `https://`, or when the
+/// request has TLS enabled in its settings.
+pub fn postman(collection: &str) -> Result<Import> {
+    let collection: Value =
+        serde_json::from_str(collection).context("parsing Postman collection")?;
+    let mut import = Import::default();
+
+    let variables: Vec<(String, String)> = collection
+        .get("variable")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|variable| {
+            let key = variable.get("key")?.as_str()?;
+            let value = match variable.get("value")? {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            Some((key.to_string(), value))
+        })
+        .collect();
+    let expand = |text: &str| {
+        variables
+            .iter()
+            .fold(text.to_string(), |text, (key, value)| {
+                text.replace(&format!("{{{{{}}}}}", key), value)
+            })
+    };
+
+    let mut items = Vec::new();
+    collect_items(&collection, &mut items);
+    for item in items {
+        let name = item.get("name").and_then(Value::as_str).unwrap_or_default();
+        let request = &item["request"];
+
+        let method = match request
+            .get("methodPath")
+            .or_else(|| request.get("method"))
+            .and_then(Value::as_str)
+        {
+            Some(method) if method.contains('/') || method.contains('.') => expand(method),
+            _ => {
+                import
+                    .warnings
+                    .push(format!("`{}` is not a gRPC request and was left out", name));
+                continue;
+            }
+        };
+
+        let url = match request.get("url") {
+            Some(Value::String(url)) => url.clone(),
+            Some(url) => url
+                .get("raw")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            None => String::new(),
+        };
+        let url = expand(&url);
+        let (is_tls_url, authority) = match url.split_once("://") {
+            Some((scheme, authority)) => (scheme == "grpcs" || scheme == "https", authority),
+            None => (false, url.as_str()),
+        };
+        let has_tls_enabled = ["tls", "enableTls", "useTls"].iter().any(|key| {
+            request.get(*key).and_then(Value::as_bool).unwrap_or(false)
+                || request["settings"]
+                    .get(*key)
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false)
+        });
+        let endpoint = Endpoint {
+            authority: authority.trim_end_matches('/').to_string(),
+            tls: (is_tls_url || has_tls_enabled).then(TlsOptions::default),
+            ..Default::default()
+        };
+
+        let body = request["body"]
+            .get("raw")
+            .or_else(|| {
+                request["messages"]
+                    .get(0)
+                    .and_then(|message| message.get("content"))
+            })
+            .or_else(|| request["message"].get("content"))
+            .and_then(Value::as_str)
+            .map(&expand)
+            .unwrap_or_default();
+
+        let metadata = request
+            .get("metadata")
+            .or_else(|| request.get("header"))
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter(|pair| {
+                !pair
+                    .get("disabled")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false)
+            })
+            .filter_map(|pair| {
+                let key = pair.get("key")?.as_str()?;
+                let value = pair
+                    .get("value")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                Some((expand(key), expand(value)))
+            })
+            .collect();
+
+        let idx = import.requests.len();
+        push_request(&mut import, endpoint, &method, body, metadata);
+        if !name.is_empty() {
+            import.requests[idx].name = name.to_string();
+        }
+    }
+
+    Ok(import)
+}
+
+/// Items of a collection that have a request, in order, taken out of their folders.
+fn collect_items<'a>(node: &'a Value, items: &mut Vec<&'a Value>) {
+    for item in node
+        .get("item")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if item.get("request").is_some() {
+            items.push(item);
+        }
+        collect_items(item, items);
+    }
+}
+
+fn push_request(
+    import: &mut Import,
+    endpoint: Endpoint,
+    method: &str,
+    body: String,
+    metadata: Vec<(String, String)>,
+) {
+    // Both `pkg.Service/Method` and `/pkg.Service/Method` are common
+    let method_full_name = method.trim_start_matches('/').replace('/', ".");
+    let name = method_full_name
+        .rsplit('.')
+        .next()
+        .unwrap_or(&method_full_name)
+        .to_string();
+
+    // Keep the body as it was written if it's not valid JSON, like a stream of messages
+    let body = match serde_json::from_str::<Value>(&body) {
+        Ok(value) => serde_json::to_string_pretty(&value).expect("JSON values to serialize"),
+        Err(_) if body.trim().is_empty() => "{}".to_string(),
+        Err(_) => body,
+    };
+
+    let endpoint_idx = match import.endpoints.iter().position(|known| *known == endpoint) {
+        Some(idx) => idx,
+        None => {
+            import.endpoints.push(endpoint);
+            import.endpoints.len() - 1
+        }
+    };
+
+    import.requests.push(ImportedRequest {
+        name,
+        method_full_name,
+        body,
+        metadata,
+        endpoint: Some(endpoint_idx),
+    });
+}
+
+fn seconds_to_ms(value: Option<String>) -> Option<u64> {
+    let seconds: f64 = value?.parse().ok()?;
+    Some((seconds * 1000.0) as u64)
+}
+
+/// A command of a shell script.
+struct ShellCommand {
+    words: Vec<String>,
+    /// Content of the heredoc fed to the command, if any.
+    stdin: Option<String>,
+}
+
+/// Splits a shell script into commands, following quotes, escapes, comments, line continuations
+/// and heredocs. Anything fancier, like variable expansion, is taken literally.
+fn shell_commands(script: &str) -> Result<Vec<ShellCommand>> {
+    let mut commands = Vec::new();
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut heredoc: Option<String> = None;
+    let mut stdin = None;
+
+    let mut chars = script.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                // Line continuation
+                Some('\n') => {}
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => {}
+            },
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(anyhow!("unterminated single quote")),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(anyhow!("unterminated double quote")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(anyhow!("unterminated double quote")),
+                    }
+                }
+            }
+            '#' if word.is_none() => {
+                while chars.peek().map_or(false, |c| *c != '\n') {
+                    chars.next();
+                }
+            }
+            '<' if word.is_none() && chars.peek() == Some(&'<') => {
+                chars.next();
+                if chars.peek() == Some(&'-') {
+                    chars.next();
+                }
+                while chars.peek().map_or(false, |c| *c == ' ' || *c == '\t') {
+                    chars.next();
+                }
+                let mut delimiter = String::new();
+                while let Some(c) = chars.peek().copied() {
+                    if c.is_whitespace() || c == ';' || c == '|' || c == '&' {
+                        break;
+                    }
+                    chars.next();
+                    if c != '\'' && c != '"' {
+                        delimiter.push(c);
+                    }
+                }
+                heredoc = Some(delimiter);
+            }
+            ' ' | '\t' => words.extend(word.take()),
+            '\n' | ';' | '|' | '&' => {
+                words.extend(word.take());
+                // The heredoc starts on the line after the command
+                if c == '\n' {
+                    if let Some(delimiter) = heredoc.take() {
+                        let mut lines = Vec::new();
+                        loop {
+                            let line: String = chars.by_ref().take_while(|c| *c != '\n').collect();
+                            if line.trim() == delimiter {
+                                break;
+                            }
+                            lines.push(line);
+                            if chars.peek().is_none() {
+                                break;
+                            }
+                        }
+                        stdin = Some(lines.join("\n"));
+                    }
+                }
+                // The heredoc belongs to the command it's written after, even if it's read
+                // once the whole line is over
+                if heredoc.is_none() && !words.is_empty() {
+                    commands.push(ShellCommand {
+                        words: std::mem::take(&mut words),
+                        stdin: stdin.take(),
+                    });
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word.take());
+    if !words.is_empty() {
+        commands.push(ShellCommand { words, stdin });
+    }
+
+    Ok(commands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(script: &str) -> Vec<Vec<String>> {
+        shell_commands(script)
+            .unwrap()
+            .into_iter()
+            .map(|command| command.words)
+            .collect()
+    }
+
+    #[test]
+    fn splits_words_and_follows_quotes() {
+        assert_eq!(
+            words(r#"grpcurl -d '{"id": 1}' -H "x-tag: \"a\" \$b" 'it''s' host:443 pkg.Svc/Get"#),
+            [[
+                "grpcurl",
+                "-d",
+                r#"{"id": 1}"#,
+                "-H",
+                r#"x-tag: "a" $b"#,
+                "its",
+                "host:443",
+                "pkg.Svc/Get",
+            ]]
+        );
+        assert_eq!(words("echo '' \"\" x"), [["echo", "", "", "x"]]);
+        assert_eq!(words(r"echo a\ b \n"), [["echo", "a b", "n"]]);
+    }
+
+    #[test]
+    fn follows_line_continuations() {
+        assert_eq!(
+            words("grpcurl \\\n  -plaintext \\\n  localhost:50051 \\\n  pkg.Svc/Get\n"),
+            [["grpcurl", "-plaintext", "localhost:50051", "pkg.Svc/Get"]]
+        );
+        assert_eq!(
+            words("grpcurl -d \"{\\\n}\" a\n"),
+            [["grpcurl", "-d", "{}", "a"]]
+        );
+    }
+
+    #[test]
+    fn splits_commands_and_skips_comments() {
+        assert_eq!(
+            words(
+                "# list first\ngrpcurl a list; grpcurl b list && echo done # twice\n\ncat x | jq"
+            ),
+            vec![
+                vec!["grpcurl", "a", "list"],
+                vec!["grpcurl", "b", "list"],
+                vec!["echo", "done"],
+                vec!["cat", "x"],
+                vec!["jq"],
+            ]
+        );
+        // Only at the start of a word
+        assert_eq!(words("echo a#b"), [["echo", "a#b"]]);
+    }
+
+    #[test]
+    fn reads_heredocs() {
+        let commands = shell_commands(
+            "grpcurl -d @ host pkg.Svc/Get <<EOF\n{\n  \"id\": 1\n}\nEOF\ngrpcurl host list\n",
+        )
+        .unwrap();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(
+            commands[0].words,
+            ["grpcurl", "-d", "@", "host", "pkg.Svc/Get"]
+        );
+        assert_eq!(commands[0].stdin.as_deref(), Some("{\n  \"id\": 1\n}"));
+        assert_eq!(commands[1].words, ["grpcurl", "host", "list"]);
+        assert_eq!(commands[1].stdin, None);
+
+        let commands = shell_commands("grpcurl -d @ h s/m <<-'END'\n\t{}\n\tEND\n").unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].stdin.as_deref(), Some("\t{}"));
+
+        // Unterminated heredocs run to the end of the script
+        let commands = shell_commands("grpcurl -d @ h s/m <<EOF\n{}").unwrap();
+        assert_eq!(commands[0].stdin.as_deref(), Some("{}"));
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes() {
+        assert!(shell_commands("grpcurl -d '{}").is_err());
+        assert!(shell_commands("grpcurl -H \"a: b").is_err());
+        assert!(shell_commands("grpcurl -H \"a: b\\").is_err());
+    }
+}
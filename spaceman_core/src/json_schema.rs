@@ -0,0 +1,250 @@
+use prost_reflect::{EnumDescriptor, FieldDescriptor, Kind, MessageDescriptor};
+use serde_json::{json, Map, Value};
+
+use crate::schema::is_well_known_type;
+use crate::source_info;
+
+/// Generates a JSON Schema (draft 7) that validates the JSON mapping of `desc`, for editors to
+/// offer completion and validation. Every message and enum reachable from `desc` gets an entry in
+/// `definitions`, keyed by full name, so recursive messages are fine.
+pub fn json_schema(desc: &MessageDescriptor) -> Value {
+    let mut definitions = Map::new();
+    add_message(&mut definitions, desc);
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "$ref": reference(desc.full_name()),
+        "definitions": definitions,
+    })
+}
+
+fn reference(full_name: &str) -> String {
+    format!("#/definitions/{}", full_name)
+}
+
+fn add_message(definitions: &mut Map<String, Value>, desc: &MessageDescriptor) {
+    if definitions.contains_key(desc.full_name()) {
+        return;
+    }
+    if is_well_known_type(desc.full_name()) {
+        definitions.insert(desc.full_name().to_string(), well_known_type(desc));
+        return;
+    }
+
+    // Insert a placeholder right away so that recursive messages don't loop forever
+    definitions.insert(desc.full_name().to_string(), Value::Null);
+
+    let mut properties = Map::new();
+    for field in desc.fields() {
+        let mut schema = field_schema(definitions, &field);
+        let schema_obj = schema.as_object_mut().expect("field schemas are objects");
+
+        let mut description = source_info::field_info(&field)
+            .comments()
+            .unwrap_or_default();
+        if let Some(oneof) = field
+            .containing_oneof()
+            .filter(|_| !field.field_descriptor_proto().proto3_optional())
+        {
+            let others: Vec<String> = oneof
+                .fields()
+                .filter(|other| other.name() != field.name())
+                .map(|other| format!("`{}`", other.json_name()))
+                .collect();
+            if !others.is_empty() {
+                if !description.is_empty() {
+                    description.push_str("\n\n");
+                }
+                description.push_str(&format!(
+                    "Part of oneof `{}`, set at most one of this and {}.",
+                    oneof.name(),
+                    others.join(", ")
+                ));
+            }
+        }
+        if !description.is_empty() {
+            schema_obj.insert("description".to_string(), Value::String(description));
+        }
+        if field
+            .field_descriptor_proto()
+            .options
+            .as_ref()
+            .map_or(false, |options| options.deprecated())
+        {
+            schema_obj.insert("deprecated".to_string(), Value::Bool(true));
+        }
+
+        // Parsers also accept the original field name, but don't suggest both
+        if field.name() != field.json_name() {
+            let mut alias = schema.clone();
+            alias["doNotSuggest"] = Value::Bool(true);
+            properties.insert(field.name().to_string(), alias);
+        }
+        properties.insert(field.json_name().to_string(), schema);
+    }
+
+    let mut definition = json!({
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    });
+    if let Some(comments) = source_info::message_info(desc).comments() {
+        definition["description"] = Value::String(comments);
+    }
+    definitions.insert(desc.full_name().to_string(), definition);
+}
+
+fn add_enum(definitions: &mut Map<String, Value>, desc: &EnumDescriptor) {
+    if definitions.contains_key(desc.full_name()) {
+        return;
+    }
+    if desc.full_name() == "google.protobuf.NullValue" {
+        definitions.insert(desc.full_name().to_string(), json!({ "type": "null" }));
+        return;
+    }
+
+    let names: Vec<String> = desc.values().map(|value| value.name().to_owned()).collect();
+    let descriptions: Vec<String> = desc
+        .values()
+        .map(|value| {
+            source_info::enum_value_info(&value)
+                .comments()
+                .unwrap_or_default()
+        })
+        .collect();
+    let mut definition = json!({
+        "anyOf": [
+            { "type": "string", "enum": names, "enumDescriptions": descriptions },
+            // Numbers are accepted too but are not something to suggest
+            { "type": "integer", "doNotSuggest": true },
+        ],
+    });
+    if let Some(comments) = source_info::enum_info(desc).comments() {
+        definition["description"] = Value::String(comments);
+    }
+    definitions.insert(desc.full_name().to_string(), definition);
+}
+
+fn field_schema(definitions: &mut Map<String, Value>, field: &FieldDescriptor) -> Value {
+    if field.is_map() {
+        let entry = match field.kind() {
+            Kind::Message(entry) => entry,
+            _ => unreachable!("map fields are always messages"),
+        };
+        let key = entry.map_entry_key_field();
+        let value = entry.map_entry_value_field();
+        let mut schema = json!({
+            "type": "object",
+            "additionalProperties": kind_schema(definitions, &value.kind()),
+        });
+        match key.kind() {
+            Kind::String => (),
+            Kind::Bool => schema["propertyNames"] = json!({ "enum": ["true", "false"] }),
+            _ => schema["propertyNames"] = json!({ "pattern": "^-?[0-9]+$" }),
+        }
+        schema
+    } else if field.is_list() {
+        json!({
+            "type": "array",
+            "items": kind_schema(definitions, &field.kind()),
+        })
+    } else {
+        kind_schema(definitions, &field.kind())
+    }
+}
+
+fn kind_schema(definitions: &mut Map<String, Value>, kind: &Kind) -> Value {
+    match kind {
+        Kind::Double | Kind::Float => json!({
+            "anyOf": [
+                { "type": "number" },
+                { "type": "string", "enum": ["NaN", "Infinity", "-Infinity"] },
+            ],
+        }),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => json!({
+            "type": "integer",
+            "minimum": i32::MIN,
+            "maximum": i32::MAX,
+        }),
+        Kind::Uint32 | Kind::Fixed32 => json!({
+            "type": "integer",
+            "minimum": 0,
+            "maximum": u32::MAX,
+        }),
+        // Encoded as strings because doubles can't represent all of their values
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => json!({
+            "type": ["string", "integer"],
+            "pattern": "^-?[0-9]+$",
+            "defaultSnippets": [{ "label": "\"0\"", "body": "0" }],
+        }),
+        Kind::Uint64 | Kind::Fixed64 => json!({
+            "type": ["string", "integer"],
+            "pattern": "^[0-9]+$",
+            "minimum": 0,
+            "defaultSnippets": [{ "label": "\"0\"", "body": "0" }],
+        }),
+        Kind::Bool => json!({ "type": "boolean" }),
+        Kind::String => json!({ "type": "string" }),
+        Kind::Bytes => json!({
+            "type": "string",
+            "contentEncoding": "base64",
+            "pattern": "^[A-Za-z0-9+/_-]*={0,2}$",
+        }),
+        Kind::Enum(desc) => {
+            add_enum(definitions, desc);
+            json!({ "$ref": reference(desc.full_name()) })
+        }
+        Kind::Message(desc) => {
+            add_message(definitions, desc);
+            json!({ "$ref": reference(desc.full_name()) })
+        }
+    }
+}
+
+/// Schemas of the `google.protobuf` messages, which have a special JSON mapping.
+fn well_known_type(desc: &MessageDescriptor) -> Value {
+    match desc.full_name() {
+        "google.protobuf.Any" => json!({
+            "type": "object",
+            "properties": {
+                "@type": {
+                    "type": "string",
+                    "description": "URL of the type of the packed message, like `type.googleapis.com/package.Message`",
+                },
+            },
+            "required": ["@type"],
+        }),
+        "google.protobuf.Timestamp" => json!({
+            "type": "string",
+            "format": "date-time",
+            "description": "RFC 3339 date, like `1972-01-01T10:00:20.021Z`",
+        }),
+        "google.protobuf.Duration" => json!({
+            "type": "string",
+            "pattern": "^-?[0-9]+(\\.[0-9]{1,9})?s$",
+            "description": "Seconds with an `s` suffix, like `1.5s`",
+        }),
+        "google.protobuf.FieldMask" => json!({
+            "type": "string",
+            "description": "Comma separated field paths in camelCase, like `user.displayName,photo`",
+        }),
+        "google.protobuf.Struct" => json!({ "type": "object" }),
+        "google.protobuf.ListValue" => json!({ "type": "array" }),
+        "google.protobuf.Value" => json!({}),
+        "google.protobuf.Empty" => json!({ "type": "object", "additionalProperties": false }),
+        "google.protobuf.DoubleValue" | "google.protobuf.FloatValue" => {
+            json!({ "type": ["number", "string", "null"] })
+        }
+        "google.protobuf.Int32Value" | "google.protobuf.UInt32Value" => {
+            json!({ "type": ["integer", "null"] })
+        }
+        "google.protobuf.Int64Value" | "google.protobuf.UInt64Value" => {
+            json!({ "type": ["string", "integer", "null"], "pattern": "^-?[0-9]+$" })
+        }
+        "google.protobuf.BoolValue" => json!({ "type": ["boolean", "null"] }),
+        "google.protobuf.StringValue" | "google.protobuf.BytesValue" => {
+            json!({ "type": ["string", "null"] })
+        }
+        // Any other message in the package maps to an object like usual
+        _ => json!({ "type": "object" }),
+    }
+}
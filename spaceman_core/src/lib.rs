@@ -1,44 +1,112 @@
 use std::str::FromStr;
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
 use futures::Stream;
 use http::uri::PathAndQuery;
 use http::Uri;
-use hyper::client::HttpConnector;
 use hyper::Client;
 use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
 pub use prost_reflect::{DynamicMessage, MethodDescriptor, SerializeOptions, MessageDescriptor, Value, Kind};
+pub use prost_reflect::prost::Message;
+pub use prost_reflect::prost::bytes::Bytes;
 use tonic::body::BoxBody;
 use tonic::client::Grpc;
 use tonic::codec::Streaming;
+use tonic::metadata::MetadataMap;
 pub use tonic::{IntoRequest, IntoStreamingRequest};
-use tonic::{Request, Response};
+use tonic::{Request, Response, Status};
 
 pub use spaceman_types as types;
-use spaceman_types::endpoint::{Endpoint, TlsOptions};
-pub use metadata::Metadata;
+use spaceman_types::calloptions::CallOptions;
+use spaceman_types::endpoint::{ConnectionOptions, Endpoint, TlsOptions};
+pub use any::{message_from_json, message_to_json};
+pub use codec::Received;
+pub use metadata::{metadata_pairs, Metadata};
+pub use proxy::{parse_proxy_url, proxy_from_env};
 pub use repo::Repo;
+pub use retry::parse_code;
+pub use json_schema::json_schema;
+pub use schema::{is_well_known_type, message_schema};
 
+use crate::balance::Target;
 use crate::codec::DynamicCodec;
+use crate::connector::{Connector, PeerSlot, RecordPeer};
 
+mod any;
+mod balance;
+pub mod breaking;
 mod codec;
+mod conflicts;
+mod connector;
+pub mod descset;
+pub mod diff;
+mod docs;
+pub mod export;
+pub mod health;
+pub mod import;
+mod json_schema;
+pub mod lint;
 mod metadata;
+mod proxy;
 mod repo;
+mod retry;
+pub mod sample;
+mod schema;
+mod search;
+mod source_info;
+pub mod sources;
+mod symbols;
 mod tls;
+pub mod wire;
+
+/// Descriptor set compiled by the build script out of the protos under `proto`.
+pub(crate) const BUNDLED_DESCRIPTORS: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/bundled.desc"));
 
 /// A gRPC connection.
-pub struct Conn(Grpc<Client<HttpsConnector<HttpConnector>, BoxBody>>);
+pub struct Conn {
+    grpc: Grpc<RecordPeer<Client<HttpsConnector<Connector>, BoxBody>>>,
+    options: CallOptions,
+}
+
+/// Result of a call along with how it was carried out, which is known whether the call succeeded
+/// or not.
+pub struct CallOutcome<T> {
+    pub result: Result<T>,
+    /// Number of attempts made, retries included. Client-streaming and bidi calls are never
+    /// retried.
+    pub attempts: u32,
+    /// Address of the server that the call was sent to, if it got that far. When going through a
+    /// proxy, this is the address the proxy was asked to connect to.
+    pub peer: Option<String>,
+}
+
+impl<T> CallOutcome<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> CallOutcome<U> {
+        CallOutcome {
+            result: self.result.map(f),
+            attempts: self.attempts,
+            peer: self.peer,
+        }
+    }
+}
 
 impl Conn {
     #[allow(dead_code)]
     pub fn new(ep: &Endpoint) -> Result<Self> {
+        Self::with_options(ep, CallOptions::default())
+    }
+
+    pub fn with_options(ep: &Endpoint, options: CallOptions) -> Result<Self> {
+        let target = Target::parse(&ep.authority)?;
         let uri = Uri::builder()
             .scheme(if ep.tls.is_some() {
                 http::uri::Scheme::HTTPS
             } else {
                 http::uri::Scheme::HTTP
             })
-            .authority(ep.authority.clone())
+            .authority(target.origin())
             .path_and_query(PathAndQuery::from_static("/"))
             .build()?;
 
@@ -46,7 +114,7 @@ impl Conn {
             tls::make_rustls_config(tls)
         } else {
             // It shouldn't matter all that much what config we give here because the
-            // `HttpsConnector` should just forward any request to `HttpConnector` because of the
+            // `HttpsConnector` should just forward any request to `Connector` because of the
             // scheme defined above.
             tls::make_rustls_config(&TlsOptions {
                 no_check: true,
@@ -54,92 +122,206 @@ impl Conn {
             })
         }?;
 
+        let default_port = if ep.tls.is_some() { 443 } else { 80 };
+        let mut tcp_connector = Connector::new(target, ep.lb_policy, default_port, ep.proxy.clone());
+        if let Some(opts) = &ep.connection {
+            tcp_connector.set_nodelay(opts.tcp_nodelay);
+            tcp_connector.set_connect_timeout(opts.connect_timeout_ms.map(Duration::from_millis));
+        }
+
         let connector = HttpsConnectorBuilder::new().with_tls_config(rustls_config);
-        let connector = connector.https_or_http().enable_http2().wrap_connector({
-            let mut http_connector = HttpConnector::new();
-            http_connector.enforce_http(false);
-            http_connector
-        });
+        let connector = connector
+            .https_or_http()
+            .enable_http2()
+            .wrap_connector(tcp_connector);
 
-        let transport = Client::builder().pool_max_idle_per_host(0).http2_only(true).build(connector);
-        let client = Grpc::with_origin(transport, uri);
+        let mut builder = Client::builder();
+        builder.pool_max_idle_per_host(0).http2_only(true);
+
+        if let Some(opts) = &ep.connection {
+            // h2 panics on values outside of the range during the handshake
+            if let Some(max_frame_size) = opts.max_frame_size {
+                let range = ConnectionOptions::MAX_FRAME_SIZE_RANGE;
+                if !range.contains(&max_frame_size) {
+                    return Err(anyhow!(
+                        "max frame size must be between {} and {} bytes, not {max_frame_size}",
+                        range.start(),
+                        range.end()
+                    ));
+                }
+            }
+            builder
+                .http2_keep_alive_interval(opts.keepalive_interval_ms.map(Duration::from_millis))
+                .http2_keep_alive_while_idle(opts.keepalive_while_idle)
+                .http2_initial_stream_window_size(opts.initial_stream_window_size)
+                .http2_initial_connection_window_size(opts.initial_connection_window_size)
+                .http2_max_frame_size(opts.max_frame_size);
+            if let Some(keepalive_timeout_ms) = opts.keepalive_timeout_ms {
+                builder.http2_keep_alive_timeout(Duration::from_millis(keepalive_timeout_ms));
+            }
+        }
 
-        Ok(Self(client))
+        let transport = RecordPeer::new(builder.build(connector));
+        let grpc = Grpc::with_origin(transport, uri);
+
+        Ok(Self { grpc, options })
     }
 
     pub async fn unary(
         &self,
         md: &MethodDescriptor,
         req: Request<DynamicMessage>,
-    ) -> Result<Response<DynamicMessage>> {
-        let mut conn = self.0.clone();
-
-        conn.ready().await?;
+    ) -> CallOutcome<Response<Received>> {
+        let peer = PeerSlot::default();
+        let mut attempts = 0;
+        let metadata = req.metadata().clone();
+        let msg = req.into_inner();
 
-        let path = method_desc_to_path(md)?;
-        let codec = DynamicCodec::new(md.clone());
+        let result = async {
+            let path = method_desc_to_path(md)?;
+            retry::with_retries(
+                self.options.retry_policy.as_ref(),
+                |n| attempts = n,
+                || {
+                    let mut conn = self.grpc.clone();
+                    let req = request_for_attempt(msg.clone(), &metadata, &peer);
+                    let (path, codec) = (path.clone(), DynamicCodec::new(md.clone()));
+                    async move {
+                        conn.ready()
+                            .await
+                            .map_err(|err| Status::from_error(Box::new(err)))?;
+                        conn.unary(req, path, codec).await
+                    }
+                },
+            )
+            .await
+        }
+        .await;
 
-        conn.unary(req, path, codec).await.map_err(|err| err.into())
+        CallOutcome {
+            result,
+            attempts,
+            peer: peer.get(),
+        }
     }
 
     pub async fn client_streaming<S>(
         &self,
         md: &MethodDescriptor,
-        req: Request<S>,
-    ) -> Result<Response<DynamicMessage>>
+        mut req: Request<S>,
+    ) -> CallOutcome<Response<Received>>
     where
         S: Stream<Item = DynamicMessage> + Send + 'static,
     {
-        let mut conn = self.0.clone();
+        let peer = PeerSlot::default();
+        req.extensions_mut().insert(peer.clone());
 
-        conn.ready().await?;
+        let result = async {
+            let mut conn = self.grpc.clone();
 
-        let path = method_desc_to_path(md)?;
-        let codec = DynamicCodec::new(md.clone());
+            conn.ready().await?;
 
-        conn.client_streaming(req, path, codec)
-            .await
-            .map_err(|err| err.into())
+            let path = method_desc_to_path(md)?;
+            let codec = DynamicCodec::new(md.clone());
+
+            conn.client_streaming(req, path, codec)
+                .await
+                .map_err(|err| err.into())
+        }
+        .await;
+
+        CallOutcome {
+            result,
+            attempts: 1,
+            peer: peer.get(),
+        }
     }
 
+    /// Retries only cover failures that happen before the server sends back the response
+    /// headers, which is when the first message is yet to be received.
     pub async fn server_streaming(
         &self,
         md: &MethodDescriptor,
         req: Request<DynamicMessage>,
-    ) -> Result<Response<Streaming<DynamicMessage>>> {
-        let mut conn = self.0.clone();
-
-        conn.ready().await?;
+    ) -> CallOutcome<Response<Streaming<Received>>> {
+        let peer = PeerSlot::default();
+        let mut attempts = 0;
+        let metadata = req.metadata().clone();
+        let msg = req.into_inner();
 
-        let path = method_desc_to_path(md)?;
-        let codec = DynamicCodec::new(md.clone());
-
-        conn.server_streaming(req, path, codec)
+        let result = async {
+            let path = method_desc_to_path(md)?;
+            retry::with_retries(
+                self.options.retry_policy.as_ref(),
+                |n| attempts = n,
+                || {
+                    let mut conn = self.grpc.clone();
+                    let req = request_for_attempt(msg.clone(), &metadata, &peer);
+                    let (path, codec) = (path.clone(), DynamicCodec::new(md.clone()));
+                    async move {
+                        conn.ready()
+                            .await
+                            .map_err(|err| Status::from_error(Box::new(err)))?;
+                        conn.server_streaming(req, path, codec).await
+                    }
+                },
+            )
             .await
-            .map_err(|err| err.into())
+        }
+        .await;
+
+        CallOutcome {
+            result,
+            attempts,
+            peer: peer.get(),
+        }
     }
 
     pub async fn bidi_streaming<S>(
         &self,
         md: &MethodDescriptor,
-        req: Request<S>,
-    ) -> Result<Response<Streaming<DynamicMessage>>>
+        mut req: Request<S>,
+    ) -> CallOutcome<Response<Streaming<Received>>>
     where
         S: Stream<Item = DynamicMessage> + Send + 'static,
     {
-        let mut conn = self.0.clone();
+        let peer = PeerSlot::default();
+        req.extensions_mut().insert(peer.clone());
 
-        conn.ready().await?;
+        let result = async {
+            let mut conn = self.grpc.clone();
 
-        let path = method_desc_to_path(md)?;
-        let codec = DynamicCodec::new(md.clone());
+            conn.ready().await?;
 
-        conn.streaming(req, path, codec)
-            .await
-            .map_err(|err| err.into())
+            let path = method_desc_to_path(md)?;
+            let codec = DynamicCodec::new(md.clone());
+
+            conn.streaming(req, path, codec)
+                .await
+                .map_err(|err| err.into())
+        }
+        .await;
+
+        CallOutcome {
+            result,
+            attempts: 1,
+            peer: peer.get(),
+        }
     }
 }
 
+/// Builds a fresh request for each attempt since tonic's requests can't be cloned.
+fn request_for_attempt(
+    msg: DynamicMessage,
+    metadata: &MetadataMap,
+    peer: &PeerSlot,
+) -> Request<DynamicMessage> {
+    let mut req = Request::new(msg);
+    *req.metadata_mut() = metadata.clone();
+    req.extensions_mut().insert(peer.clone());
+    req
+}
+
 fn method_desc_to_path(md: &MethodDescriptor) -> Result<PathAndQuery> {
     let full_name = md.full_name();
     let (namespace, method_name) = full_name
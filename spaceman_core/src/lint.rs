@@ -0,0 +1,689 @@
+//! Checks that protos follow common conventions, see `LintRule` for what each rule checks.
+
+use std::collections::HashSet;
+
+use prost_reflect::{Kind, MessageDescriptor};
+
+use spaceman_types::lint::{LintConfig, LintFinding, LintRule};
+
+use crate::source_info::{self, SourceInfo};
+use crate::Repo;
+
+const EMPTY: &str = "google.protobuf.Empty";
+
+/// Runs the rules of `config` over all the files of `repo`, except bundled ones and those it
+/// ignores. Findings are sorted by file and line.
+pub fn lint(repo: &Repo, config: &LintConfig) -> Vec<LintFinding> {
+    let mut linter = Linter {
+        config,
+        findings: Vec::new(),
+    };
+    let pool = repo.pool();
+    let is_linted = |file_name: &str| {
+        !repo.is_bundled(file_name)
+            && !config
+                .ignore
+                .iter()
+                .any(|prefix| file_name.starts_with(prefix.as_str()))
+    };
+
+    for file in pool.files().filter(|file| is_linted(file.name())) {
+        let info = source_info::package_info(&file);
+        let package = file.package_name();
+        if linter.runs(LintRule::Naming) {
+            for segment in package.split('.').filter(|segment| !segment.is_empty()) {
+                if !is_lower_snake_case(segment) {
+                    linter.push(
+                        LintRule::Naming,
+                        &info,
+                        package,
+                        format!("package segment {segment} should be lower_snake_case"),
+                    );
+                }
+            }
+        }
+        if linter.runs(LintRule::PackageDirectory) {
+            let directory = file
+                .name()
+                .rsplit_once('/')
+                .map_or("", |(directory, _)| directory);
+            let expected = directory.replace('/', ".");
+            if package.is_empty() {
+                linter.push(
+                    LintRule::PackageDirectory,
+                    &info,
+                    file.name(),
+                    "file has no package",
+                );
+            } else if package != expected {
+                linter.push(
+                    LintRule::PackageDirectory,
+                    &info,
+                    package,
+                    format!("package should be {expected:?} to match the directory of the file"),
+                );
+            }
+        }
+        if linter.runs(LintRule::Comments)
+            && file.file_descriptor_proto().source_code_info.is_none()
+        {
+            linter.push(
+                LintRule::Comments,
+                &info,
+                file.name(),
+                "compiled without source info, comments can't be checked",
+            );
+        }
+    }
+
+    for service in pool.services() {
+        let file = service.parent_file();
+        if !is_linted(file.name()) {
+            continue;
+        }
+        let has_source_info = file.file_descriptor_proto().source_code_info.is_some();
+        let info = source_info::service_info(&service);
+        if linter.runs(LintRule::Naming) && !is_pascal_case(service.name()) {
+            linter.push(
+                LintRule::Naming,
+                &info,
+                service.full_name(),
+                "service should be PascalCase",
+            );
+        }
+        if linter.runs(LintRule::Comments) && has_source_info && info.comments().is_none() {
+            linter.push(
+                LintRule::Comments,
+                &info,
+                service.full_name(),
+                "service has no comment",
+            );
+        }
+
+        for method in service.methods() {
+            let info = source_info::method_info(&method);
+            if linter.runs(LintRule::Naming) && !is_pascal_case(method.name()) {
+                linter.push(
+                    LintRule::Naming,
+                    &info,
+                    method.full_name(),
+                    "method should be PascalCase",
+                );
+            }
+            if linter.runs(LintRule::Comments) && has_source_info && info.comments().is_none() {
+                linter.push(
+                    LintRule::Comments,
+                    &info,
+                    method.full_name(),
+                    "method has no comment",
+                );
+            }
+            if linter.runs(LintRule::RpcMessageNames) {
+                for (message, suffix) in
+                    [(method.input(), "Request"), (method.output(), "Response")]
+                {
+                    let expected = format!("{}{suffix}", method.name());
+                    let is_allowed_empty = config.allow_empty && message.full_name() == EMPTY;
+                    if message.name() != expected && !is_allowed_empty {
+                        linter.push(
+                            LintRule::RpcMessageNames,
+                            &info,
+                            method.full_name(),
+                            format!("{} should be called {expected}", message.full_name()),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    // Looked for in all files, messages of ignored files can use those of linted ones too
+    let mut used = HashSet::new();
+    for service in pool.services() {
+        for method in service.methods() {
+            used.insert(method.input().full_name().to_string());
+            used.insert(method.output().full_name().to_string());
+        }
+    }
+    for message in pool.all_messages() {
+        for field in message.fields() {
+            if let Kind::Message(field_message) = field.kind() {
+                used.insert(field_message.full_name().to_string());
+            }
+        }
+    }
+
+    for message in pool.all_messages() {
+        let file = message.parent_file();
+        if message.is_map_entry() || !is_linted(file.name()) {
+            continue;
+        }
+        let has_source_info = file.file_descriptor_proto().source_code_info.is_some();
+        linter.message(&message, has_source_info);
+        if linter.runs(LintRule::UnusedMessages) && !used.contains(message.full_name()) {
+            linter.push(
+                LintRule::UnusedMessages,
+                &source_info::message_info(&message),
+                message.full_name(),
+                "message isn't used by any method or field",
+            );
+        }
+    }
+
+    for enum_desc in pool.all_enums() {
+        let file = enum_desc.parent_file();
+        if !is_linted(file.name()) {
+            continue;
+        }
+        let has_source_info = file.file_descriptor_proto().source_code_info.is_some();
+        let info = source_info::enum_info(&enum_desc);
+        if linter.runs(LintRule::Naming) && !is_pascal_case(enum_desc.name()) {
+            linter.push(
+                LintRule::Naming,
+                &info,
+                enum_desc.full_name(),
+                "enum should be PascalCase",
+            );
+        }
+        if linter.runs(LintRule::Comments) && has_source_info && info.comments().is_none() {
+            linter.push(
+                LintRule::Comments,
+                &info,
+                enum_desc.full_name(),
+                "enum has no comment",
+            );
+        }
+        if linter.runs(LintRule::Naming) {
+            for value in enum_desc.values() {
+                if !is_upper_snake_case(value.name()) {
+                    linter.push(
+                        LintRule::Naming,
+                        &source_info::enum_value_info(&value),
+                        value.full_name(),
+                        "enum value should be UPPER_SNAKE_CASE",
+                    );
+                }
+            }
+        }
+        if linter.runs(LintRule::EnumZeroValue) {
+            let expected = format!("{}_UNSPECIFIED", to_upper_snake_case(enum_desc.name()));
+            match enum_desc.get_value(0) {
+                Some(zero) if zero.name() != expected => linter.push(
+                    LintRule::EnumZeroValue,
+                    &source_info::enum_value_info(&zero),
+                    zero.full_name(),
+                    format!("zero value should be called {expected}"),
+                ),
+                Some(_) => (),
+                None => linter.push(
+                    LintRule::EnumZeroValue,
+                    &info,
+                    enum_desc.full_name(),
+                    format!("enum has no zero value, it should be called {expected}"),
+                ),
+            }
+        }
+    }
+
+    let mut findings = linter.findings;
+    findings.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    findings
+}
+
+struct Linter<'a> {
+    config: &'a LintConfig,
+    findings: Vec<LintFinding>,
+}
+
+impl Linter<'_> {
+    fn runs(&self, rule: LintRule) -> bool {
+        self.config.runs(rule)
+    }
+
+    fn push(
+        &mut self,
+        rule: LintRule,
+        info: &SourceInfo,
+        subject: &str,
+        message: impl Into<String>,
+    ) {
+        self.findings.push(LintFinding {
+            rule,
+            file: info.file.clone(),
+            line: info.line.map(|line| line as u32 + 1),
+            subject: subject.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn message(&mut self, message: &MessageDescriptor, has_source_info: bool) {
+        let info = source_info::message_info(message);
+        if self.runs(LintRule::Naming) && !is_pascal_case(message.name()) {
+            self.push(
+                LintRule::Naming,
+                &info,
+                message.full_name(),
+                "message should be PascalCase",
+            );
+        }
+        if self.runs(LintRule::Comments) && has_source_info && info.comments().is_none() {
+            self.push(
+                LintRule::Comments,
+                &info,
+                message.full_name(),
+                "message has no comment",
+            );
+        }
+        if self.runs(LintRule::Naming) {
+            for field in message.fields() {
+                if !is_lower_snake_case(field.name()) {
+                    self.push(
+                        LintRule::Naming,
+                        &source_info::field_info(&field),
+                        field.full_name(),
+                        "field should be lower_snake_case",
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn is_pascal_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn is_lower_snake_case(name: &str) -> bool {
+    is_snake_case(name)
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && !name.contains(|c: char| c.is_ascii_uppercase())
+}
+
+fn is_upper_snake_case(name: &str) -> bool {
+    is_snake_case(name)
+        && name.starts_with(|c: char| c.is_ascii_uppercase())
+        && !name.contains(|c: char| c.is_ascii_lowercase())
+}
+
+fn is_snake_case(name: &str) -> bool {
+    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.ends_with('_')
+        && !name.contains("__")
+}
+
+/// `HTTPServerError` becomes `HTTP_SERVER_ERROR`.
+fn to_upper_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut upper = String::new();
+    for (idx, c) in chars.iter().enumerate() {
+        if idx > 0 && c.is_ascii_uppercase() {
+            let prev = chars[idx - 1];
+            let next_is_lower = chars
+                .get(idx + 1)
+                .map_or(false, |next| next.is_ascii_lowercase());
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_is_lower)
+            {
+                upper.push('_');
+            }
+        }
+        upper.push(c.to_ascii_uppercase());
+    }
+    upper
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Subjects and messages of what `rule` finds in `protos`, in the order of the findings
+    fn findings(rule: LintRule, protos: &[(&str, &str)]) -> Vec<(String, String)> {
+        let config = LintConfig {
+            rules: vec![rule],
+            ..LintConfig::default()
+        };
+        lint(&Repo::from_protos(protos), &config)
+            .into_iter()
+            .map(|finding| {
+                assert_eq!(finding.rule, rule);
+                (finding.subject, finding.message)
+            })
+            .collect()
+    }
+
+    fn finding(subject: &str, message: &str) -> (String, String) {
+        (subject.to_string(), message.to_string())
+    }
+
+    #[test]
+    fn naming_finds_badly_cased_names() {
+        let proto = r#"
+            syntax = "proto3";
+            package Foo.bar;
+            service svc {
+                rpc doThing(bad_name) returns (bad_name);
+            }
+            message bad_name {
+                string someField = 1;
+            }
+            enum kind {
+                lower = 0;
+            }
+        "#;
+        assert_eq!(
+            findings(LintRule::Naming, &[("naming.proto", proto)]),
+            [
+                finding("Foo.bar", "package segment Foo should be lower_snake_case"),
+                finding("Foo.bar.svc", "service should be PascalCase"),
+                finding("Foo.bar.svc.doThing", "method should be PascalCase"),
+                finding("Foo.bar.bad_name", "message should be PascalCase"),
+                finding(
+                    "Foo.bar.bad_name.someField",
+                    "field should be lower_snake_case"
+                ),
+                finding("Foo.bar.kind", "enum should be PascalCase"),
+                finding("Foo.bar.lower", "enum value should be UPPER_SNAKE_CASE"),
+            ]
+        );
+    }
+
+    #[test]
+    fn naming_accepts_conventional_names() {
+        let proto = r#"
+            syntax = "proto3";
+            package foo.bar_baz;
+            service Greeter {
+                rpc SayHello(HelloRequest) returns (HelloResponse);
+            }
+            message HelloRequest {
+                string first_name = 1;
+                map<string, int32> counts_by_name = 2;
+            }
+            message HelloResponse {}
+            enum HTTPStatus {
+                HTTP_STATUS_UNSPECIFIED = 0;
+                NOT_FOUND_404 = 1;
+            }
+        "#;
+        assert_eq!(findings(LintRule::Naming, &[("naming.proto", proto)]), []);
+    }
+
+    #[test]
+    fn rpc_message_names_finds_mismatched_messages() {
+        let proto = r#"
+            syntax = "proto3";
+            package test;
+            import "google/protobuf/empty.proto";
+            service Svc {
+                rpc Get(Thing) returns (GetResponse);
+                rpc Ping(google.protobuf.Empty) returns (google.protobuf.Empty);
+            }
+            message Thing {}
+            message GetResponse {}
+        "#;
+        let protos = [("test.proto", proto)];
+        assert_eq!(
+            findings(LintRule::RpcMessageNames, &protos),
+            [finding(
+                "test.Svc.Get",
+                "test.Thing should be called GetRequest"
+            )]
+        );
+
+        let config = LintConfig {
+            rules: vec![LintRule::RpcMessageNames],
+            allow_empty: false,
+            ..LintConfig::default()
+        };
+        let messages: Vec<String> = lint(&Repo::from_protos(&protos), &config)
+            .into_iter()
+            .map(|finding| finding.message)
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "test.Thing should be called GetRequest",
+                "google.protobuf.Empty should be called PingRequest",
+                "google.protobuf.Empty should be called PingResponse",
+            ]
+        );
+    }
+
+    #[test]
+    fn rpc_message_names_accepts_matching_messages() {
+        let proto = r#"
+            syntax = "proto3";
+            package test;
+            import "google/protobuf/empty.proto";
+            service Svc {
+                rpc Get(GetRequest) returns (GetResponse);
+                rpc Ping(google.protobuf.Empty) returns (google.protobuf.Empty);
+            }
+            message GetRequest {}
+            message GetResponse {}
+        "#;
+        assert_eq!(
+            findings(LintRule::RpcMessageNames, &[("test.proto", proto)]),
+            []
+        );
+    }
+
+    #[test]
+    fn comments_finds_undocumented_definitions() {
+        let proto = r#"
+            syntax = "proto3";
+            package test;
+            service Svc {
+                rpc Get(Msg) returns (Msg);
+            }
+            message Msg {}
+            enum Kind {
+                KIND_UNSPECIFIED = 0;
+            }
+        "#;
+        assert_eq!(
+            findings(LintRule::Comments, &[("test.proto", proto)]),
+            [
+                finding("test.Svc", "service has no comment"),
+                finding("test.Svc.Get", "method has no comment"),
+                finding("test.Msg", "message has no comment"),
+                finding("test.Kind", "enum has no comment"),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_accepts_documented_definitions() {
+        let proto = r#"
+            syntax = "proto3";
+            package test;
+            // Serves things.
+            service Svc {
+                /* Gets a thing. */
+                rpc Get(Msg) returns (Msg);
+            }
+            // A thing.
+            message Msg {}
+            // Kinds of things.
+            enum Kind {
+                KIND_UNSPECIFIED = 0;
+            }
+        "#;
+        assert_eq!(findings(LintRule::Comments, &[("test.proto", proto)]), []);
+    }
+
+    #[test]
+    fn enum_zero_value_finds_misnamed_and_missing_zero_values() {
+        let proto3 = r#"
+            syntax = "proto3";
+            package test;
+            enum Color {
+                RED = 0;
+            }
+        "#;
+        let proto2 = r#"
+            syntax = "proto2";
+            package test;
+            enum HTTPStatus {
+                OK = 200;
+            }
+        "#;
+        assert_eq!(
+            findings(
+                LintRule::EnumZeroValue,
+                &[("color.proto", proto3), ("status.proto", proto2)]
+            ),
+            [
+                finding("test.RED", "zero value should be called COLOR_UNSPECIFIED"),
+                finding(
+                    "test.HTTPStatus",
+                    "enum has no zero value, it should be called HTTP_STATUS_UNSPECIFIED"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn enum_zero_value_accepts_unspecified_zero_values() {
+        let proto = r#"
+            syntax = "proto3";
+            package test;
+            enum Color {
+                COLOR_UNSPECIFIED = 0;
+                RED = 1;
+            }
+        "#;
+        assert_eq!(
+            findings(LintRule::EnumZeroValue, &[("test.proto", proto)]),
+            []
+        );
+    }
+
+    #[test]
+    fn package_directory_finds_mismatched_and_missing_packages() {
+        let mismatched = r#"syntax = "proto3"; package foo.baz;"#;
+        let missing = r#"syntax = "proto3";"#;
+        assert_eq!(
+            findings(
+                LintRule::PackageDirectory,
+                &[
+                    ("foo/bar/mismatched.proto", mismatched),
+                    ("missing.proto", missing)
+                ]
+            ),
+            [
+                finding(
+                    "foo.baz",
+                    r#"package should be "foo.bar" to match the directory of the file"#
+                ),
+                finding("missing.proto", "file has no package"),
+            ]
+        );
+    }
+
+    #[test]
+    fn package_directory_accepts_matching_packages() {
+        let proto = r#"syntax = "proto3"; package foo.bar;"#;
+        assert_eq!(
+            findings(LintRule::PackageDirectory, &[("foo/bar/baz.proto", proto)]),
+            []
+        );
+    }
+
+    #[test]
+    fn unused_messages_finds_orphans() {
+        let proto = r#"
+            syntax = "proto3";
+            package test;
+            service Svc {
+                rpc Get(Msg) returns (Msg);
+            }
+            message Msg {}
+            message Orphan {
+                message Nested {}
+            }
+        "#;
+        assert_eq!(
+            findings(LintRule::UnusedMessages, &[("test.proto", proto)]),
+            [
+                finding("test.Orphan", "message isn't used by any method or field"),
+                finding(
+                    "test.Orphan.Nested",
+                    "message isn't used by any method or field"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unused_messages_accepts_messages_used_by_fields() {
+        let proto = r#"
+            syntax = "proto3";
+            package test;
+            service Svc {
+                rpc Get(Msg) returns (Msg);
+            }
+            message Msg {
+                Part part = 1;
+                map<string, Part> parts = 2;
+            }
+            message Part {
+                Part parent = 1;
+            }
+        "#;
+        assert_eq!(
+            findings(LintRule::UnusedMessages, &[("test.proto", proto)]),
+            []
+        );
+    }
+
+    #[test]
+    fn ignored_files_and_skipped_rules_find_nothing() {
+        let proto = r#"syntax = "proto3"; package Bad; message bad {}"#;
+        let repo = Repo::from_protos(&[("vendor/bad.proto", proto)]);
+
+        let ignoring = LintConfig {
+            ignore: vec!["vendor/".to_string()],
+            ..LintConfig::default()
+        };
+        assert_eq!(lint(&repo, &ignoring), []);
+
+        let except_all = LintConfig {
+            except: LintRule::ALL.to_vec(),
+            ..LintConfig::default()
+        };
+        assert_eq!(lint(&repo, &except_all), []);
+    }
+
+    #[test]
+    fn findings_have_one_based_lines() {
+        let proto = "syntax = \"proto3\";\npackage test;\n\nmessage bad {}\n";
+        let findings = lint(
+            &Repo::from_protos(&[("test.proto", proto)]),
+            &LintConfig {
+                rules: vec![LintRule::Naming],
+                ..LintConfig::default()
+            },
+        );
+        assert_eq!(
+            findings,
+            [LintFinding {
+                rule: LintRule::Naming,
+                file: "test.proto".to_string(),
+                line: Some(4),
+                subject: "test.bad".to_string(),
+                message: "message should be PascalCase".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn converts_to_upper_snake_case() {
+        assert_eq!(to_upper_snake_case("Color"), "COLOR");
+        assert_eq!(to_upper_snake_case("HTTPServerError"), "HTTP_SERVER_ERROR");
+        assert_eq!(to_upper_snake_case("Status2xx"), "STATUS2XX");
+        assert_eq!(to_upper_snake_case("V2Api"), "V2_API");
+    }
+}
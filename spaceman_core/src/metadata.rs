@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::str::FromStr;
 
 use anyhow::{anyhow, Result};
-use tonic::metadata::{Ascii, Binary, MetadataKey, MetadataMap, MetadataValue};
+use tonic::metadata::{Ascii, Binary, KeyAndValueRef, MetadataKey, MetadataMap, MetadataValue};
 
 #[derive(Default)]
 pub struct Metadata {
@@ -52,3 +52,22 @@ impl Metadata {
         Ok(result)
     }
 }
+
+/// Flattens metadata into key-value pairs. Values of binary keys are base64 encoded.
+pub fn metadata_pairs(map: &MetadataMap) -> Vec<(String, String)> {
+    map.iter()
+        .map(|entry| match entry {
+            KeyAndValueRef::Ascii(key, value) => (
+                key.to_string(),
+                value
+                    .to_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|_| String::from_utf8_lossy(value.as_bytes()).into_owned()),
+            ),
+            KeyAndValueRef::Binary(key, value) => (
+                key.to_string(),
+                value.to_bytes().map(base64::encode).unwrap_or_default(),
+            ),
+        })
+        .collect()
+}
@@ -1,16 +1,73 @@
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use prost_reflect::{
-    prost::Message, prost_types::FileDescriptorSet, DescriptorPool, MethodDescriptor,
+    prost::Message,
+    prost_types::{
+        DescriptorProto, EnumDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+        MethodDescriptorProto,
+    },
+    DescriptorPool, Kind, MessageDescriptor, MethodDescriptor,
 };
 
-use spaceman_types::repo::{MethodView, RepoView, ServiceView};
+use spaceman_types::docs::Docs;
+use spaceman_types::repo::{method_full_name, MethodView, ReloadSummary, RepoView, ServiceView};
+use spaceman_types::search::SearchHit;
+
+use crate::conflicts::{self, LoadedFile};
+use crate::docs::{self, OptionsIndex};
+use crate::search::SearchIndex;
+use crate::sources;
+use crate::symbols::SymbolIndex;
+use crate::BUNDLED_DESCRIPTORS;
 
 /// Stores protobuf descriptors.
-#[derive(Default, Clone)]
+///
+/// It starts out with the well-known types and the common Google API protos, so that descriptor
+/// sets don't need to include them. Loaded files with the same name or symbols take their place.
+#[derive(Clone)]
 pub struct Repo {
     pool: DescriptorPool,
+    // Every file in the pool, with the descriptor set it came from
+    files: Vec<LoadedFile>,
+    // Kept around because decoding them into the pool loses custom options, by the source they
+    // were read from. Dropped once none of their files are left
+    raw_descriptor_sets: Vec<(PathBuf, Vec<u8>)>,
+    // Built along with the pool, since it decodes all the raw descriptor sets again
+    options: OptionsIndex,
+    index: SearchIndex,
+    symbols: SymbolIndex,
+    later_overrides: bool,
+}
+
+impl Default for Repo {
+    fn default() -> Self {
+        let files: Vec<LoadedFile> = FileDescriptorSet::decode(BUNDLED_DESCRIPTORS)
+            .expect("bundled descriptors are valid")
+            .file
+            .into_iter()
+            .filter(|file| file.name().starts_with("google/"))
+            .map(LoadedFile::bundled)
+            .collect();
+        let pool = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+            file: files.iter().map(|file| file.proto.clone()).collect(),
+        })
+        .expect("bundled descriptors are valid");
+        let raw_descriptor_sets = vec![(
+            PathBuf::from(conflicts::BUNDLED_SOURCE),
+            BUNDLED_DESCRIPTORS.to_vec(),
+        )];
+        Repo {
+            options: options_index(&pool, &raw_descriptor_sets),
+            index: SearchIndex::new(&pool),
+            symbols: SymbolIndex::new(&pool),
+            pool,
+            files,
+            raw_descriptor_sets,
+            later_overrides: false,
+        }
+    }
 }
 
 impl Repo {
@@ -19,24 +76,84 @@ impl Repo {
         Repo::default()
     }
 
+    /// Makes files that are defined differently by a descriptor set added later replace the
+    /// earlier definition, rather than rejecting the later set.
+    pub fn set_later_overrides(&mut self, later_overrides: bool) {
+        self.later_overrides = later_overrides;
+    }
+
+    /// Adds all the descriptor sets that `source` stands for, see `sources`.
+    pub fn add_descriptors(&mut self, source: &str) -> Result<()> {
+        self.add_all_descriptors(&[source])
+    }
+
+    /// Adds all the descriptor sets that each of `sources` stands for, in this order. The pool is
+    /// only built once they are all merged, and nothing is added if any of them fails.
+    pub fn add_all_descriptors<S: AsRef<str>>(&mut self, sources: &[S]) -> Result<()> {
+        let mut paths = Vec::new();
+        for source in sources {
+            paths.extend(sources::expand(source.as_ref())?);
+        }
+        self.add_sets(&paths)
+    }
+
+    /// Adds a descriptor set. Files that were already added from other sets are skipped if
+    /// identical, otherwise the set is rejected with a report of the conflicts unless later sets
+    /// are allowed to override earlier ones.
     #[allow(dead_code)]
     pub fn add_descriptor(&mut self, path: &Path) -> Result<()> {
-        // Read whole file descriptor set to bytes vec
-        let content = std::fs::read(path).context("reading file descriptor set")?;
-        // Decode it
-        let file_desc_set =
-            FileDescriptorSet::decode(&content[..]).context("decoding file descriptor set")?;
-        // And add it to the pool
-        self.pool
-            .add_file_descriptor_set(file_desc_set)
-            .context("adding file descriptor set to pool")?;
+        self.add_sets(&[path.to_path_buf()])
+    }
+
+    fn add_sets(&mut self, paths: &[PathBuf]) -> Result<()> {
+        let mut sets = Vec::new();
+        for path in paths {
+            // Read whole file descriptor set to bytes vec
+            let content = std::fs::read(path)
+                .context("reading file descriptor set")
+                .with_context(|| format!("adding {}", path.display()))?;
+            sets.push((path.clone(), content));
+        }
+        self.add_encoded_sets(sets)
+    }
+
+    /// Adds encoded descriptor sets along with the paths they were read from.
+    fn add_encoded_sets(&mut self, sets: Vec<(PathBuf, Vec<u8>)>) -> Result<()> {
+        let mut files = self.files.clone();
+        let mut raw_descriptor_sets = self.raw_descriptor_sets.clone();
+        for (path, content) in sets {
+            FileDescriptorSet::decode(&content[..])
+                .context("decoding file descriptor set")
+                .and_then(|file_desc_set| {
+                    // Merge it with the files that are already there
+                    files = conflicts::merge(&files, file_desc_set, &path, self.later_overrides)?;
+                    Ok(())
+                })
+                .with_context(|| format!("adding {}", path.display()))?;
+            // A set read again from the same source supersedes the earlier read
+            raw_descriptor_sets.retain(|(source, _)| source != &path);
+            raw_descriptor_sets.push((path, content));
+        }
+        raw_descriptor_sets.retain(|(source, _)| files.iter().any(|file| &file.source == source));
+
+        // And build the pool anew, files that are replaced can't be removed from it
+        let pool = DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
+            file: files.iter().map(|file| file.proto.clone()).collect(),
+        })
+        .context("adding file descriptor sets to pool")?;
+        self.pool = pool;
+        self.files = files;
+        self.raw_descriptor_sets = raw_descriptor_sets;
+        self.options = options_index(&self.pool, &self.raw_descriptor_sets);
+        self.index = SearchIndex::new(&self.pool);
+        self.symbols = SymbolIndex::new(&self.pool);
         Ok(())
     }
 
     #[allow(dead_code)]
     pub fn view(&self) -> RepoView {
-        RepoView {
-            services: self.pool.services().map(|service| {
+        RepoView::new(
+            self.pool.services().map(|service| {
                 ServiceView {
                     name: service.name().to_string(),
                     full_name: service.full_name().to_string(),
@@ -53,21 +170,209 @@ impl Repo {
                     }).collect()
                 }
             }).collect()
-        }
+        )
     }
 
+    /// The method called `full_name`, which can also be written like `/package.Service/Method`.
     #[allow(dead_code)]
     pub fn find_method_desc(&self, full_name: &str) -> Option<MethodDescriptor> {
-        // TODO Someday in the future, I should probably find a way to make this
-        // lookup faster cause right now it takes
-        // O(|services| + |methods per service|)
-        let service = self
-            .pool
-            .services()
-            .find(|service| full_name.starts_with(service.full_name()))?;
-        let method = service
-            .methods()
-            .find(|method| method.full_name() == full_name)?;
-        Some(method)
+        self.symbols.method(&method_full_name(full_name))
+    }
+
+    /// Like `find_method_desc` but the error suggests methods with similar names.
+    pub fn find_method(&self, full_name: &str) -> Result<MethodDescriptor> {
+        let full_name = method_full_name(full_name);
+        if let Some(method) = self.symbols.method(&full_name) {
+            return Ok(method);
+        }
+        let suggestions = self.symbols.suggest_methods(&full_name);
+        if suggestions.is_empty() {
+            Err(anyhow!("no method called {full_name}"))
+        } else {
+            Err(anyhow!(
+                "no method called {full_name}, did you mean {}?",
+                suggestions.join(" or ")
+            ))
+        }
+    }
+
+    /// The message called `full_name`.
+    pub fn find_message(&self, full_name: &str) -> Result<MessageDescriptor> {
+        let full_name = full_name.trim().trim_start_matches('.');
+        self.symbols
+            .message(full_name)
+            .ok_or_else(|| anyhow!("no message called {full_name}"))
+    }
+
+    /// Documentation of the method called `full_name`.
+    pub fn method_docs(&self, full_name: &str) -> Option<Docs> {
+        let method = self.find_method_desc(full_name)?;
+        Some(docs::method_docs(&method, self.options_index()))
+    }
+
+    /// Documentation of the message or enum called `full_name`.
+    pub fn type_docs(&self, full_name: &str) -> Option<Docs> {
+        if let Some(desc) = self.symbols.message(full_name) {
+            Some(docs::message_docs(&desc, self.options_index()))
+        } else {
+            let desc = self.symbols.enumeration(full_name)?;
+            Some(docs::enum_docs(&desc, self.options_index()))
+        }
+    }
+
+    /// Fuzzy searches the names and comments of every definition, returning at most `limit` hits,
+    /// best first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        self.index.search(query, limit)
+    }
+
+    /// Methods added, removed or changed in this repo compared to `old`.
+    pub fn changes_since(&self, old: &Repo) -> ReloadSummary {
+        let methods = |repo: &Repo| -> BTreeMap<String, MethodDescriptor> {
+            repo.pool
+                .services()
+                .flat_map(|service| service.methods().collect::<Vec<_>>())
+                .map(|method| (method.full_name().to_string(), method))
+                .collect()
+        };
+        let (old_methods, new_methods) = (methods(old), methods(self));
+
+        let mut summary = ReloadSummary::default();
+        for (full_name, new_method) in &new_methods {
+            match old_methods.get(full_name) {
+                None => summary.added.push(full_name.clone()),
+                Some(old_method) if MethodShape::of(old_method) != MethodShape::of(new_method) => {
+                    summary.changed.push(full_name.clone())
+                }
+                Some(_) => (),
+            }
+        }
+        summary.removed = old_methods
+            .keys()
+            .filter(|full_name| !new_methods.contains_key(*full_name))
+            .cloned()
+            .collect();
+        summary
+    }
+
+    pub(crate) fn pool(&self) -> &DescriptorPool {
+        &self.pool
+    }
+
+    /// Every file in the pool, in the order they were added.
+    pub(crate) fn file_protos(&self) -> impl Iterator<Item = &FileDescriptorProto> {
+        self.files.iter().map(|file| &file.proto)
+    }
+
+    /// Whether the file called `name` ships with the crate rather than being loaded.
+    pub(crate) fn is_bundled(&self, name: &str) -> bool {
+        self.files
+            .iter()
+            .any(|file| file.is_bundled && file.proto.name() == name)
+    }
+
+    pub(crate) fn options_index(&self) -> &OptionsIndex {
+        &self.options
+    }
+}
+
+fn options_index(
+    pool: &DescriptorPool,
+    raw_descriptor_sets: &[(PathBuf, Vec<u8>)],
+) -> OptionsIndex {
+    let raw_descriptor_sets: Vec<&[u8]> = raw_descriptor_sets
+        .iter()
+        .map(|(_, raw)| &raw[..])
+        .collect();
+    OptionsIndex::new(pool, &raw_descriptor_sets)
+}
+
+// Everything that makes up the signature of a method: its own descriptor and those of all the
+// messages and enums reachable from its input and output
+#[derive(PartialEq)]
+struct MethodShape {
+    method: MethodDescriptorProto,
+    messages: BTreeMap<String, DescriptorProto>,
+    enums: BTreeMap<String, EnumDescriptorProto>,
+}
+
+impl MethodShape {
+    fn of(method: &MethodDescriptor) -> Self {
+        let mut shape = MethodShape {
+            method: method.method_descriptor_proto().clone(),
+            messages: BTreeMap::new(),
+            enums: BTreeMap::new(),
+        };
+        shape.visit(&method.input());
+        shape.visit(&method.output());
+        shape
     }
+
+    fn visit(&mut self, message: &MessageDescriptor) {
+        if self.messages.contains_key(message.full_name()) {
+            return;
+        }
+        self.messages.insert(
+            message.full_name().to_string(),
+            message.descriptor_proto().clone(),
+        );
+        for field in message.fields() {
+            match field.kind() {
+                Kind::Message(message) => self.visit(&message),
+                Kind::Enum(enum_desc) => {
+                    self.enums.insert(
+                        enum_desc.full_name().to_string(),
+                        enum_desc.enum_descriptor_proto().clone(),
+                    );
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl Repo {
+    /// Repo with the files compiled out of `protos`, pairs of file names and their source, added
+    /// as one descriptor set. See `compile_protos`.
+    pub(crate) fn from_protos(protos: &[(&str, &str)]) -> Repo {
+        let mut repo = Repo::new();
+        repo.add_encoded_sets(vec![(PathBuf::from("test.desc"), compile_protos(protos))])
+            .expect("test protos to be loadable");
+        repo
+    }
+}
+
+/// Compiles `protos`, pairs of file names and their source, into an encoded descriptor set with
+/// source info. They can import each other and the well-known types.
+#[cfg(test)]
+pub(crate) fn compile_protos(protos: &[(&str, &str)]) -> Vec<u8> {
+    use protox::file::{ChainFileResolver, File, FileResolver, GoogleFileResolver};
+
+    struct Sources(Vec<(String, String)>);
+
+    impl FileResolver for Sources {
+        fn open_file(&self, name: &str) -> Result<File, protox::Error> {
+            match self.0.iter().find(|(file_name, _)| file_name == name) {
+                Some((_, source)) => File::from_source(name, source),
+                None => Err(protox::Error::file_not_found(name)),
+            }
+        }
+    }
+
+    let mut resolver = ChainFileResolver::new();
+    resolver.add(Sources(
+        protos
+            .iter()
+            .map(|(name, source)| (name.to_string(), source.to_string()))
+            .collect(),
+    ));
+    resolver.add(GoogleFileResolver::new());
+
+    let mut compiler = protox::Compiler::with_file_resolver(resolver);
+    compiler.include_source_info(true);
+    compiler
+        .open_files(protos.iter().map(|(name, _)| name))
+        .expect("test protos to compile");
+    compiler.encode_file_descriptor_set()
 }
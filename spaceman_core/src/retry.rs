@@ -0,0 +1,236 @@
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use tonic::{Code, Status};
+
+use spaceman_types::calloptions::RetryPolicy;
+
+/// Name of the trailer that servers use to override the client's backoff, as per gRPC's retry
+/// design.
+const PUSHBACK_KEY: &str = "grpc-retry-pushback-ms";
+
+/// Parses a status code spelled like in gRPC's service config, e.g. `UNAVAILABLE`.
+pub fn parse_code(name: &str) -> Result<Code> {
+    let code = match name.trim().to_ascii_uppercase().as_str() {
+        "OK" => Code::Ok,
+        "CANCELLED" => Code::Cancelled,
+        "UNKNOWN" => Code::Unknown,
+        "INVALID_ARGUMENT" => Code::InvalidArgument,
+        "DEADLINE_EXCEEDED" => Code::DeadlineExceeded,
+        "NOT_FOUND" => Code::NotFound,
+        "ALREADY_EXISTS" => Code::AlreadyExists,
+        "PERMISSION_DENIED" => Code::PermissionDenied,
+        "RESOURCE_EXHAUSTED" => Code::ResourceExhausted,
+        "FAILED_PRECONDITION" => Code::FailedPrecondition,
+        "ABORTED" => Code::Aborted,
+        "OUT_OF_RANGE" => Code::OutOfRange,
+        "UNIMPLEMENTED" => Code::Unimplemented,
+        "INTERNAL" => Code::Internal,
+        "UNAVAILABLE" => Code::Unavailable,
+        "DATA_LOSS" => Code::DataLoss,
+        "UNAUTHENTICATED" => Code::Unauthenticated,
+        _ => return Err(anyhow!("unknown status code '{}'", name)),
+    };
+    Ok(code)
+}
+
+/// Runs `attempt` until it succeeds, fails with a status that is not retryable, or the policy runs
+/// out of attempts. `on_attempt` is told the number of each attempt before it starts.
+pub async fn with_retries<T, F, Fut>(
+    policy: Option<&RetryPolicy>,
+    mut on_attempt: impl FnMut(u32),
+    mut attempt: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Status>>,
+{
+    let policy = if let Some(policy) = policy {
+        policy
+    } else {
+        on_attempt(1);
+        return attempt().await.map_err(|status| status.into());
+    };
+
+    let retryable_codes = policy
+        .retryable_status_codes
+        .iter()
+        .map(|name| parse_code(name))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut backoff_ms = policy.initial_backoff_ms as f64;
+    let mut n = 1;
+    loop {
+        on_attempt(n);
+        let status = match attempt().await {
+            Ok(res) => return Ok(res),
+            Err(status) => status,
+        };
+
+        if n >= policy.max_attempts || !retryable_codes.contains(&status.code()) {
+            return Err(status.into());
+        }
+
+        let delay_ms = match pushback_ms(&status) {
+            Some(Some(delay_ms)) => delay_ms,
+            // The server asked us not to retry
+            Some(None) => return Err(status.into()),
+            None => rand::random::<f64>() * backoff_ms,
+        };
+        tokio::time::sleep(Duration::from_millis(delay_ms as u64)).await;
+
+        backoff_ms = (backoff_ms * policy.backoff_multiplier).min(policy.max_backoff_ms as f64);
+        n += 1;
+    }
+}
+
+/// Reads the server's pushback from the trailers: `None` if there is none, `Some(None)` if it
+/// forbids further retries, and `Some(Some(delay))` if it prescribes a delay.
+fn pushback_ms(status: &Status) -> Option<Option<f64>> {
+    let value = status.metadata().get(PUSHBACK_KEY)?;
+    let delay_ms = value
+        .to_str()
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|delay_ms| *delay_ms >= 0)
+        .map(|delay_ms| delay_ms as f64);
+    Some(delay_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::Instant;
+    use tonic::metadata::MetadataMap;
+
+    use super::*;
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 300,
+            backoff_multiplier: 2.0,
+            retryable_status_codes: vec!["UNAVAILABLE".to_string()],
+        }
+    }
+
+    fn pushback(delay_ms: &str) -> Status {
+        let mut metadata = MetadataMap::new();
+        metadata.insert(PUSHBACK_KEY, delay_ms.parse().unwrap());
+        Status::with_metadata(Code::Unavailable, "busy", metadata)
+    }
+
+    // Runs `with_retries` over `results`, one per attempt, and returns the outcome along with the
+    // numbers the attempts were given and when they started
+    async fn run(
+        policy: Option<&RetryPolicy>,
+        results: Vec<Result<&'static str, Status>>,
+    ) -> (Result<&'static str>, Vec<u32>, Vec<Instant>) {
+        let mut results = results.into_iter();
+        let mut numbers = Vec::new();
+        let mut starts = Vec::new();
+        let result = with_retries(
+            policy,
+            |n| numbers.push(n),
+            || {
+                starts.push(Instant::now());
+                let result = results.next().expect("more attempts than results");
+                async move { result }
+            },
+        )
+        .await;
+        (result, numbers, starts)
+    }
+
+    fn gaps(starts: &[Instant]) -> Vec<Duration> {
+        starts
+            .windows(2)
+            .map(|pair| pair[1].duration_since(pair[0]))
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn no_policy_makes_a_single_attempt() {
+        let (result, numbers, _) = run(None, vec![Err(Status::unavailable("down"))]).await;
+        assert!(result.is_err());
+        assert_eq!(numbers, [1]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retryable_codes_are_retried_until_max_attempts() {
+        let failures = vec![Err(Status::unavailable("down")); 3];
+        let (result, numbers, _) = run(Some(&policy(3)), failures).await;
+        let status = result.unwrap_err().downcast::<Status>().unwrap();
+        assert_eq!(status.code(), Code::Unavailable);
+        assert_eq!(numbers, [1, 2, 3]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn success_stops_retrying() {
+        let results = vec![Err(Status::unavailable("down")), Ok("done")];
+        let (result, numbers, _) = run(Some(&policy(5)), results).await;
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(numbers, [1, 2]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn other_codes_are_not_retried() {
+        let results = vec![Err(Status::invalid_argument("bad"))];
+        let (result, numbers, _) = run(Some(&policy(3)), results).await;
+        assert!(result.is_err());
+        assert_eq!(numbers, [1]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backoff_grows_up_to_its_cap() {
+        let failures = vec![Err(Status::unavailable("down")); 5];
+        let (_, _, starts) = run(Some(&policy(5)), failures).await;
+        let gaps = gaps(&starts);
+        assert_eq!(gaps.len(), 4);
+        let bounds = [100, 200, 300, 300].map(Duration::from_millis);
+        for (gap, bound) in gaps.into_iter().zip(bounds) {
+            assert!(gap <= bound, "{gap:?} > {bound:?}");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn pushback_replaces_backoff() {
+        let results = vec![Err(pushback("5000")), Ok("done")];
+        let (result, numbers, starts) = run(Some(&policy(3)), results).await;
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(numbers, [1, 2]);
+        assert_eq!(gaps(&starts), [Duration::from_millis(5000)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn negative_pushback_stops_retrying() {
+        let (result, numbers, _) = run(Some(&policy(3)), vec![Err(pushback("-1"))]).await;
+        assert!(result.is_err());
+        assert_eq!(numbers, [1]);
+    }
+
+    #[tokio::test]
+    async fn unknown_codes_in_the_policy_are_an_error() {
+        let policy = RetryPolicy {
+            retryable_status_codes: vec!["UNAVAILABLE".to_string(), "FLAKY".to_string()],
+            ..policy(3)
+        };
+        let (result, numbers, _) = run(Some(&policy), vec![]).await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "unknown status code 'FLAKY'"
+        );
+        assert!(numbers.is_empty());
+    }
+
+    #[test]
+    fn codes_are_parsed_case_insensitively() {
+        assert_eq!(parse_code(" unavailable ").unwrap(), Code::Unavailable);
+        assert_eq!(
+            parse_code("DEADLINE_EXCEEDED").unwrap(),
+            Code::DeadlineExceeded
+        );
+        assert!(parse_code("NOPE").is_err());
+    }
+}
@@ -0,0 +1,861 @@
+//! Made up, but plausible, messages to start requests from.
+//!
+//! Values are picked by looking at the kind and the name of each field, so that an `email` gets
+//! an email address and a `page_size` a small number. Constraints of protoc-gen-validate
+//! (`validate.rules`) and protovalidate (`buf.validate.field`) narrow them down further: bounds
+//! of numbers, lengths, prefixes, suffixes and formats of strings, allowed values and the number
+//! of items of repeated and map fields. Regular expressions aren't followed.
+//!
+//! The same seed makes the same message on every platform and build, as long as the descriptors
+//! don't change.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use prost_reflect::prost::bytes::Bytes;
+use prost_reflect::{
+    DynamicMessage, FieldDescriptor, Kind, MapKey, MessageDescriptor, ReflectMessage,
+    SerializeOptions, Value,
+};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use spaceman_types::sample::SampleOptions;
+
+use crate::docs::OptionsIndex;
+use crate::Repo;
+
+/// Extensions of `FieldOptions` that hold the constraints of a field.
+const RULES_EXTENSIONS: &[&str] = &["validate.rules", "buf.validate.field"];
+
+// Somewhere in late 2023, so that timestamps look recent but don't depend on the clock
+const BASE_TIMESTAMP: i64 = 1_700_000_000;
+
+const FIRST_NAMES: &[&str] = &["Alice", "Bob", "Carol", "Dave", "Erin", "Frank", "Grace"];
+const LAST_NAMES: &[&str] = &[
+    "Smith", "Rossi", "Müller", "Tanaka", "Silva", "Novak", "Kim",
+];
+const WORDS: &[&str] = &[
+    "alpha", "bravo", "comet", "delta", "ember", "falcon", "galaxy", "harbor", "orbit", "nebula",
+];
+const CITIES: &[&str] = &["Lisbon", "Turin", "Osaka", "Denver", "Nairobi", "Oslo"];
+const COUNTRIES: &[&str] = &["PT", "IT", "JP", "US", "KE", "NO"];
+const COLORS: &[&str] = &["red", "green", "blue", "yellow", "purple"];
+const CURRENCIES: &[&str] = &["USD", "EUR", "JPY", "GBP"];
+
+/// A sample of `desc`, see the module docs.
+pub fn sample_message(
+    repo: &Repo,
+    desc: &MessageDescriptor,
+    options: &SampleOptions,
+) -> DynamicMessage {
+    let rng = match options.seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => ChaCha8Rng::from_entropy(),
+    };
+    let mut sampler = Sampler {
+        rng,
+        options,
+        index: options.constraints.then(|| repo.options_index()),
+    };
+    sampler.message(desc, options.max_depth, desc.name())
+}
+
+/// `msg` as JSON, like `message_to_json`, with the entries of maps sorted by key so that the same
+/// message always prints the same way.
+pub fn to_sorted_json(
+    msg: &DynamicMessage,
+    options: &SerializeOptions,
+) -> Result<serde_json::Value> {
+    let mut json = crate::message_to_json(msg, serde_json::value::Serializer, options)?;
+    sort_maps(&mut json, &msg.descriptor());
+    Ok(json)
+}
+
+struct Sampler<'a> {
+    rng: ChaCha8Rng,
+    options: &'a SampleOptions,
+    // Only there if constraints are followed
+    index: Option<&'a OptionsIndex>,
+}
+
+// Constraints of a field, those of its items for repeated and map fields
+#[derive(Default)]
+struct Rules {
+    constant: Option<Value>,
+    allowed: Vec<Value>,
+    gt: Option<f64>,
+    gte: Option<f64>,
+    lt: Option<f64>,
+    lte: Option<f64>,
+    min_len: Option<usize>,
+    max_len: Option<usize>,
+    prefix: Option<String>,
+    suffix: Option<String>,
+    contains: Option<String>,
+    // Name of a well-known string format, like `email`
+    format: Option<String>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+}
+
+impl Sampler<'_> {
+    // `name` is that of the field holding the message, used to pick values of wrapper types
+    fn message(&mut self, desc: &MessageDescriptor, depth: u32, name: &str) -> DynamicMessage {
+        if let Some(message) = self.well_known(desc, name) {
+            return message;
+        }
+
+        // Only one field of each oneof can be set. They are picked in the order of the
+        // descriptor, so that the seed decides which
+        let mut chosen: HashMap<String, u32> = HashMap::new();
+        for oneof in desc.oneofs() {
+            let numbers: Vec<u32> = oneof
+                .fields()
+                .filter(|field| real_oneof(field).is_some())
+                .map(|field| field.number())
+                .collect();
+            if let Some(number) = numbers.choose(&mut self.rng) {
+                chosen.insert(oneof.name().to_string(), *number);
+            }
+        }
+
+        let mut message = DynamicMessage::new(desc.clone());
+        for field in desc.fields() {
+            if let Some(oneof) = real_oneof(&field) {
+                if chosen.get(&oneof) != Some(&field.number()) {
+                    continue;
+                }
+            }
+            if let Some(value) = self.field(&field, depth) {
+                message.set_field(&field, value);
+            }
+        }
+        message
+    }
+
+    fn field(&mut self, field: &FieldDescriptor, depth: u32) -> Option<Value> {
+        let rules = self.rules(field);
+        if field.is_map() {
+            let entry = match field.kind() {
+                Kind::Message(entry) => entry,
+                _ => unreachable!("map fields are always messages"),
+            };
+            let key_kind = entry.map_entry_key_field().kind();
+            let value_kind = entry.map_entry_value_field().kind();
+            let mut map = HashMap::new();
+            for _ in 0..self.items(&rules) {
+                let key = self.single(&key_kind, &format!("{}_key", field.name()), &rules, depth);
+                let value = self.single(&value_kind, field.name(), &Rules::default(), depth);
+                if let (Some(key), Some(value)) = (key.and_then(map_key), value) {
+                    map.insert(key, value);
+                }
+            }
+            Some(Value::Map(map))
+        } else if field.is_list() {
+            let kind = field.kind();
+            let items: Option<Vec<Value>> = (0..self.items(&rules))
+                .map(|_| self.single(&kind, field.name(), &rules, depth))
+                .collect();
+            items.map(Value::List)
+        } else {
+            self.single(&field.kind(), field.name(), &rules, depth)
+        }
+    }
+
+    // At least one item unless `max_items` is 0, constraints asking for more win over it
+    fn items(&mut self, rules: &Rules) -> usize {
+        let max = rules.max_items.unwrap_or(self.options.max_items);
+        let min = rules.min_items.unwrap_or_else(|| max.min(1));
+        let max = max.max(min);
+        self.rng.gen_range(min..=max)
+    }
+
+    // A value for a field, or an item of it, of kind `kind`. Messages are only made while
+    // `depth` allows, well-known types aside
+    fn single(&mut self, kind: &Kind, name: &str, rules: &Rules, depth: u32) -> Option<Value> {
+        if let Some(constant) = &rules.constant {
+            return Some(of_kind(constant.clone(), kind));
+        }
+        if let Some(allowed) = rules.allowed.choose(&mut self.rng) {
+            return Some(of_kind(allowed.clone(), kind));
+        }
+
+        let name = name.to_lowercase();
+        Some(match kind {
+            Kind::Message(desc) => {
+                // Can't make up the type of the content
+                if desc.full_name() == "google.protobuf.Any" {
+                    return None;
+                }
+                if is_well_known(desc) {
+                    Value::Message(self.message(desc, 0, &name))
+                } else if depth == 0 {
+                    return None;
+                } else {
+                    Value::Message(self.message(desc, depth - 1, &name))
+                }
+            }
+            Kind::Enum(desc) => {
+                // The zero value usually means that the field wasn't set
+                let numbers: Vec<i32> = desc.values().map(|value| value.number()).collect();
+                let non_zero: Vec<i32> = numbers.iter().copied().filter(|&n| n != 0).collect();
+                let candidates = if non_zero.is_empty() {
+                    numbers
+                } else {
+                    non_zero
+                };
+                Value::EnumNumber(candidates.choose(&mut self.rng).copied().unwrap_or(0))
+            }
+            Kind::Bool => Value::Bool(self.rng.gen()),
+            Kind::String => Value::String(self.string(&name, rules)),
+            Kind::Bytes => {
+                let len = self.length(rules, 4, 12);
+                Value::Bytes(Bytes::from(
+                    (0..len).map(|_| self.rng.gen::<u8>()).collect::<Vec<u8>>(),
+                ))
+            }
+            Kind::Float => Value::F32(self.float(&name, rules) as f32),
+            Kind::Double => Value::F64(self.float(&name, rules)),
+            Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => {
+                Value::I32(self.integer(&name, rules, i32::MIN as f64, i32::MAX as f64) as i32)
+            }
+            Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => {
+                Value::I64(self.integer(&name, rules, i64::MIN as f64, i64::MAX as f64) as i64)
+            }
+            Kind::Uint32 | Kind::Fixed32 => {
+                Value::U32(self.integer(&name, rules, 0.0, u32::MAX as f64) as u32)
+            }
+            Kind::Uint64 | Kind::Fixed64 => {
+                Value::U64(self.integer(&name, rules, 0.0, u64::MAX as f64) as u64)
+            }
+        })
+    }
+
+    // Timestamps, durations, structs and wrappers get values that make sense for them
+    fn well_known(&mut self, desc: &MessageDescriptor, name: &str) -> Option<DynamicMessage> {
+        if !is_well_known(desc) {
+            return None;
+        }
+        let mut message = DynamicMessage::new(desc.clone());
+        match desc.full_name() {
+            "google.protobuf.Timestamp" => {
+                let seconds = BASE_TIMESTAMP + self.rng.gen_range(0..31_536_000);
+                message.set_field_by_name("seconds", Value::I64(seconds));
+            }
+            "google.protobuf.Duration" => {
+                message.set_field_by_name("seconds", Value::I64(self.rng.gen_range(1..3600)));
+            }
+            "google.protobuf.Struct" => {
+                let value_desc =
+                    desc.get_field_by_name("fields")
+                        .and_then(|fields| match fields.kind() {
+                            Kind::Message(entry) => match entry.map_entry_value_field().kind() {
+                                Kind::Message(value_desc) => Some(value_desc),
+                                _ => None,
+                            },
+                            _ => None,
+                        })?;
+                let mut fields = HashMap::new();
+                let mut text = DynamicMessage::new(value_desc.clone());
+                text.set_field_by_name("string_value", Value::String(self.word()));
+                fields.insert(MapKey::String("name".to_string()), Value::Message(text));
+                let mut number = DynamicMessage::new(value_desc);
+                number.set_field_by_name(
+                    "number_value",
+                    Value::F64(self.rng.gen_range(0..100) as f64),
+                );
+                fields.insert(MapKey::String("count".to_string()), Value::Message(number));
+                message.set_field_by_name("fields", Value::Map(fields));
+            }
+            "google.protobuf.Value" => {
+                message.set_field_by_name("string_value", Value::String(self.word()));
+            }
+            "google.protobuf.ListValue" => {
+                let value_desc =
+                    desc.get_field_by_name("values")
+                        .and_then(|values| match values.kind() {
+                            Kind::Message(value_desc) => Some(value_desc),
+                            _ => None,
+                        })?;
+                let values = (0..2)
+                    .map(|_| {
+                        let mut value = DynamicMessage::new(value_desc.clone());
+                        value.set_field_by_name("string_value", Value::String(self.word()));
+                        Value::Message(value)
+                    })
+                    .collect();
+                message.set_field_by_name("values", Value::List(values));
+            }
+            full_name if full_name.ends_with("Value") => {
+                // Wrappers, whose value is named after the field that holds them
+                let field = desc.get_field_by_name("value")?;
+                let value = self.single(&field.kind(), name, &Rules::default(), 0)?;
+                message.set_field(&field, value);
+            }
+            // Empty, FieldMask and the like are fine empty
+            _ => (),
+        }
+        Some(message)
+    }
+
+    fn string(&mut self, name: &str, rules: &Rules) -> String {
+        let format = rules.format.as_deref().unwrap_or_else(|| format_of(name));
+        let mut text = match format {
+            "email" => format!(
+                "{}.{}@example.com",
+                self.pick(FIRST_NAMES),
+                self.pick(LAST_NAMES)
+            )
+            .to_lowercase(),
+            "uuid" => {
+                let bytes: [u8; 16] = self.rng.gen();
+                let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+                format!(
+                    "{}-{}-4{}-a{}-{}",
+                    &hex[0..8],
+                    &hex[8..12],
+                    &hex[13..16],
+                    &hex[17..20],
+                    &hex[20..32]
+                )
+            }
+            "uri" => format!("https://example.com/{}", self.word()),
+            "hostname" => format!("{}.example.com", self.word()),
+            "ip" => format!(
+                "192.168.{}.{}",
+                self.rng.gen::<u8>(),
+                self.rng.gen_range(1..255)
+            ),
+            "phone" => format!("+1 555 01{:02}", self.rng.gen_range(0..100)),
+            "first_name" => self.pick(FIRST_NAMES).to_string(),
+            "last_name" => self.pick(LAST_NAMES).to_string(),
+            "name" => format!("{} {}", self.pick(FIRST_NAMES), self.pick(LAST_NAMES)),
+            "username" => {
+                format!("{}{}", self.pick(FIRST_NAMES), self.rng.gen_range(1..100)).to_lowercase()
+            }
+            "title" => {
+                let words: Vec<String> = (0..3).map(|_| capitalize(&self.word())).collect();
+                words.join(" ")
+            }
+            "text" => {
+                let words: Vec<String> = (0..8).map(|_| self.word()).collect();
+                format!("{}.", capitalize(&words.join(" ")))
+            }
+            "timestamp" => {
+                let seconds = BASE_TIMESTAMP + self.rng.gen_range(0..31_536_000);
+                rfc3339(seconds)
+            }
+            "city" => self.pick(CITIES).to_string(),
+            "country" => self.pick(COUNTRIES).to_string(),
+            "color" => self.pick(COLORS).to_string(),
+            "currency" => self.pick(CURRENCIES).to_string(),
+            "locale" => "en-US".to_string(),
+            "secret" => (0..16)
+                .map(|_| self.rng.sample(rand::distributions::Alphanumeric) as char)
+                .collect(),
+            "path" => format!("/{}/{}", self.word(), self.word()),
+            "version" => format!(
+                "{}.{}.{}",
+                self.rng.gen_range(0..4),
+                self.rng.gen_range(0..20),
+                self.rng.gen_range(0..10)
+            ),
+            _ => self.word(),
+        };
+
+        if let Some(prefix) = &rules.prefix {
+            text.insert_str(0, prefix);
+        }
+        if let Some(contains) = &rules.contains {
+            if !text.contains(contains.as_str()) {
+                text.push_str(contains);
+            }
+        }
+        let suffix = rules.suffix.clone().unwrap_or_default();
+        let len = text.chars().count() + suffix.chars().count();
+        let min_len = rules.min_len.unwrap_or(0);
+        if len < min_len {
+            text.extend(std::iter::repeat('x').take(min_len - len));
+        }
+        if let Some(max_len) = rules.max_len {
+            // Cut what was made up, not what the constraints asked for
+            let keep = max_len.saturating_sub(suffix.chars().count());
+            if text.chars().count() > keep {
+                text = text.chars().take(keep).collect();
+            }
+        }
+        text + &suffix
+    }
+
+    fn integer(&mut self, name: &str, rules: &Rules, type_min: f64, type_max: f64) -> f64 {
+        let has = |words: &[&str]| words.iter().any(|word| name.contains(word));
+        let default = if name == "id" || name.ends_with("_id") {
+            (1.0, 100_000.0)
+        } else if has(&["age"]) {
+            (18.0, 80.0)
+        } else if has(&["year"]) {
+            (1990.0, 2030.0)
+        } else if has(&["port"]) {
+            (1024.0, 65_535.0)
+        } else if has(&["page"]) {
+            (1.0, 10.0)
+        } else if has(&["count", "size", "limit", "quantity", "num"]) {
+            (1.0, 100.0)
+        } else {
+            (0.0, 1000.0)
+        };
+        let (low, high) = bounds(rules, default, 1.0);
+        let (low, high) = (low.ceil().max(type_min), high.floor().min(type_max));
+        if low >= high {
+            return low;
+        }
+        self.rng.gen_range(low..=high).round()
+    }
+
+    fn float(&mut self, name: &str, rules: &Rules) -> f64 {
+        let default = if name.starts_with("lat") {
+            (-90.0, 90.0)
+        } else if name.starts_with("lng") || name.starts_with("lon") {
+            (-180.0, 180.0)
+        } else if name.contains("ratio") || name.contains("rate") {
+            (0.0, 1.0)
+        } else if name.contains("price") || name.contains("amount") {
+            (1.0, 500.0)
+        } else {
+            (0.0, 100.0)
+        };
+        let (low, high) = bounds(rules, default, 0.01);
+        if low >= high {
+            return low;
+        }
+        (self.rng.gen_range(low..=high) * 100.0).round() / 100.0
+    }
+
+    fn length(&mut self, rules: &Rules, min: usize, max: usize) -> usize {
+        let min = rules.min_len.unwrap_or(min);
+        let max = rules.max_len.unwrap_or(max).max(min);
+        self.rng.gen_range(min..=max)
+    }
+
+    fn word(&mut self) -> String {
+        self.pick(WORDS).to_string()
+    }
+
+    fn pick(&mut self, choices: &[&'static str]) -> &'static str {
+        choices.choose(&mut self.rng).copied().unwrap_or_default()
+    }
+
+    fn rules(&self, field: &FieldDescriptor) -> Rules {
+        let mut rules = Rules::default();
+        let options = match self.index.and_then(|index| index.field_options(field)) {
+            Some(options) => options,
+            None => return rules,
+        };
+        for extension in options.descriptor().extensions() {
+            if RULES_EXTENSIONS.contains(&extension.full_name())
+                && options.has_extension(&extension)
+            {
+                if let Some(field_rules) = options.get_extension(&extension).as_message() {
+                    read_rules(field_rules, &mut rules);
+                }
+            }
+        }
+        rules
+    }
+}
+
+// Reads the `FieldRules` of protoc-gen-validate, or the `FieldConstraints` of protovalidate,
+// which name their fields the same way
+fn read_rules(field_rules: &DynamicMessage, rules: &mut Rules) {
+    // The rules for the kind of the field, like `string` or `repeated`, are in a oneof
+    let typed = field_rules.descriptor().fields().find_map(|field| {
+        let value = set_field(field_rules, field.name())?;
+        value
+            .as_message()
+            .cloned()
+            .map(|typed| (field.name().to_string(), typed))
+    });
+    let (kind, typed) = match typed {
+        Some(typed) => typed,
+        None => return,
+    };
+
+    if kind == "repeated" || kind == "map" {
+        let count = |name: &str| set_field(&typed, name).and_then(|value| as_f64(&value));
+        rules.min_items = count("min_items")
+            .or_else(|| count("min_pairs"))
+            .map(|n| n as usize);
+        rules.max_items = count("max_items")
+            .or_else(|| count("max_pairs"))
+            .map(|n| n as usize);
+        if let Some(items) = set_field(&typed, "items") {
+            if let Some(items) = items.as_message() {
+                read_rules(items, rules);
+            }
+        }
+        return;
+    }
+
+    let number = |name: &str| set_field(&typed, name).and_then(|value| as_f64(&value));
+    let text =
+        |name: &str| set_field(&typed, name).and_then(|value| value.as_str().map(str::to_string));
+    rules.constant = set_field(&typed, "const");
+    rules.allowed = set_field(&typed, "in")
+        .and_then(|value| value.as_list().map(<[Value]>::to_vec))
+        .unwrap_or_default();
+    rules.gt = number("gt");
+    rules.gte = number("gte");
+    rules.lt = number("lt");
+    rules.lte = number("lte");
+    let len = number("len").map(|n| n as usize);
+    rules.min_len = number("min_len").map(|n| n as usize).or(len);
+    rules.max_len = number("max_len").map(|n| n as usize).or(len);
+    rules.prefix = text("prefix");
+    rules.suffix = text("suffix");
+    rules.contains = text("contains");
+    rules.format = [
+        ("email", "email"),
+        ("hostname", "hostname"),
+        ("ip", "ip"),
+        ("ipv4", "ip"),
+        ("uri", "uri"),
+        ("uri_ref", "uri"),
+        ("address", "hostname"),
+        ("uuid", "uuid"),
+    ]
+    .into_iter()
+    .find(|(name, _)| set_field(&typed, name).and_then(|value| value.as_bool()) == Some(true))
+    .map(|(_, format)| format.to_string());
+}
+
+// The value of the field called `name`, if it was set
+fn set_field(message: &DynamicMessage, name: &str) -> Option<Value> {
+    let field = message.descriptor().get_field_by_name(name)?;
+    message
+        .has_field(&field)
+        .then(|| message.get_field(&field).into_owned())
+}
+
+// The range of values allowed by `rules`, `default` where they don't say. `step` is the
+// smallest difference between two values, used to turn exclusive bounds into inclusive ones
+fn bounds(rules: &Rules, default: (f64, f64), step: f64) -> (f64, f64) {
+    let span = default.1 - default.0;
+    let min = rules.gte.or(rules.gt.map(|gt| gt + step));
+    let max = rules.lte.or(rules.lt.map(|lt| lt - step));
+    match (min, max) {
+        (Some(min), Some(max)) => (min, max),
+        (Some(min), None) => (min, default.1.max(min + span)),
+        (None, Some(max)) => (default.0.min(max - span), max),
+        (None, None) => default,
+    }
+}
+
+/// Kinds of strings, and the words that make a field called after them hold one.
+const STRING_FORMATS: &[(&str, &[&str])] = &[
+    ("email", &["email"]),
+    ("uuid", &["uuid"]),
+    ("uri", &["url", "uri", "link", "website"]),
+    ("hostname", &["host", "domain"]),
+    ("phone", &["phone"]),
+    ("first_name", &["first_name", "given_name"]),
+    ("last_name", &["last_name", "surname", "family_name"]),
+    ("username", &["username", "login", "handle"]),
+    ("name", &["name", "author", "owner"]),
+    ("title", &["title", "subject", "headline"]),
+    (
+        "text",
+        &[
+            "description",
+            "comment",
+            "text",
+            "message",
+            "body",
+            "content",
+            "summary",
+            "note",
+        ],
+    ),
+    ("timestamp", &["time", "date"]),
+    ("city", &["city"]),
+    ("country", &["country"]),
+    ("color", &["color", "colour"]),
+    ("currency", &["currency"]),
+    ("locale", &["locale", "language", "lang"]),
+    ("secret", &["token", "secret", "password", "key"]),
+    ("path", &["path", "dir"]),
+    ("version", &["version"]),
+];
+
+// A kind of string that fits a field called `name`, see `Sampler::string`
+fn format_of(name: &str) -> &'static str {
+    // Short words only count as a whole word, `id` shouldn't match `valid`
+    let is = |word: &str| name == word || name.ends_with(&format!("_{word}"));
+    if is("id") {
+        return "uuid";
+    }
+    if is("ip") || is("ip_address") {
+        return "ip";
+    }
+    if is("at") {
+        return "timestamp";
+    }
+    STRING_FORMATS
+        .iter()
+        .find(|(_, words)| words.iter().any(|word| name.contains(word)))
+        .map_or("word", |(format, _)| format)
+}
+
+// The oneof that `field` is part of, unless it's the one made up for a proto3 optional field
+fn real_oneof(field: &FieldDescriptor) -> Option<String> {
+    field
+        .containing_oneof()
+        .filter(|_| !field.field_descriptor_proto().proto3_optional())
+        .map(|oneof| oneof.name().to_string())
+}
+
+// Sorts the entries of the maps in `json`, a message of type `desc`, and of the messages in it.
+// Keys are sorted as strings, numbers included
+fn sort_maps(json: &mut serde_json::Value, desc: &MessageDescriptor) {
+    let object = match json {
+        serde_json::Value::Object(object) => object,
+        _ => return,
+    };
+    if desc.full_name() == "google.protobuf.Struct" {
+        sort_object(object);
+        return;
+    }
+    for field in desc.fields() {
+        let value = match object.get_mut(field.json_name()) {
+            Some(value) => value,
+            None => match object.get_mut(field.name()) {
+                Some(value) => value,
+                None => continue,
+            },
+        };
+        let nested = match field.kind() {
+            Kind::Message(nested) => nested,
+            _ => continue,
+        };
+        if field.is_map() {
+            if let serde_json::Value::Object(entries) = value {
+                sort_object(entries);
+                if let Kind::Message(value_desc) = nested.map_entry_value_field().kind() {
+                    for entry in entries.values_mut() {
+                        sort_maps(entry, &value_desc);
+                    }
+                }
+            }
+        } else if field.is_list() {
+            if let serde_json::Value::Array(items) = value {
+                for item in items {
+                    sort_maps(item, &nested);
+                }
+            }
+        } else {
+            sort_maps(value, &nested);
+        }
+    }
+}
+
+fn sort_object(object: &mut serde_json::Map<String, serde_json::Value>) {
+    let mut entries: Vec<_> = std::mem::take(object).into_iter().collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    object.extend(entries);
+}
+
+fn is_well_known(desc: &MessageDescriptor) -> bool {
+    desc.full_name().starts_with("google.protobuf.")
+}
+
+// Constraints on enums list numbers, which have to be turned into enum values
+fn of_kind(value: Value, kind: &Kind) -> Value {
+    match (kind, value) {
+        (Kind::Enum(_), Value::I32(number)) => Value::EnumNumber(number),
+        (_, value) => value,
+    }
+}
+
+fn map_key(value: Value) -> Option<MapKey> {
+    Some(match value {
+        Value::Bool(key) => MapKey::Bool(key),
+        Value::I32(key) => MapKey::I32(key),
+        Value::I64(key) => MapKey::I64(key),
+        Value::U32(key) => MapKey::U32(key),
+        Value::U64(key) => MapKey::U64(key),
+        Value::String(key) => MapKey::String(key),
+        _ => return None,
+    })
+}
+
+fn as_f64(value: &Value) -> Option<f64> {
+    Some(match value {
+        Value::I32(number) => *number as f64,
+        Value::I64(number) => *number as f64,
+        Value::U32(number) => *number as f64,
+        Value::U64(number) => *number as f64,
+        Value::F32(number) => *number as f64,
+        Value::F64(number) => *number,
+        _ => return None,
+    })
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// Formats seconds since the epoch like `2023-11-14T22:13:20Z`
+fn rfc3339(seconds: i64) -> String {
+    let days = seconds.div_euclid(86_400);
+    let time = seconds.rem_euclid(86_400);
+    // Civil from days, by Howard Hinnant
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROTO: &str = r#"
+        syntax = "proto3";
+        package test;
+        import "google/protobuf/timestamp.proto";
+        enum Status { STATUS_UNSPECIFIED = 0; STATUS_ACTIVE = 3; STATUS_CLOSED = 7; }
+        message Node {
+            string name = 1;
+            Node child = 2;
+            repeated Node children = 3;
+        }
+        message Order {
+            string id = 1;
+            string email = 2;
+            int32 page_size = 3;
+            double price = 4;
+            Status status = 5;
+            repeated Status history = 6;
+            map<string, int64> counts = 7;
+            google.protobuf.Timestamp created_at = 8;
+            oneof payment {
+                string card = 9;
+                string iban = 10;
+                Node voucher = 11;
+            }
+            optional string note = 12;
+            Node root = 13;
+        }
+    "#;
+
+    fn sample(repo: &Repo, name: &str, seed: u64, max_depth: u32) -> DynamicMessage {
+        let options = SampleOptions {
+            seed: Some(seed),
+            max_depth,
+            ..SampleOptions::default()
+        };
+        sample_message(repo, &repo.find_message(name).unwrap(), &options)
+    }
+
+    fn field(message: &DynamicMessage, name: &str) -> Value {
+        message.get_field_by_name(name).unwrap().into_owned()
+    }
+
+    // How many levels of `Node` hang from `node`, itself included
+    fn depth(node: &DynamicMessage) -> u32 {
+        let child = node
+            .has_field_by_name("child")
+            .then(|| depth(field(node, "child").as_message().unwrap()));
+        let children = field(node, "children");
+        let children = children
+            .as_list()
+            .unwrap()
+            .iter()
+            .map(|child| depth(child.as_message().unwrap()));
+        1 + children.chain(child).max().unwrap_or(0)
+    }
+
+    #[test]
+    fn same_seed_same_message() {
+        let repo = Repo::from_protos(&[("test.proto", PROTO)]);
+        for seed in 0..10 {
+            assert_eq!(
+                sample(&repo, "test.Order", seed, 3),
+                sample(&repo, "test.Order", seed, 3)
+            );
+        }
+    }
+
+    #[test]
+    fn enums_are_declared_non_zero_values() {
+        let repo = Repo::from_protos(&[("test.proto", PROTO)]);
+        for seed in 0..50 {
+            let order = sample(&repo, "test.Order", seed, 3);
+            let history = field(&order, "history");
+            let statuses = history
+                .as_list()
+                .unwrap()
+                .iter()
+                .chain([&field(&order, "status")])
+                .map(|status| status.as_enum_number().unwrap())
+                .collect::<Vec<_>>();
+            assert!(
+                statuses.iter().all(|status| [3, 7].contains(status)),
+                "{statuses:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn one_field_of_each_oneof() {
+        let repo = Repo::from_protos(&[("test.proto", PROTO)]);
+        let mut picked = HashMap::new();
+        for seed in 0..50 {
+            let order = sample(&repo, "test.Order", seed, 3);
+            let set: Vec<&str> = ["card", "iban", "voucher"]
+                .into_iter()
+                .filter(|name| order.has_field_by_name(name))
+                .collect();
+            assert_eq!(set.len(), 1, "{set:?}");
+            *picked.entry(set[0]).or_insert(0) += 1;
+            // A proto3 optional field isn't a oneof to choose from
+            assert!(order.has_field_by_name("note"));
+        }
+        assert_eq!(picked.len(), 3, "{picked:?}");
+    }
+
+    #[test]
+    fn recursive_messages_stop_at_max_depth() {
+        let repo = Repo::from_protos(&[("test.proto", PROTO)]);
+        for max_depth in 0..4 {
+            let node = sample(&repo, "test.Node", 1, max_depth);
+            assert_eq!(depth(&node), max_depth + 1);
+
+            let order = sample(&repo, "test.Order", 1, max_depth);
+            if max_depth == 0 {
+                assert!(!order.has_field_by_name("root"));
+            } else {
+                assert_eq!(
+                    depth(field(&order, "root").as_message().unwrap()),
+                    max_depth
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use prost_reflect::{
+    Cardinality as ProtoCardinality, EnumDescriptor, FieldDescriptor, Kind, MessageDescriptor,
+};
+
+use spaceman_types::schema::{
+    Cardinality, EnumSchema, EnumValueSchema, FieldKind, FieldSchema, MessageSchema, Schema,
+};
+
+/// Describes `desc` and everything reachable from it in a form that can be sent to the GUI.
+pub fn message_schema(desc: &MessageDescriptor) -> Schema {
+    let mut schema = Schema {
+        root: desc.full_name().to_string(),
+        messages: Default::default(),
+        enums: Default::default(),
+    };
+    add_message(&mut schema, desc);
+    schema
+}
+
+/// Whether the message is one of the `google.protobuf` types with a special JSON mapping.
+pub fn is_well_known_type(full_name: &str) -> bool {
+    full_name.starts_with("google.protobuf.")
+}
+
+fn add_message(schema: &mut Schema, desc: &MessageDescriptor) {
+    if schema.messages.contains_key(desc.full_name()) || is_well_known_type(desc.full_name()) {
+        return;
+    }
+
+    // Insert a placeholder right away so that recursive messages don't loop forever
+    schema.messages.insert(
+        desc.full_name().to_string(),
+        MessageSchema {
+            name: desc.name().to_string(),
+            full_name: desc.full_name().to_string(),
+            fields: Vec::new(),
+            oneofs: Vec::new(),
+        },
+    );
+
+    let mut oneofs = Vec::new();
+    let mut fields = Vec::new();
+    for field in desc.fields() {
+        let oneof = field
+            .containing_oneof()
+            .filter(|_| !field.field_descriptor_proto().proto3_optional())
+            .map(|oneof| {
+                oneofs
+                    .iter()
+                    .position(|name| name == oneof.name())
+                    .unwrap_or_else(|| {
+                        oneofs.push(oneof.name().to_string());
+                        oneofs.len() - 1
+                    })
+            });
+
+        let (kind, cardinality) = if field.is_map() {
+            let entry = match field.kind() {
+                Kind::Message(entry) => entry,
+                _ => unreachable!("map fields are always messages"),
+            };
+            let key = field_kind(schema, &entry.map_entry_key_field());
+            let value = field_kind(schema, &entry.map_entry_value_field());
+            (value, Cardinality::Map { key })
+        } else if field.cardinality() == ProtoCardinality::Repeated {
+            (field_kind(schema, &field), Cardinality::Repeated)
+        } else {
+            (field_kind(schema, &field), Cardinality::Single)
+        };
+
+        fields.push(FieldSchema {
+            name: field.name().to_string(),
+            json_name: field.json_name().to_string(),
+            number: field.number(),
+            kind,
+            cardinality,
+            oneof,
+        });
+    }
+
+    let message = schema
+        .messages
+        .get_mut(desc.full_name())
+        .expect("placeholder inserted above");
+    message.fields = fields;
+    message.oneofs = oneofs;
+}
+
+fn add_enum(schema: &mut Schema, desc: &EnumDescriptor) {
+    if schema.enums.contains_key(desc.full_name()) {
+        return;
+    }
+    schema.enums.insert(
+        desc.full_name().to_string(),
+        EnumSchema {
+            name: desc.name().to_string(),
+            full_name: desc.full_name().to_string(),
+            values: desc
+                .values()
+                .map(|value| EnumValueSchema {
+                    name: value.name().to_string(),
+                    number: value.number(),
+                })
+                .collect(),
+        },
+    );
+}
+
+/// Kind of the field, also adding the message or enum it refers to to `schema`.
+fn field_kind(schema: &mut Schema, field: &FieldDescriptor) -> FieldKind {
+    match field.kind() {
+        Kind::Double => FieldKind::Double,
+        Kind::Float => FieldKind::Float,
+        Kind::Int32 => FieldKind::Int32,
+        Kind::Int64 => FieldKind::Int64,
+        Kind::Uint32 => FieldKind::Uint32,
+        Kind::Uint64 => FieldKind::Uint64,
+        Kind::Sint32 => FieldKind::Sint32,
+        Kind::Sint64 => FieldKind::Sint64,
+        Kind::Fixed32 => FieldKind::Fixed32,
+        Kind::Fixed64 => FieldKind::Fixed64,
+        Kind::Sfixed32 => FieldKind::Sfixed32,
+        Kind::Sfixed64 => FieldKind::Sfixed64,
+        Kind::Bool => FieldKind::Bool,
+        Kind::String => FieldKind::String,
+        Kind::Bytes => FieldKind::Bytes,
+        Kind::Enum(desc) => {
+            add_enum(schema, &desc);
+            FieldKind::Enum(desc.full_name().to_string())
+        }
+        Kind::Message(desc) => {
+            add_message(schema, &desc);
+            FieldKind::Message(desc.full_name().to_string())
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! Fuzzy search over every named definition in a `DescriptorPool` and over their comments.
+
+use std::collections::{HashMap, HashSet};
+
+use prost_reflect::{DescriptorPool, Kind, MessageDescriptor};
+
+use spaceman_types::search::{SearchHit, SymbolKind};
+
+use crate::source_info;
+
+/// Score of a hit that matched in the comments only, names always rank higher.
+const COMMENT_SCORE: i64 = 1;
+
+#[derive(Default, Clone)]
+pub(crate) struct SearchIndex {
+    symbols: Vec<Symbol>,
+}
+
+#[derive(Clone)]
+struct Symbol {
+    kind: SymbolKind,
+    name: String,
+    full_name: String,
+    parent: Option<String>,
+    comments: Option<String>,
+    methods: Vec<String>,
+}
+
+impl SearchIndex {
+    pub(crate) fn new(pool: &DescriptorPool) -> Self {
+        let usages = usages(pool);
+        let methods_using = |full_name: &str| usages.get(full_name).cloned().unwrap_or_default();
+
+        let mut symbols = Vec::new();
+        for service in pool.services() {
+            symbols.push(Symbol {
+                kind: SymbolKind::Service,
+                name: service.name().to_string(),
+                full_name: service.full_name().to_string(),
+                parent: None,
+                comments: source_info::service_info(&service).comments(),
+                methods: service
+                    .methods()
+                    .map(|method| method.full_name().to_string())
+                    .collect(),
+            });
+            for method in service.methods() {
+                symbols.push(Symbol {
+                    kind: SymbolKind::Method,
+                    name: method.name().to_string(),
+                    full_name: method.full_name().to_string(),
+                    parent: Some(service.full_name().to_string()),
+                    comments: source_info::method_info(&method).comments(),
+                    methods: vec![method.full_name().to_string()],
+                });
+            }
+        }
+        for message in pool.all_messages() {
+            // Map entries are an implementation detail of map fields
+            if message.is_map_entry() {
+                continue;
+            }
+            symbols.push(Symbol {
+                kind: SymbolKind::Message,
+                name: message.name().to_string(),
+                full_name: message.full_name().to_string(),
+                parent: None,
+                comments: source_info::message_info(&message).comments(),
+                methods: methods_using(message.full_name()),
+            });
+            for field in message.fields() {
+                symbols.push(Symbol {
+                    kind: SymbolKind::Field,
+                    name: field.name().to_string(),
+                    full_name: field.full_name().to_string(),
+                    parent: Some(message.full_name().to_string()),
+                    comments: source_info::field_info(&field).comments(),
+                    methods: methods_using(message.full_name()),
+                });
+            }
+        }
+        for desc in pool.all_enums() {
+            symbols.push(Symbol {
+                kind: SymbolKind::Enum,
+                name: desc.name().to_string(),
+                full_name: desc.full_name().to_string(),
+                parent: None,
+                comments: source_info::enum_info(&desc).comments(),
+                methods: methods_using(desc.full_name()),
+            });
+            for value in desc.values() {
+                symbols.push(Symbol {
+                    kind: SymbolKind::EnumValue,
+                    name: value.name().to_string(),
+                    full_name: value.full_name().to_string(),
+                    parent: Some(desc.full_name().to_string()),
+                    comments: source_info::enum_value_info(&value).comments(),
+                    methods: methods_using(desc.full_name()),
+                });
+            }
+        }
+        SearchIndex { symbols }
+    }
+
+    /// Returns at most `limit` hits for `query`, best first.
+    pub(crate) fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let words: Vec<&str> = query.split_whitespace().collect();
+
+        let mut hits: Vec<SearchHit> = self
+            .symbols
+            .iter()
+            .filter_map(|symbol| {
+                let name_score = [
+                    // Matching the short name is worth more than matching the full one
+                    fuzzy_score(&query, &symbol.name).map(|score| score * 2),
+                    fuzzy_score(&query, &symbol.full_name),
+                ]
+                .into_iter()
+                .flatten()
+                .max();
+                let (score, comment) = match name_score {
+                    Some(score) => (score, None),
+                    None => (COMMENT_SCORE, Some(matching_comment(&words, symbol)?)),
+                };
+                Some(SearchHit {
+                    kind: symbol.kind,
+                    full_name: symbol.full_name.clone(),
+                    parent: symbol.parent.clone(),
+                    comment,
+                    methods: symbol.methods.clone(),
+                    score,
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.full_name.len().cmp(&b.full_name.len()))
+                .then_with(|| a.full_name.cmp(&b.full_name))
+        });
+        hits.truncate(limit);
+        hits
+    }
+}
+
+/// Methods using each message and enum, by full name of the type.
+fn usages(pool: &DescriptorPool) -> HashMap<String, Vec<String>> {
+    let mut usages: HashMap<String, Vec<String>> = HashMap::new();
+    for method in pool
+        .services()
+        .flat_map(|service| service.methods().collect::<Vec<_>>())
+    {
+        let mut seen = HashSet::new();
+        let mut stack: Vec<MessageDescriptor> = vec![method.input(), method.output()];
+        while let Some(message) = stack.pop() {
+            if !seen.insert(message.full_name().to_string()) {
+                continue;
+            }
+            for field in message.fields() {
+                match field.kind() {
+                    Kind::Message(nested) => stack.push(nested),
+                    Kind::Enum(desc) => {
+                        seen.insert(desc.full_name().to_string());
+                    }
+                    _ => (),
+                }
+            }
+        }
+        for full_name in seen {
+            usages
+                .entry(full_name)
+                .or_default()
+                .push(method.full_name().to_string());
+        }
+    }
+    for methods in usages.values_mut() {
+        methods.sort();
+    }
+    usages
+}
+
+/// Scores `candidate` as a match for `query`, which must be lowercase. Every character of the query
+/// has to appear in the candidate in order, with runs of consecutive characters and characters
+/// at the start of words ranking higher. Returns `None` if the candidate doesn't match at all.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let lowercase = candidate.to_lowercase();
+    if lowercase == query {
+        return Some(1000);
+    }
+
+    let candidate: Vec<char> = candidate.chars().collect();
+    let lowercase: Vec<char> = lowercase.chars().collect();
+    // Lowercasing can change the number of characters, in which case positions don't line up
+    if candidate.len() != lowercase.len() {
+        return None;
+    }
+
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+    for wanted in query.chars() {
+        let found = position + lowercase[position..].iter().position(|c| *c == wanted)?;
+        score += 10;
+        if previous_match.map_or(false, |previous| previous + 1 == found) {
+            score += 15;
+        }
+        if is_word_start(&candidate, found) {
+            score += 20;
+        }
+        // Characters skipped in between cost a little
+        score -= (found - position) as i64;
+        previous_match = Some(found);
+        position = found + 1;
+    }
+
+    let lowercase: String = lowercase.into_iter().collect();
+    if lowercase.starts_with(query) {
+        score += 50;
+    } else if lowercase.contains(query) {
+        score += 30;
+    }
+    // Leftover characters make a match less precise
+    score -= (candidate.len() - query.chars().count()) as i64 / 4;
+    Some(score.max(COMMENT_SCORE + 1))
+}
+
+fn is_word_start(candidate: &[char], index: usize) -> bool {
+    match index.checked_sub(1).map(|previous| candidate[previous]) {
+        None => true,
+        Some('.' | '_') => true,
+        Some(previous) => previous.is_lowercase() && candidate[index].is_uppercase(),
+    }
+}
+
+/// First comment line that contains all of the query's words, if the comments contain them all.
+fn matching_comment(words: &[&str], symbol: &Symbol) -> Option<String> {
+    let comments = symbol.comments.as_ref()?;
+    let lowercase = comments.to_lowercase();
+    if !words.iter().all(|word| lowercase.contains(word)) {
+        return None;
+    }
+    let line = comments
+        .lines()
+        .find(|line| line.to_lowercase().contains(words[0]))
+        .unwrap_or_default();
+    Some(line.trim().to_string())
+}
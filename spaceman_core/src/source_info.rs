@@ -0,0 +1,220 @@
+//! Lookups into the `source_code_info` of file descriptors, which is where `protoc` stores the
+//! comments and source locations of each definition. Locations are keyed by the path of field
+//! numbers and indices that leads from the `FileDescriptorProto` to the definition.
+
+use prost_reflect::prost_types::{DescriptorProto, FileDescriptorProto};
+use prost_reflect::{
+    EnumDescriptor, EnumValueDescriptor, FieldDescriptor, FileDescriptor, MessageDescriptor,
+    MethodDescriptor, ServiceDescriptor,
+};
+
+// Field numbers from descriptor.proto
+const FILE_PACKAGE: i32 = 2;
+const FILE_MESSAGE_TYPE: i32 = 4;
+const FILE_ENUM_TYPE: i32 = 5;
+const FILE_SERVICE: i32 = 6;
+const MESSAGE_FIELD: i32 = 2;
+const MESSAGE_NESTED_TYPE: i32 = 3;
+const MESSAGE_ENUM_TYPE: i32 = 4;
+const ENUM_VALUE: i32 = 2;
+const SERVICE_METHOD: i32 = 2;
+
+/// Where a definition lives in its source file and the comments attached to it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceInfo {
+    /// Name of the file, relative to the compiler's root.
+    pub file: String,
+    /// Zero-based line of the start of the definition.
+    pub line: Option<i32>,
+    pub leading_comments: Option<String>,
+    pub trailing_comments: Option<String>,
+}
+
+impl SourceInfo {
+    /// Leading and trailing comments joined together, with surrounding whitespace trimmed.
+    pub fn comments(&self) -> Option<String> {
+        let parts: Vec<&str> = [&self.leading_comments, &self.trailing_comments]
+            .into_iter()
+            .flatten()
+            .map(|comment| comment.trim())
+            .filter(|comment| !comment.is_empty())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join("\n\n"))
+    }
+}
+
+/// Where the package of the file is declared.
+pub fn package_info(file: &FileDescriptor) -> SourceInfo {
+    lookup(file, Some(vec![FILE_PACKAGE]))
+}
+
+pub fn message_info(desc: &MessageDescriptor) -> SourceInfo {
+    lookup(&desc.parent_file(), message_path(desc))
+}
+
+pub fn field_info(field: &FieldDescriptor) -> SourceInfo {
+    lookup(&field.parent_message().parent_file(), field_path(field))
+}
+
+pub fn enum_info(desc: &EnumDescriptor) -> SourceInfo {
+    lookup(&desc.parent_file(), enum_path(desc))
+}
+
+pub fn enum_value_info(value: &EnumValueDescriptor) -> SourceInfo {
+    lookup(&value.parent_enum().parent_file(), enum_value_path(value))
+}
+
+pub fn service_info(desc: &ServiceDescriptor) -> SourceInfo {
+    lookup(
+        &desc.parent_file(),
+        service_path(desc.parent_file().file_descriptor_proto(), desc.name()),
+    )
+}
+
+pub fn method_info(method: &MethodDescriptor) -> SourceInfo {
+    lookup(&method.parent_service().parent_file(), method_path(method))
+}
+
+pub(crate) fn message_path(desc: &MessageDescriptor) -> Option<Vec<i32>> {
+    type_path(
+        desc.parent_file().file_descriptor_proto(),
+        desc.full_name(),
+        false,
+    )
+}
+
+pub(crate) fn field_path(field: &FieldDescriptor) -> Option<Vec<i32>> {
+    let message = field.parent_message();
+    let file = message.parent_file();
+    let proto = file.file_descriptor_proto();
+    let mut path = type_path(proto, message.full_name(), false)?;
+    let index = message_proto(proto, &path)?
+        .field
+        .iter()
+        .position(|candidate| candidate.number() == field.number() as i32)?;
+    path.extend([MESSAGE_FIELD, index as i32]);
+    Some(path)
+}
+
+pub(crate) fn enum_path(desc: &EnumDescriptor) -> Option<Vec<i32>> {
+    type_path(
+        desc.parent_file().file_descriptor_proto(),
+        desc.full_name(),
+        true,
+    )
+}
+
+pub(crate) fn enum_value_path(value: &EnumValueDescriptor) -> Option<Vec<i32>> {
+    let desc = value.parent_enum();
+    let mut path = enum_path(desc)?;
+    let index = desc
+        .values()
+        .position(|candidate| candidate.name() == value.name())?;
+    path.extend([ENUM_VALUE, index as i32]);
+    Some(path)
+}
+
+pub(crate) fn method_path(method: &MethodDescriptor) -> Option<Vec<i32>> {
+    let service = method.parent_service();
+    let mut path = service_path(
+        service.parent_file().file_descriptor_proto(),
+        service.name(),
+    )?;
+    let index = service
+        .methods()
+        .position(|candidate| candidate.name() == method.name())?;
+    path.extend([SERVICE_METHOD, index as i32]);
+    Some(path)
+}
+
+fn lookup(file: &FileDescriptor, path: Option<Vec<i32>>) -> SourceInfo {
+    let mut info = SourceInfo {
+        file: file.name().to_string(),
+        ..Default::default()
+    };
+    let (path, source_code_info) = match (path, &file.file_descriptor_proto().source_code_info) {
+        (Some(path), Some(source_code_info)) => (path, source_code_info),
+        // Descriptor sets compiled without `--include_source_info` carry no locations
+        _ => return info,
+    };
+    if let Some(location) = source_code_info
+        .location
+        .iter()
+        .find(|location| location.path == path)
+    {
+        info.line = location.span.first().copied();
+        info.leading_comments = location.leading_comments.clone();
+        info.trailing_comments = location.trailing_comments.clone();
+    }
+    info
+}
+
+/// Path to the message or enum called `full_name`, walking down nested messages.
+fn type_path(file: &FileDescriptorProto, full_name: &str, is_enum: bool) -> Option<Vec<i32>> {
+    let relative = match file.package() {
+        "" => full_name,
+        package => full_name.strip_prefix(package)?.strip_prefix('.')?,
+    };
+    let mut names: Vec<&str> = relative.split('.').collect();
+    let last = names.pop()?;
+
+    let mut path = Vec::new();
+    let mut parent: Option<&DescriptorProto> = None;
+    for name in names {
+        let siblings = parent.map_or(&file.message_type, |parent| &parent.nested_type);
+        let index = siblings.iter().position(|message| message.name() == name)?;
+        path.extend([
+            if parent.is_some() {
+                MESSAGE_NESTED_TYPE
+            } else {
+                FILE_MESSAGE_TYPE
+            },
+            index as i32,
+        ]);
+        parent = Some(&siblings[index]);
+    }
+
+    let index = match (is_enum, parent) {
+        (false, None) => file
+            .message_type
+            .iter()
+            .position(|desc| desc.name() == last),
+        (false, Some(parent)) => parent
+            .nested_type
+            .iter()
+            .position(|desc| desc.name() == last),
+        (true, None) => file.enum_type.iter().position(|desc| desc.name() == last),
+        (true, Some(parent)) => parent.enum_type.iter().position(|desc| desc.name() == last),
+    }?;
+    path.extend([
+        match (is_enum, parent.is_some()) {
+            (false, false) => FILE_MESSAGE_TYPE,
+            (false, true) => MESSAGE_NESTED_TYPE,
+            (true, false) => FILE_ENUM_TYPE,
+            (true, true) => MESSAGE_ENUM_TYPE,
+        },
+        index as i32,
+    ]);
+    Some(path)
+}
+
+pub(crate) fn service_path(file: &FileDescriptorProto, name: &str) -> Option<Vec<i32>> {
+    let index = file
+        .service
+        .iter()
+        .position(|service| service.name() == name)?;
+    Some(vec![FILE_SERVICE, index as i32])
+}
+
+/// Message found by following a path made by `type_path`.
+fn message_proto<'a>(file: &'a FileDescriptorProto, path: &[i32]) -> Option<&'a DescriptorProto> {
+    let mut message: Option<&DescriptorProto> = None;
+    for step in path.chunks(2) {
+        let index = *step.get(1)? as usize;
+        message = Some(match message {
+            None => file.message_type.get(index)?,
+            Some(parent) => parent.nested_type.get(index)?,
+        });
+    }
+    message
+}
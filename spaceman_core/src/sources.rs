@@ -0,0 +1,111 @@
+//! Descriptor sources, which are either a file, a directory or a glob pattern.
+//!
+//! A directory stands for the descriptor sets right inside it, recognized by their extension.
+
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+/// Extensions that `protoc -o` output is commonly given.
+const EXTENSIONS: &[&str] = &["desc", "protoset", "pb", "binpb", "bin"];
+
+/// Paths of the descriptor sets that `source` stands for, sorted.
+pub fn expand(source: &str) -> Result<Vec<PathBuf>> {
+    if is_glob(source) {
+        // Already sorted by glob
+        let paths: Vec<PathBuf> = glob::glob(source)
+            .with_context(|| format!("parsing glob pattern {source}"))?
+            .filter_map(Result::ok)
+            .filter(|path| path.is_file())
+            .collect();
+        if paths.is_empty() {
+            bail!("no file matches {source}");
+        }
+        Ok(paths)
+    } else if Path::new(source).is_dir() {
+        let mut paths = Vec::new();
+        for entry in
+            std::fs::read_dir(source).with_context(|| format!("reading directory {source}"))?
+        {
+            let path = entry
+                .with_context(|| format!("reading directory {source}"))?
+                .path();
+            if path.is_file() && has_descriptor_extension(&path) {
+                paths.push(path);
+            }
+        }
+        if paths.is_empty() {
+            bail!(
+                "no descriptor set in directory {source}, looked for files ending in .{}",
+                EXTENSIONS.join(", .")
+            );
+        }
+        paths.sort();
+        Ok(paths)
+    } else {
+        Ok(vec![PathBuf::from(source)])
+    }
+}
+
+/// Whether the file at `path` is one of those that `source` stands for, even if it doesn't
+/// exist yet.
+pub fn matches(source: &str, path: &Path) -> bool {
+    if is_glob(source) {
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        glob::Pattern::new(source)
+            .map(|pattern| pattern.matches_path_with(path, options))
+            .unwrap_or(false)
+    } else if Path::new(source).is_dir() {
+        path.parent() == Some(Path::new(source)) && has_descriptor_extension(path)
+    } else {
+        path == Path::new(source)
+    }
+}
+
+/// The directory to watch to learn about changes to the files that `source` stands for, and
+/// whether its subdirectories have to be watched as well.
+pub fn watch_root(source: &str) -> (PathBuf, bool) {
+    let path = Path::new(source);
+    if is_glob(source) {
+        let mut root = PathBuf::new();
+        let mut components = path.components();
+        for component in components.by_ref() {
+            if is_glob(&component.as_os_str().to_string_lossy()) {
+                break;
+            }
+            root.push(component);
+        }
+        // The component with the first wildcard was consumed, more of them means that matches
+        // can be deeper than the root
+        let recursive = components.next().is_some() || source.contains("**");
+        (non_empty(root), recursive)
+    } else if path.is_dir() {
+        (path.to_path_buf(), false)
+    } else {
+        let parent = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        (non_empty(parent), false)
+    }
+}
+
+fn is_glob(source: &str) -> bool {
+    source.contains(['*', '?', '['])
+}
+
+fn has_descriptor_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| EXTENSIONS.contains(&extension))
+        .unwrap_or(false)
+}
+
+// The parent of a bare file name is the empty path, which stands for the current directory
+fn non_empty(path: PathBuf) -> PathBuf {
+    if path.as_os_str().is_empty() {
+        PathBuf::from(Component::CurDir.as_os_str())
+    } else {
+        path
+    }
+}
@@ -0,0 +1,110 @@
+//! Lookup of services, methods, messages and enums by full name, with suggestions for names that
+//! are slightly off.
+
+use std::collections::HashMap;
+
+use prost_reflect::{DescriptorPool, EnumDescriptor, MessageDescriptor, MethodDescriptor};
+
+/// How many suggestions to make at most.
+const MAX_SUGGESTIONS: usize = 3;
+
+#[derive(Clone)]
+enum Symbol {
+    Service,
+    Method(MethodDescriptor),
+    Message(MessageDescriptor),
+    Enum(EnumDescriptor),
+}
+
+#[derive(Default, Clone)]
+pub(crate) struct SymbolIndex {
+    symbols: HashMap<String, Symbol>,
+}
+
+impl SymbolIndex {
+    pub(crate) fn new(pool: &DescriptorPool) -> Self {
+        let mut symbols = HashMap::new();
+        for service in pool.services() {
+            for method in service.methods() {
+                symbols.insert(method.full_name().to_string(), Symbol::Method(method));
+            }
+            symbols.insert(service.full_name().to_string(), Symbol::Service);
+        }
+        for message in pool.all_messages() {
+            symbols.insert(message.full_name().to_string(), Symbol::Message(message));
+        }
+        for enum_desc in pool.all_enums() {
+            symbols.insert(enum_desc.full_name().to_string(), Symbol::Enum(enum_desc));
+        }
+        SymbolIndex { symbols }
+    }
+
+    pub(crate) fn method(&self, full_name: &str) -> Option<MethodDescriptor> {
+        match self.symbols.get(full_name)? {
+            Symbol::Method(method) => Some(method.clone()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn message(&self, full_name: &str) -> Option<MessageDescriptor> {
+        match self.symbols.get(full_name)? {
+            Symbol::Message(message) => Some(message.clone()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn enumeration(&self, full_name: &str) -> Option<EnumDescriptor> {
+        match self.symbols.get(full_name)? {
+            Symbol::Enum(enum_desc) => Some(enum_desc.clone()),
+            _ => None,
+        }
+    }
+
+    /// Full names of the methods that `full_name` was probably meant to be, best first.
+    ///
+    /// A method is suggested if its full name is a few typos away, or if its own name matches
+    /// and only the service or package is wrong.
+    pub(crate) fn suggest_methods(&self, full_name: &str) -> Vec<String> {
+        let short_name = |name: &str| name.rsplit('.').next().unwrap_or(name).to_lowercase();
+        let target = full_name.to_lowercase();
+        let target_short = short_name(full_name);
+        // Short names have little room for typos
+        let max_distance = (target.len() / 4).max(1);
+
+        let mut suggestions: Vec<(usize, &str)> = self
+            .symbols
+            .iter()
+            .filter(|(_, symbol)| matches!(symbol, Symbol::Method(_)))
+            .filter_map(|(candidate, _)| {
+                let distance = edit_distance(&target, &candidate.to_lowercase());
+                let distance = if short_name(candidate) == target_short {
+                    distance.min(1)
+                } else {
+                    distance
+                };
+                (distance <= max_distance).then_some((distance, candidate.as_str()))
+            })
+            .collect();
+        suggestions.sort();
+        suggestions
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, candidate)| candidate.to_string())
+            .collect()
+    }
+}
+
+// Levenshtein distance, counted in chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,563 @@
+//! Decoding of the Protobuf wire format without a schema.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use anyhow::{anyhow, bail, Result};
+use prost_reflect::{Kind, MessageDescriptor};
+
+use spaceman_types::wire::{UnknownField, WireField, WireType, WireValue};
+
+const MAX_FIELD_NUMBER: u32 = (1 << 29) - 1;
+// Same as prost's, deeper messages wouldn't decode anyway
+const RECURSION_LIMIT: u32 = 100;
+
+/// Decodes `bytes` as a message of unknown type, guessing what length-delimited values are.
+pub fn decode(bytes: &[u8]) -> Result<Vec<WireField>> {
+    let fields = Reader::new(bytes).fields(None, 0)?;
+    Ok(wire_fields(fields, 0))
+}
+
+/// `bytes` decoded like `decode`, one field per line with nested messages indented. Bytes that
+/// aren't a valid message are dumped in hex instead, after what's wrong with them.
+pub fn dump(bytes: &[u8]) -> String {
+    match decode(bytes) {
+        Ok(fields) => {
+            let mut dump = String::new();
+            dump_fields(&fields, 0, &mut dump);
+            dump
+        }
+        Err(err) => format!("not a valid message: {err}\n{}", hex_dump(bytes)),
+    }
+}
+
+/// `bytes` 16 per line, each line starting with its offset and ending with the printable ones.
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for (line, chunk) in bytes.chunks(16).enumerate() {
+        let _ = write!(dump, "{:08x} ", line * 16);
+        for i in 0..16 {
+            if i == 8 {
+                dump.push(' ');
+            }
+            match chunk.get(i) {
+                Some(byte) => {
+                    let _ = write!(dump, " {byte:02x}");
+                }
+                None => dump.push_str("   "),
+            }
+        }
+        let printable: String = chunk
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        let _ = writeln!(dump, "  |{printable}|");
+    }
+    dump
+}
+
+/// Fields of `bytes`, a message of type `desc`, that `desc` doesn't define, including those of
+/// nested messages. Nothing is returned if `bytes` isn't a valid message.
+pub fn unknown_fields(desc: &MessageDescriptor, bytes: &[u8]) -> Vec<UnknownField> {
+    let mut unknown = Vec::new();
+    if let Ok(fields) = Reader::new(bytes).fields(None, 0) {
+        collect_unknown(desc, fields, "", 0, &mut unknown);
+    }
+    unknown
+}
+
+// A field value as found on the wire, before any guessing
+enum Raw<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Fixed32(u32),
+    Len(&'a [u8]),
+    Group(Vec<(u32, Raw<'a>)>),
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, pos: 0 }
+    }
+
+    // Fields up to the end of the input or, inside a group, up to the tag that ends it
+    fn fields(&mut self, group: Option<u32>, depth: u32) -> Result<Vec<(u32, Raw<'a>)>> {
+        if depth > RECURSION_LIMIT {
+            bail!("nested more than {RECURSION_LIMIT} levels deep");
+        }
+        let mut fields = Vec::new();
+        while self.pos < self.bytes.len() {
+            let at = self.pos;
+            let tag = self.varint()?;
+            let number = u32::try_from(tag >> 3)
+                .ok()
+                .filter(|number| (1..=MAX_FIELD_NUMBER).contains(number))
+                .ok_or_else(|| anyhow!("invalid field number {} at byte {at}", tag >> 3))?;
+            let wire_type = WireType::from_number(tag & 0b111)
+                .ok_or_else(|| anyhow!("invalid wire type {} at byte {at}", tag & 0b111))?;
+            let value = match wire_type {
+                WireType::Varint => Raw::Varint(self.varint()?),
+                WireType::Fixed64 => Raw::Fixed64(u64::from_le_bytes(
+                    self.take(8)?.try_into().expect("took 8 bytes"),
+                )),
+                WireType::Fixed32 => Raw::Fixed32(u32::from_le_bytes(
+                    self.take(4)?.try_into().expect("took 4 bytes"),
+                )),
+                WireType::LengthDelimited => {
+                    let len = self.varint()?;
+                    Raw::Len(self.take(usize::try_from(len)?)?)
+                }
+                WireType::StartGroup => Raw::Group(self.fields(Some(number), depth + 1)?),
+                WireType::EndGroup if group == Some(number) => return Ok(fields),
+                WireType::EndGroup => bail!("unexpected end of group {number} at byte {at}"),
+            };
+            fields.push((number, value));
+        }
+        match group {
+            Some(number) => Err(anyhow!("group {number} is never closed")),
+            None => Ok(fields),
+        }
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let at = self.pos;
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .bytes
+                .get(self.pos)
+                .ok_or_else(|| anyhow!("truncated varint at byte {at}"))?;
+            self.pos += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(anyhow!("varint longer than 10 bytes at byte {at}"))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| anyhow!("{len} bytes at byte {} run past the end", self.pos))?;
+        let taken = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(taken)
+    }
+}
+
+fn wire_fields(fields: Vec<(u32, Raw)>, depth: u32) -> Vec<WireField> {
+    fields
+        .into_iter()
+        .map(|(number, raw)| wire_field(number, raw, depth))
+        .collect()
+}
+
+fn wire_field(number: u32, raw: Raw, depth: u32) -> WireField {
+    let value = match raw {
+        Raw::Varint(value) => WireValue::Varint(value),
+        Raw::Fixed64(value) => WireValue::Fixed64(value),
+        Raw::Fixed32(value) => WireValue::Fixed32(value),
+        Raw::Len(bytes) => guess(bytes, depth + 1),
+        Raw::Group(fields) => WireValue::Group(wire_fields(fields, depth + 1)),
+    };
+    WireField { number, value }
+}
+
+// Printable UTF-8 is taken for a string even though it may well parse as a message too, since
+// short strings often do
+fn guess(bytes: &[u8], depth: u32) -> WireValue {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        if text.chars().all(|c| !c.is_control() || c.is_whitespace()) {
+            return WireValue::String(text.to_string());
+        }
+    }
+    match Reader::new(bytes).fields(None, depth) {
+        Ok(fields) => WireValue::Message(wire_fields(fields, depth)),
+        Err(_) => WireValue::Bytes(bytes.to_vec()),
+    }
+}
+
+fn dump_fields(fields: &[WireField], depth: usize, dump: &mut String) {
+    let indent = "  ".repeat(depth);
+    for field in fields {
+        let _ = write!(
+            dump,
+            "{indent}{}: {} ",
+            field.number,
+            field.value.wire_type().as_str()
+        );
+        let _ = match &field.value {
+            WireValue::Varint(value) if (*value as i64) < 0 => {
+                writeln!(dump, "{value} (int64 {})", *value as i64)
+            }
+            WireValue::Varint(value) => writeln!(dump, "{value}"),
+            WireValue::Fixed64(value) => writeln!(
+                dump,
+                "0x{value:016x} (double {}, int64 {})",
+                f64::from_bits(*value),
+                *value as i64
+            ),
+            WireValue::Fixed32(value) => writeln!(
+                dump,
+                "0x{value:08x} (float {}, int32 {})",
+                f32::from_bits(*value),
+                *value as i32
+            ),
+            WireValue::String(value) => writeln!(dump, "{value:?}"),
+            WireValue::Bytes(value) => {
+                let hex: Vec<String> = value.iter().map(|byte| format!("{byte:02x}")).collect();
+                writeln!(dump, "<{}>", hex.join(" "))
+            }
+            WireValue::Message(fields) | WireValue::Group(fields) => {
+                let _ = writeln!(dump, "{{");
+                dump_fields(fields, depth + 1, dump);
+                writeln!(dump, "{indent}}}")
+            }
+        };
+    }
+}
+
+fn collect_unknown(
+    desc: &MessageDescriptor,
+    fields: Vec<(u32, Raw)>,
+    path: &str,
+    depth: u32,
+    unknown: &mut Vec<UnknownField>,
+) {
+    // Occurrences of each field so far, to tell the elements of repeated fields apart
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    for (number, raw) in fields {
+        let field = match desc.get_field(number) {
+            Some(field) => field,
+            None => {
+                unknown.push(UnknownField {
+                    path: path.to_string(),
+                    field: wire_field(number, raw, depth),
+                });
+                continue;
+            }
+        };
+        let count = counts.entry(number).or_default();
+        let index = *count;
+        *count += 1;
+
+        let nested = match field.kind() {
+            Kind::Message(nested) => nested,
+            _ => continue,
+        };
+        let nested_fields = match raw {
+            Raw::Len(bytes) => match Reader::new(bytes).fields(None, depth + 1) {
+                Ok(fields) => fields,
+                Err(_) => continue,
+            },
+            Raw::Group(fields) => fields,
+            _ => continue,
+        };
+        let segment = if field.is_list() || field.is_map() {
+            format!("{}[{index}]", field.name())
+        } else {
+            field.name().to_string()
+        };
+        let nested_path = if path.is_empty() {
+            segment
+        } else {
+            format!("{path}.{segment}")
+        };
+        collect_unknown(&nested, nested_fields, &nested_path, depth + 1, unknown);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prost_reflect::prost_types::field_descriptor_proto::{Label, Type};
+    use prost_reflect::prost_types::{
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    };
+    use prost_reflect::DescriptorPool;
+
+    use super::*;
+
+    fn field(name: &str, number: i32, kind: Type, label: Label) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(label as i32),
+            r#type: Some(kind as i32),
+            type_name: (kind == Type::Message).then(|| ".test.Inner".to_string()),
+            ..Default::default()
+        }
+    }
+
+    // message Inner { int32 x = 1; }
+    // message Outer { string name = 1; repeated Inner items = 2; Inner single = 3; }
+    fn outer() -> MessageDescriptor {
+        let file = FileDescriptorProto {
+            name: Some("test.proto".to_string()),
+            package: Some("test".to_string()),
+            syntax: Some("proto3".to_string()),
+            message_type: vec![
+                DescriptorProto {
+                    name: Some("Inner".to_string()),
+                    field: vec![field("x", 1, Type::Int32, Label::Optional)],
+                    ..Default::default()
+                },
+                DescriptorProto {
+                    name: Some("Outer".to_string()),
+                    field: vec![
+                        field("name", 1, Type::String, Label::Optional),
+                        field("items", 2, Type::Message, Label::Repeated),
+                        field("single", 3, Type::Message, Label::Optional),
+                    ],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        DescriptorPool::from_file_descriptor_set(FileDescriptorSet { file: vec![file] })
+            .unwrap()
+            .get_message_by_name("test.Outer")
+            .unwrap()
+    }
+
+    fn unknown(path: &str, number: u32, value: WireValue) -> UnknownField {
+        UnknownField {
+            path: path.to_string(),
+            field: WireField { number, value },
+        }
+    }
+
+    fn varint(number: u32, value: u64) -> WireField {
+        WireField {
+            number,
+            value: WireValue::Varint(value),
+        }
+    }
+
+    #[test]
+    fn decodes_every_wire_type() {
+        let bytes = [
+            0x08, 0x96, 0x01, // 1: 150
+            0x11, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, // 2: fixed64
+            0x1d, 0x01, 0x02, 0x03, 0x04, // 3: fixed32
+            0x22, 0x02, b'h', b'i', // 4: "hi"
+            0x2a, 0x03, 0x08, 0x96, 0x01, // 5: { 1: 150 }
+            0x32, 0x02, 0xff, 0xff, // 6: bytes
+            // 7: group { 1: 1, 8: group { 1: 2 } }
+            0x3b, 0x08, 0x01, 0x43, 0x08, 0x02, 0x44, 0x3c,
+        ];
+        assert_eq!(
+            decode(&bytes).unwrap(),
+            [
+                varint(1, 150),
+                WireField {
+                    number: 2,
+                    value: WireValue::Fixed64(0x0807060504030201),
+                },
+                WireField {
+                    number: 3,
+                    value: WireValue::Fixed32(0x04030201),
+                },
+                WireField {
+                    number: 4,
+                    value: WireValue::String("hi".to_string()),
+                },
+                WireField {
+                    number: 5,
+                    value: WireValue::Message(vec![varint(1, 150)]),
+                },
+                WireField {
+                    number: 6,
+                    value: WireValue::Bytes(vec![0xff, 0xff]),
+                },
+                WireField {
+                    number: 7,
+                    value: WireValue::Group(vec![
+                        varint(1, 1),
+                        WireField {
+                            number: 8,
+                            value: WireValue::Group(vec![varint(1, 2)]),
+                        },
+                    ]),
+                },
+            ]
+        );
+        assert_eq!(decode(&[]).unwrap(), []);
+        // Largest varint and field number
+        assert_eq!(
+            decode(&[
+                0xf8, 0xff, 0xff, 0xff, 0x0f, // 536870911
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, // u64::MAX
+            ])
+            .unwrap(),
+            [varint(MAX_FIELD_NUMBER, u64::MAX)]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        let error = |bytes: &[u8]| decode(bytes).unwrap_err().to_string();
+        assert_eq!(error(&[0x08]), "truncated varint at byte 1");
+        assert_eq!(error(&[0x08, 0x96]), "truncated varint at byte 1");
+        assert_eq!(
+            error(&[0x19, 0x01, 0x02]),
+            "8 bytes at byte 1 run past the end"
+        );
+        assert_eq!(error(&[0x15, 0x01]), "4 bytes at byte 1 run past the end");
+        assert_eq!(
+            error(&[0x0a, 0x05, b'a']),
+            "5 bytes at byte 2 run past the end"
+        );
+        assert_eq!(error(&[0x3b, 0x08, 0x01]), "group 7 is never closed");
+        assert_eq!(error(&[0x3b, 0x44]), "unexpected end of group 8 at byte 1");
+        assert_eq!(error(&[0x00]), "invalid field number 0 at byte 0");
+        assert_eq!(error(&[0x08, 0x01, 0x0e]), "invalid wire type 6 at byte 2");
+        assert_eq!(error(&[0xff; 11]), "varint longer than 10 bytes at byte 0");
+        let nested = [0x0b; RECURSION_LIMIT as usize + 2];
+        assert_eq!(
+            error(&nested),
+            format!("nested more than {RECURSION_LIMIT} levels deep")
+        );
+    }
+
+    #[test]
+    fn dumps_fields() {
+        let bytes = [
+            0x08, 0x96, 0x01, // 1: 150
+            0x10, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, // 2: -1
+            0x19, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xf0, 0x3f, // 3: 1.0
+            0x25, 0x00, 0x00, 0x80, 0x3f, // 4: 1.0f
+            0x2a, 0x02, b'h', b'i', // 5: "hi"
+            0x32, 0x05, 0x08, 0x96, 0x01, 0x18, 0x01, // 6: { 1: 150, 3: 1 }
+            0x3a, 0x02, 0xff, 0xff, // 7: bytes
+            0x43, 0x08, 0x01, 0x44, // 8: group { 1: 1 }
+        ];
+        assert_eq!(
+            dump(&bytes),
+            "1: varint 150\n\
+             2: varint 18446744073709551615 (int64 -1)\n\
+             3: i64 0x3ff0000000000000 (double 1, int64 4607182418800017408)\n\
+             4: i32 0x3f800000 (float 1, int32 1065353216)\n\
+             5: len \"hi\"\n\
+             6: len {\n\
+             \x20 1: varint 150\n\
+             \x20 3: varint 1\n\
+             }\n\
+             7: len <ff ff>\n\
+             8: sgroup {\n\
+             \x20 1: varint 1\n\
+             }\n"
+        );
+        assert_eq!(dump(&[]), "");
+    }
+
+    #[test]
+    fn dumps_invalid_input_in_hex() {
+        assert_eq!(
+            dump(&[0x08, 0x96]),
+            "not a valid message: truncated varint at byte 1\n\
+             00000000  08 96                                             |..|\n"
+        );
+    }
+
+    #[test]
+    fn hex_dumps() {
+        assert_eq!(hex_dump(&[]), "");
+        assert_eq!(
+            hex_dump(b"hi"),
+            "00000000  68 69                                             |hi|\n"
+        );
+        assert_eq!(
+            hex_dump(b"0123456789abcdef\x00\n "),
+            "00000000  30 31 32 33 34 35 36 37  38 39 61 62 63 64 65 66  |0123456789abcdef|\n\
+             00000010  00 0a 20                                          |.. |\n"
+        );
+    }
+
+    #[test]
+    fn finds_no_unknown_fields_in_known_messages() {
+        let bytes = [
+            0x0a, 0x01, b'a', // name: "a"
+            0x12, 0x02, 0x08, 0x01, // items { x: 1 }
+            0x1a, 0x00, // single {}
+        ];
+        assert_eq!(unknown_fields(&outer(), &bytes), []);
+        assert_eq!(unknown_fields(&outer(), &[]), []);
+    }
+
+    #[test]
+    fn finds_unknown_fields_at_every_level() {
+        let bytes = [
+            0x0a, 0x01, b'a', // name: "a"
+            0x48, 0x05, // 9: 5
+            0x12, 0x02, 0x08, 0x01, // items[0] { x: 1 }
+            0x12, 0x07, 0x08, 0x01, 0x3d, 0x01, 0x00, 0x00, 0x00, // items[1] { x: 1, 7: 1 }
+            0x1a, 0x04, 0x2a, 0x02, b'h', b'i', // single { 5: "hi" }
+        ];
+        let unknown_fields = unknown_fields(&outer(), &bytes);
+        assert_eq!(
+            unknown_fields,
+            [
+                unknown("", 9, WireValue::Varint(5)),
+                unknown("items[1]", 7, WireValue::Fixed32(1)),
+                unknown("single", 5, WireValue::String("hi".to_string())),
+            ]
+        );
+        let shown: Vec<String> = unknown_fields.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            shown,
+            [
+                "9: 5 (varint)",
+                "items[1].7: 0x00000001 (i32)",
+                "single.5: \"hi\" (len)"
+            ]
+        );
+    }
+
+    #[test]
+    fn guesses_unknown_messages() {
+        // 4: { 1: 150 }, whose bytes aren't UTF-8
+        let bytes = [0x22, 0x03, 0x08, 0x96, 0x01];
+        assert_eq!(
+            unknown_fields(&outer(), &bytes),
+            [unknown(
+                "",
+                4,
+                WireValue::Message(vec![WireField {
+                    number: 1,
+                    value: WireValue::Varint(150)
+                }])
+            )]
+        );
+    }
+
+    #[test]
+    fn skips_invalid_messages() {
+        // Truncated varint
+        assert_eq!(unknown_fields(&outer(), &[0x48]), []);
+        // Length past the end
+        assert_eq!(
+            unknown_fields(&outer(), &[0x48, 0x05, 0x0a, 0x05, b'a']),
+            []
+        );
+        // A known field that isn't a valid message is only skipped itself
+        let bytes = [0x1a, 0x01, 0xff, 0x48, 0x05];
+        assert_eq!(
+            unknown_fields(&outer(), &bytes),
+            [unknown("", 9, WireValue::Varint(5))]
+        );
+    }
+}
@@ -3,16 +3,20 @@
     windows_subsystem = "windows"
 )]
 
-use std::{collections::HashMap, path::Path, sync::{RwLock, Mutex}, time::Duration, f32::consts::E};
+use std::{collections::HashMap, path::{Path, PathBuf}, sync::{Arc, RwLock, Mutex, atomic::{AtomicUsize, Ordering}}, time::{Duration, SystemTime, UNIX_EPOCH}, f32::consts::E};
 
-use tauri::{Manager, State, LogicalSize};
+use tauri::{Manager, State, LogicalSize, PhysicalPosition, PhysicalSize, WindowBuilder, WindowUrl};
 use tokio_stream::StreamExt;
-use spaceman_core::{Conn, DynamicMessage, IntoRequest, IntoStreamingRequest, Metadata, Repo, SerializeOptions, zero_message};
+use spaceman_core::{diff, export, health, import, sample, sources, wire, metadata_pairs, Bytes, Conn, DynamicMessage, IntoRequest, IntoStreamingRequest, Metadata, Received, Repo, SerializeOptions, json_schema, message_from_json, message_schema, message_to_json, zero_message};
 use anyhow::Result;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 
 fn main() {
     tauri::Builder::default()
         .manage(RwLock::new(Repo::new()))
+        .manage(DetachedTabs::default())
+        .manage(ProtoWatcher::default())
+        .manage(ReceivedMessages::default())
         .setup(|app| {
             for win in app.windows().values_mut() {
                 win.set_size(LogicalSize::new(1050, 600))?;
@@ -24,10 +28,27 @@ fn main() {
             get_repo_view,
             add_protobuf_descriptor,
             reset_repo,
+            watch_protos,
             get_empty_input_message,
+            get_sample_input_message,
+            get_message_schema,
+            get_json_schema,
+            get_method_docs,
+            get_type_docs,
+            search,
+            diff_messages,
+            export_call,
+            import_requests,
             start_call,
+            get_raw_dump,
+            forget_call,
+            check_health,
+            detach_tab,
+            take_detached_tab,
             save_settings,
             load_settings,
+            save_workspace,
+            load_workspace,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -40,16 +61,120 @@ fn get_repo_view(repo: State<RwLock<Repo>>) -> Result<String, String> {
     serde_json::to_string(&repo_view).map_err(|err| err.to_string())
 }
 
+/// Adds the descriptor file at `path`, or all those in the directory or matching the glob
 #[tauri::command]
-fn add_protobuf_descriptor(path: &Path, repo: State<RwLock<Repo>>) -> Result<(), String> {
+fn add_protobuf_descriptor(path: &str, repo: State<RwLock<Repo>>) -> Result<(), String> {
     let mut repo = repo.write().expect("previous holder panicked");
-    repo.add_descriptor(path).map_err(|err| err.to_string())
+    repo.add_descriptors(path).map_err(|err| format!("{:#}", err))
 }
 
 #[tauri::command]
-fn reset_repo(repo: State<RwLock<Repo>>) {
+fn reset_repo(later_overrides: bool, repo: State<RwLock<Repo>>) {
     let mut repo = repo.write().expect("previous holder panicked");
     *repo = Repo::new();
+    repo.set_later_overrides(later_overrides);
+}
+
+/// Watches the descriptor files so that they are reloaded when they change
+#[derive(Default)]
+struct ProtoWatcher(Mutex<Option<RecommendedWatcher>>);
+
+/// Watches the descriptor files at `paths`, which should be loaded in this order, replacing any
+/// previous watch. Paths can also be directories or globs, like for `add_protobuf_descriptor`.
+/// Whenever any of the files changes, the repo is rebuilt out of all of them and swapped in one
+/// go. Windows are then sent a `protos-reloaded` event with the JSON encoded
+/// `ReloadSummary`, or a `protos-reload-failed` event with the error if the old repo was kept.
+/// Returns the JSON encoded list of directories that couldn't be watched because they don't exist.
+#[tauri::command]
+fn watch_protos(
+    paths: Vec<String>,
+    later_overrides: bool,
+    app_handle: tauri::AppHandle,
+    proto_watcher: State<ProtoWatcher>,
+) -> Result<String, String> {
+    // Events name files by absolute path
+    let current_dir = std::env::current_dir().map_err(|err| err.to_string())?;
+    let paths: Vec<String> = paths
+        .into_iter()
+        .map(|path| current_dir.join(path).to_string_lossy().into_owned())
+        .collect();
+
+    let (events_tx, events_rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        // Only fails once the watcher is being dropped
+        let _ = events_tx.send(res);
+    }).map_err(|err| err.to_string())?;
+
+    // Protobuf compilers and editors often replace files rather than writing
+    // them in place, which loses watches on the files themselves. So their
+    // directories are watched instead, which also catches new files for
+    // directories and globs. Directories that don't exist are skipped, the
+    // sources in them fail to load anyway and there is nothing to watch until
+    // the next call.
+    let mut roots: Vec<(PathBuf, bool)> = paths
+        .iter()
+        .map(|path| sources::watch_root(path))
+        .collect();
+    roots.sort();
+    roots.dedup();
+    let mut missing = Vec::new();
+    for (root, recursive) in roots {
+        if !root.is_dir() {
+            missing.push(root.to_string_lossy().into_owned());
+            continue;
+        }
+        let mode = if recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        watcher.watch(&root, mode).map_err(|err| format!("watching {}: {}", root.display(), err))?;
+    }
+
+    std::thread::spawn(move || {
+        let is_relevant = |res: notify::Result<notify::Event>| match res {
+            Ok(event) => {
+                !matches!(event.kind, EventKind::Access(_))
+                    && event.paths.iter().any(|changed| {
+                        paths.iter().any(|path| sources::matches(path, changed))
+                    })
+            },
+            Err(_) => false,
+        };
+
+        // Ends when the watcher, and with it the sending half, is dropped
+        while let Ok(res) = events_rx.recv() {
+            if !is_relevant(res) {
+                continue;
+            }
+            // Files are usually written in several steps, so wait for things
+            // to settle down before reading them
+            loop {
+                use std::sync::mpsc::RecvTimeoutError;
+                match events_rx.recv_timeout(Duration::from_millis(300)) {
+                    Ok(_) => continue,
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+
+            let mut new_repo = Repo::new();
+            new_repo.set_later_overrides(later_overrides);
+            // All at once, so that the pool is only built once
+            if let Err(err) = new_repo.add_all_descriptors(&paths).map_err(|err| format!("{:#}", err)) {
+                let _ = app_handle.emit_all("protos-reload-failed", err);
+                continue;
+            }
+
+            let repo = app_handle.state::<RwLock<Repo>>();
+            let mut repo = repo.write().expect("previous holder panicked");
+            let summary = new_repo.changes_since(&repo);
+            *repo = new_repo;
+            drop(repo);
+
+            let summary = serde_json::to_string(&summary).expect("no error encoding ReloadSummary");
+            let _ = app_handle.emit_all("protos-reloaded", summary);
+        }
+    });
+
+    *proto_watcher.0.lock().expect("previous holder panicked") = Some(watcher);
+    serde_json::to_string(&missing).map_err(|err| err.to_string())
 }
 
 #[tauri::command]
@@ -57,11 +182,129 @@ fn get_empty_input_message(repo: State<RwLock<Repo>>, method_full_name: &str) ->
     let method = repo
         .read()
         .expect("previous holder panicked")
-        .find_method_desc(method_full_name)
-        .ok_or_else(|| "no such method".to_string())?;
+        .find_method(method_full_name)
+        .map_err(|err| err.to_string())?;
     serialize_message(&zero_message(method.input(), 3)).map_err(|err| err.to_string())
 }
 
+/// Returns a made up input message for the method, see `spaceman_core::sample`
+#[tauri::command]
+fn get_sample_input_message(repo: State<RwLock<Repo>>, method_full_name: &str) -> Result<String, String> {
+    let repo = repo.read().expect("previous holder panicked");
+    let method = repo
+        .find_method(method_full_name)
+        .map_err(|err| err.to_string())?;
+    let message = sample::sample_message(&repo, &method.input(), &SampleOptions::default());
+    serialize_message(&message).map_err(|err| err.to_string())
+}
+
+/// Returns JSON encoded `Schema` of the input message of the method
+#[tauri::command]
+fn get_message_schema(repo: State<RwLock<Repo>>, method_full_name: &str) -> Result<String, String> {
+    let method = repo
+        .read()
+        .expect("previous holder panicked")
+        .find_method(method_full_name)
+        .map_err(|err| err.to_string())?;
+    serde_json::to_string(&message_schema(&method.input())).map_err(|err| err.to_string())
+}
+
+/// Returns the JSON Schema of the input message of the method
+#[tauri::command]
+fn get_json_schema(repo: State<RwLock<Repo>>, method_full_name: &str) -> Result<String, String> {
+    let method = repo
+        .read()
+        .expect("previous holder panicked")
+        .find_method(method_full_name)
+        .map_err(|err| err.to_string())?;
+    serde_json::to_string(&json_schema(&method.input())).map_err(|err| err.to_string())
+}
+
+/// Returns JSON encoded `Docs` of the method
+#[tauri::command]
+fn get_method_docs(repo: State<RwLock<Repo>>, method_full_name: &str) -> Result<String, String> {
+    let docs = repo
+        .read()
+        .expect("previous holder panicked")
+        .method_docs(method_full_name)
+        .ok_or_else(|| "no such method".to_string())?;
+    serde_json::to_string(&docs).map_err(|err| err.to_string())
+}
+
+/// Returns JSON encoded `Docs` of the message or enum
+#[tauri::command]
+fn get_type_docs(repo: State<RwLock<Repo>>, type_full_name: &str) -> Result<String, String> {
+    let docs = repo
+        .read()
+        .expect("previous holder panicked")
+        .type_docs(type_full_name)
+        .ok_or_else(|| "no such type".to_string())?;
+    serde_json::to_string(&docs).map_err(|err| err.to_string())
+}
+
+/// Returns JSON encoded `SearchHit`s for the query, best first
+#[tauri::command]
+fn search(repo: State<RwLock<Repo>>, query: &str) -> Result<String, String> {
+    let hits = repo
+        .read()
+        .expect("previous holder panicked")
+        .search(query, 50);
+    serde_json::to_string(&hits).map_err(|err| err.to_string())
+}
+
+/// Returns JSON encoded `Difference`s between two JSON encoded messages
+#[tauri::command]
+fn diff_messages(left: &str, right: &str, ignore_encoded: &str) -> Result<String, String> {
+    let left: serde_json::Value = serde_json::from_str(left).map_err(|err| err.to_string())?;
+    let right: serde_json::Value = serde_json::from_str(right).map_err(|err| err.to_string())?;
+    let ignore: Vec<String> = serde_json::from_str(ignore_encoded).map_err(|err| err.to_string())?;
+    serde_json::to_string(&diff::diff(&left, &right, &ignore)).map_err(|err| err.to_string())
+}
+
+/// Returns the call to the method exported in the given JSON encoded `ExportFormat`
+#[tauri::command]
+fn export_call(
+    repo: State<RwLock<Repo>>,
+    endpoint_encoded: &str,
+    method_full_name: &str,
+    metadata: Vec<(String, String)>,
+    body: &str,
+    descriptor_paths: Vec<String>,
+    format_encoded: &str,
+) -> Result<String, String> {
+    let endpoint =
+        serde_json::from_str(endpoint_encoded).map_err(|_err| "unable to parse endpoint".to_string())?;
+    let format = serde_json::from_str(format_encoded).map_err(|_err| "unable to parse format".to_string())?;
+
+    let method = repo
+        .read()
+        .expect("previous holder panicked")
+        .find_method(method_full_name)
+        .map_err(|err| err.to_string())?;
+
+    let body = serde_json::from_str(body).map_err(|err| err.to_string())?;
+    let body = message_from_json(method.input(), body).map_err(|err| err.to_string())?;
+
+    export::export(
+        &export::Call {
+            endpoint: &endpoint,
+            method: &method,
+            metadata: &metadata,
+            body: &body,
+            descriptor_paths: &descriptor_paths,
+        },
+        format,
+    )
+    .map_err(|err| err.to_string())
+}
+
+/// Returns the JSON encoded `Import` of grpcurl command lines or of a Postman collection
+#[tauri::command]
+fn import_requests(text: &str) -> Result<String, String> {
+    let import = import::import(text).map_err(|err| format!("{:#}", err))?;
+    serde_json::to_string(&import).map_err(|err| err.to_string())
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 enum CallOpIn {
     Msg(String),
@@ -70,6 +313,46 @@ enum CallOpIn {
 }
 
 use spaceman_types::callopout::CallOpOut;
+use spaceman_types::sample::SampleOptions;
+use spaceman_types::wire::RawDump;
+
+/// Encoded messages received by the calls of each window, by label of the window and call id, in
+/// the order they were sent to the frontend. Kept until the frontend forgets the call, so that
+/// their encoding can be shown without sending it along with every message.
+#[derive(Default)]
+struct ReceivedMessages {
+    by_call: Arc<Mutex<HashMap<(String, i32), Vec<Bytes>>>>,
+}
+
+/// Returns the JSON encoded `RawDump` of the `index`th message received by the call
+#[tauri::command]
+fn get_raw_dump(
+    call_id: i32,
+    index: usize,
+    received: State<ReceivedMessages>,
+    window: tauri::Window,
+) -> Result<String, String> {
+    let by_call = received.by_call.lock().expect("previous holder panicked");
+    let bytes = by_call
+        .get(&(window.label().to_string(), call_id))
+        .and_then(|messages| messages.get(index))
+        .ok_or_else(|| "the message is no longer available".to_string())?;
+    let raw = RawDump {
+        wire: wire::dump(bytes),
+        hex: wire::hex_dump(bytes),
+    };
+    serde_json::to_string(&raw).map_err(|err| err.to_string())
+}
+
+/// Drops the messages received by the call, once the frontend no longer shows them
+#[tauri::command]
+fn forget_call(call_id: i32, received: State<ReceivedMessages>, window: tauri::Window) {
+    received
+        .by_call
+        .lock()
+        .expect("previous holder panicked")
+        .remove(&(window.label().to_string(), call_id));
+}
 
 static SERIALIZE_OPTIONS: &'static SerializeOptions =
     &SerializeOptions::new().skip_default_fields(false);
@@ -77,7 +360,7 @@ static SERIALIZE_OPTIONS: &'static SerializeOptions =
 fn serialize_message(msg: &DynamicMessage) -> Result<String> {
     let mut buf = Vec::new();
     let mut se = serde_json::Serializer::pretty(&mut buf);
-    msg.serialize_with_options(&mut se, SERIALIZE_OPTIONS)?;
+    message_to_json(msg, &mut se, SERIALIZE_OPTIONS)?;
     Ok(String::from_utf8(buf).expect("serde_json to emit valid utf8"))
 }
 
@@ -85,22 +368,29 @@ fn serialize_message(msg: &DynamicMessage) -> Result<String> {
 fn start_call(
     call_id: i32,
     endpoint_encoded: &str,
+    call_options_encoded: &str,
     method_full_name: &str,
     metadata: Vec<(&str, &str)>,
     repo: State<RwLock<Repo>>,
-    app_handle: tauri::AppHandle,
+    received: State<ReceivedMessages>,
+    window: tauri::Window,
 ) -> Result<(), String> {
+    // Events are exchanged with the window that started the call only, call ids
+    // are not unique across windows
     let chan_in_name = format!("i-{}", call_id);
     let chan_out_name = format!("o-{}", call_id);
 
     let endpoint =
         serde_json::from_str(endpoint_encoded).map_err(|_err| "unable to parse endpoint".to_string())?;
 
+    let call_options =
+        serde_json::from_str(call_options_encoded).map_err(|_err| "unable to parse call options".to_string())?;
+
     let method = repo
         .read()
         .expect("previous holder panicked")
-        .find_method_desc(method_full_name)
-        .ok_or_else(|| "no such method".to_string())?;
+        .find_method(method_full_name)
+        .map_err(|err| err.to_string())?;
 
     let (is_client_streaming, is_server_streaming) = (method.is_client_streaming(), method.is_server_streaming());
 
@@ -121,12 +411,18 @@ fn start_call(
         })?
     };
 
+    let received = received.by_call.clone();
+    let call_key = (window.label().to_string(), call_id);
+    received.lock().expect("previous holder panicked").insert(call_key.clone(), Vec::new());
+
     let send_outbound = {
-        // Get an app handle to be able to emit events
-        let app_handle = app_handle.clone();
+        // Get a window handle to be able to emit events
+        let window = window.clone();
         move |op: &CallOpOut| {
             let op_str = serde_json::to_string(op).expect("no error encoding CallOpOut");
-            app_handle.emit_all(&chan_out_name, op_str).expect("no error emitting event to all windows");
+            // Fails if the window has been closed in the meantime, in which
+            // case nobody is interested in the outcome anymore
+            let _ = window.emit(&chan_out_name, op_str);
         }
     };
 
@@ -194,8 +490,9 @@ fn start_call(
             let op: CallOpIn = serde_json::from_str(ev.payload().expect("event to have a payload")).expect("no error decoding CallOpIn");
             match op {
                 CallOpIn::Msg(msg_str) => {
-                    let mut de = serde_json::Deserializer::from_str(&msg_str);
-                    let msg = match DynamicMessage::deserialize(input_msg_type.clone(), &mut de) {
+                    let msg = match serde_json::from_str(&msg_str)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|json| message_from_json(input_msg_type.clone(), json)) {
                         Ok(msg) => msg,
                         Err(err) => {
                             send_outbound(&CallOpOut::InvalidInput(err.to_string()));
@@ -233,7 +530,7 @@ fn start_call(
         }
     };
     
-    let event_handler = app_handle.listen_global(chan_in_name.clone(), cb);
+    let event_handler = window.listen(chan_in_name.clone(), cb);
 
     let main_fut = async move {'fut: {
         let req = if is_client_streaming {
@@ -257,7 +554,7 @@ fn start_call(
             either::Left(req)
         };
 
-        let conn = match Conn::new(&endpoint) {
+        let conn = match Conn::with_options(&endpoint, call_options) {
             Ok(conn) => conn,
             Err(err) => {
                 send_outbound(&CallOpOut::Err(err.to_string()));
@@ -265,7 +562,7 @@ fn start_call(
             }
         };
 
-        let maybe_res = match (req, is_server_streaming) {
+        let outcome = match (req, is_server_streaming) {
             (either::Left(req), false) => {
                 conn.unary(&method, req).await.map(|res| either::Left(res))
             }
@@ -280,7 +577,15 @@ fn start_call(
             }
         };
 
-        let res = match maybe_res {
+        // Client-streaming calls are never retried
+        if !is_client_streaming {
+            send_outbound(&CallOpOut::Attempts(outcome.attempts));
+        }
+        if let Some(peer) = outcome.peer {
+            send_outbound(&CallOpOut::Peer(peer));
+        }
+
+        let res = match outcome.result {
             Ok(res) => res,
             Err(err) => {
                 send_outbound(&CallOpOut::Err(err.to_string()));
@@ -288,21 +593,45 @@ fn start_call(
             }
         };
 
+        let send_msg = |msg: &Received, seq: u32| {
+            let received_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since_epoch| since_epoch.as_millis() as u64)
+                .unwrap_or_default();
+            match serialize_message(&msg.message) {
+                Ok(msg_str) => {
+                    // The call may have been forgotten already if the tab was closed
+                    if let Some(messages) = received.lock().expect("previous holder panicked").get_mut(&call_key) {
+                        messages.push(msg.bytes.clone());
+                    }
+                    send_outbound(&CallOpOut::Msg {
+                        msg: msg_str,
+                        seq,
+                        received_at,
+                        size: msg.bytes.len(),
+                        unknown_fields: msg.unknown_fields(),
+                    })
+                }
+                Err(err) => send_outbound(&CallOpOut::InvalidOutput(format!(
+                    "{err}, the message as received:\n{}",
+                    wire::dump(&msg.bytes)
+                ))),
+            }
+        };
+
         match res {
             either::Left(res) => {
-                match serialize_message(res.get_ref()) {
-                    Ok(msg_str) => send_outbound(&CallOpOut::Msg(msg_str)),
-                    Err(err) => send_outbound(&CallOpOut::InvalidOutput(err.to_string())),
-                }
+                send_outbound(&CallOpOut::Headers(metadata_pairs(res.metadata())));
+                send_msg(res.get_ref(), 0);
             }
             either::Right(mut res) => {
+                send_outbound(&CallOpOut::Headers(metadata_pairs(res.metadata())));
+                let mut seq = 0;
                 loop {
                     match res.get_mut().next().await {
                         Some(Ok(msg)) => {
-                            match serialize_message(&msg) {
-                                Ok(msg_str) => send_outbound(&CallOpOut::Msg(msg_str)),
-                                Err(err) => send_outbound(&CallOpOut::InvalidOutput(err.to_string())),
-                            }
+                            send_msg(&msg, seq);
+                            seq += 1;
                         },
                         Some(Err(err)) => {
                             send_outbound(&CallOpOut::Err(err.to_string()));
@@ -310,6 +639,9 @@ fn start_call(
                         },
                         None => {
                             // No more messages
+                            if let Ok(Some(trailers)) = res.get_mut().trailers().await {
+                                send_outbound(&CallOpOut::Trailers(metadata_pairs(&trailers)));
+                            }
                             send_outbound(&CallOpOut::Commit);
                             break 'fut;
                         }
@@ -330,39 +662,139 @@ fn start_call(
         // main_fut is already dropped by now so there's no risk to trigger any
         // specific behavior by dropping the closure and all Sender/Receiver
         // that it might own.
-        app_handle.unlisten(event_handler);
+        window.unlisten(event_handler);
     });
 
     Ok(())
 }
 
+/// Returns JSON encoded `ServingStatus` of the server as a whole, as reported by its
+/// `grpc.health.v1.Health` service
 #[tauri::command]
-fn save_settings(app_handle: tauri::AppHandle, content: &str) -> Result<(), String> {
-    let mut config_dir = if let Some(config_dir) = app_handle.path_resolver().app_config_dir() {
-        config_dir
+async fn check_health(endpoint_encoded: String) -> Result<String, String> {
+    let endpoint =
+        serde_json::from_str(&endpoint_encoded).map_err(|_err| "unable to parse endpoint".to_string())?;
+
+    let conn = Conn::new(&endpoint).map_err(|err| err.to_string())?;
+
+    // Servers that are down often don't refuse the connection but let it hang instead
+    let status = tokio::time::timeout(Duration::from_secs(5), health::check(&conn, ""))
+        .await
+        .map_err(|_err| "timed out".to_string())?
+        .map_err(|err| err.to_string())?;
+
+    serde_json::to_string(&status).map_err(|err| err.to_string())
+}
+
+/// Tabs waiting to be picked up by the windows they were detached into
+#[derive(Default)]
+struct DetachedTabs {
+    // JSON encoded `SavedRequest` by label of the window
+    pending: Mutex<HashMap<String, String>>,
+    next_window_id: AtomicUsize,
+}
+
+/// Opens a new window with the JSON encoded `SavedRequest` as its only tab. When
+/// `side_by_side` is set, the calling window and the new one split the monitor
+/// in halves so that the two calls can be compared.
+// Windows must not be created from synchronous commands, it deadlocks on Windows
+#[tauri::command]
+async fn detach_tab(
+    request_encoded: String,
+    side_by_side: bool,
+    window: tauri::Window,
+    detached_tabs: State<'_, DetachedTabs>,
+) -> Result<(), String> {
+    let label = format!("tab-{}", detached_tabs.next_window_id.fetch_add(1, Ordering::Relaxed));
+    detached_tabs
+        .pending
+        .lock()
+        .expect("previous holder panicked")
+        .insert(label.clone(), request_encoded);
+
+    let new_window = WindowBuilder::new(&window.app_handle(), label, WindowUrl::App("index.html".into()))
+        .title("Spaceman")
+        .inner_size(1050.0, 600.0)
+        .min_inner_size(400.0, 300.0)
+        .build()
+        .map_err(|err| err.to_string())?;
+
+    if !side_by_side {
+        return Ok(());
+    }
+    let monitor = if let Some(monitor) = window.current_monitor().map_err(|err| err.to_string())? {
+        monitor
     } else {
-        return Err("Couldn't get app base config path".to_string());
+        return Ok(());
     };
-    std::fs::create_dir_all(&config_dir).map_err(|err| err.to_string())?;
-    config_dir.push("config.json");
-    println!("Writing config at {:?}", &config_dir);
-    std::fs::write(&config_dir, content).map_err(|err| err.to_string())
+    let (position, size) = (monitor.position(), monitor.size());
+    let half_size = PhysicalSize::new(size.width / 2, size.height);
+    for (win, x) in [(&window, position.x), (&new_window, position.x + half_size.width as i32)] {
+        win.unmaximize().map_err(|err| err.to_string())?;
+        win.set_size(half_size).map_err(|err| err.to_string())?;
+        win.set_position(PhysicalPosition::new(x, position.y)).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+/// Returns the JSON encoded `SavedRequest` that the calling window was opened
+/// for by `detach_tab`, if any
+#[tauri::command]
+fn take_detached_tab(window: tauri::Window, detached_tabs: State<DetachedTabs>) -> Option<String> {
+    detached_tabs
+        .pending
+        .lock()
+        .expect("previous holder panicked")
+        .remove(window.label())
+}
+
+#[tauri::command]
+fn save_settings(app_handle: tauri::AppHandle, content: &str) -> Result<(), String> {
+    let path = config_path(&app_handle, "config.json")?;
+    println!("Writing config at {:?}", &path);
+    std::fs::write(&path, content).map_err(|err| err.to_string())
 }
 
 #[tauri::command]
 fn load_settings(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let path = config_path(&app_handle, "config.json")?;
+    println!("Reading config at {:?}", &path);
+    read_config(&path)
+}
+
+/// Saves the JSON encoded `Workspace`, i.e. the tabs that are open
+#[tauri::command]
+fn save_workspace(app_handle: tauri::AppHandle, content: &str) -> Result<(), String> {
+    // Saved periodically so there's no point in logging it
+    let path = config_path(&app_handle, "workspace.json")?;
+    std::fs::write(&path, content).map_err(|err| err.to_string())
+}
+
+#[tauri::command]
+fn load_workspace(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let path = config_path(&app_handle, "workspace.json")?;
+    println!("Reading workspace at {:?}", &path);
+    read_config(&path)
+}
+
+// Path of a file in the config directory, which is created if missing
+fn config_path(app_handle: &tauri::AppHandle, file_name: &str) -> Result<PathBuf, String> {
     let mut config_dir = if let Some(config_dir) = app_handle.path_resolver().app_config_dir() {
         config_dir
     } else {
         return Err("Couldn't get app base config path".to_string());
     };
-    config_dir.push("config.json");
-    println!("Reading config at {:?}", &config_dir);
+    std::fs::create_dir_all(&config_dir).map_err(|err| err.to_string())?;
+    config_dir.push(file_name);
+    Ok(config_dir)
+}
 
-    let exists = config_dir.try_exists().map_err(|err| err.to_string())?;
+// Content of a file in the config directory, empty if the file doesn't exist yet
+fn read_config(path: &Path) -> Result<String, String> {
+    let exists = path.try_exists().map_err(|err| err.to_string())?;
     if !exists {
         return Ok("".to_string());
     }
 
-    std::fs::read_to_string(&config_dir).map_err(|err| err.to_string())
+    std::fs::read_to_string(path).map_err(|err| err.to_string())
 }
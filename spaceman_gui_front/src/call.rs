@@ -7,17 +7,21 @@ use wasm_bindgen::{closure::Closure, JsCast, JsValue};
 use crate::glue;
 use crate::MetadataRow;
 
+use spaceman_types::calloptions::CallOptions;
 use spaceman_types::endpoint::Endpoint;
 use spaceman_types::callopout::CallOpOut;
 
 pub(crate) async fn start_call(
     call_id: i32,
     endpoint: &Endpoint,
+    call_options: &CallOptions,
     method_full_name: &str,
     metadata: &[MetadataRow],
 ) -> Result<(), String> {
     let endpoint =
         serde_json::to_string(endpoint).map_err(|_err| "error serializing endpoint".to_string())?;
+    let call_options = serde_json::to_string(call_options)
+        .map_err(|_err| "error serializing call options".to_string())?;
 
     let o = Object::new();
     Reflect::set(
@@ -32,6 +36,12 @@ pub(crate) async fn start_call(
         &js_sys::JsString::from(endpoint.as_ref()),
     )
     .unwrap();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("callOptionsEncoded"),
+        &js_sys::JsString::from(call_options.as_ref()),
+    )
+    .unwrap();
     Reflect::set(
         &o,
         &js_sys::JsString::from("methodFullName"),
@@ -98,15 +108,25 @@ pub(crate) async fn listen(
     call_id: i32,
     mut f: Box<dyn FnMut(CallOpOut) + 'static>,
 ) -> Listener {
-    let chan_name = format!("o-{}", call_id);
-    let clo = Closure::new(move |js_value| {
-        let s = Reflect::get(&js_value, &JsString::from("payload")).expect("event to have a payload").as_string().expect("payload to be a string");
+    listen_event(&format!("o-{}", call_id), Box::new(move |s| {
         let call_op_out = serde_json::from_str(&s).expect("payload to be deserializable");
 
         f(call_op_out)
+    })).await
+}
+
+// Calls `f` with the payload of every event on the channel until the Listener is dropped
+pub(crate) async fn listen_event(
+    chan_name: &str,
+    mut f: Box<dyn FnMut(String) + 'static>,
+) -> Listener {
+    let clo = Closure::new(move |js_value| {
+        let s = Reflect::get(&js_value, &JsString::from("payload")).expect("event to have a payload").as_string().expect("payload to be a string");
+
+        f(s)
     });
 
-    let unlisten = glue::listen(&chan_name, &clo).await;
+    let unlisten = glue::listen(chan_name, &clo).await;
     let unlisten = unlisten.unchecked_into::<Function>();
 
     Listener { clo, unlisten }
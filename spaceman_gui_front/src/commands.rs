@@ -2,11 +2,11 @@ use std::future::Future;
 
 use js_sys::{Object, Reflect};
 use wasm_bindgen::JsValue;
-use serde_json::to_string_pretty;
+use serde_json::{to_string, to_string_pretty};
 
 use crate::glue::invoke;
 
-use spaceman_types::{repo::RepoView, settings::Settings};
+use spaceman_types::{diff::Difference, docs::Docs, endpoint::Endpoint, export::ExportFormat, import::Import, health::ServingStatus, repo::RepoView, schema::Schema, search::SearchHit, settings::{SavedRequest, Settings}, wire::RawDump, workspace::Workspace};
 
 pub(crate) async fn get_repo_view() -> Result<RepoView, String> {
     invoke("get_repo_view", JsValue::NULL)
@@ -34,13 +34,61 @@ pub(crate) fn add_protobuf_descriptor(path: &str) -> impl Future<Output = Result
     }
 }
 
-pub(crate) async fn reset_repo() -> Result<(), String> {
-    invoke("reset_repo", JsValue::NULL)
+pub(crate) async fn reset_repo(later_overrides: bool) -> Result<(), String> {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("laterOverrides"),
+        &wasm_bindgen::JsValue::from(later_overrides),
+    )
+    .unwrap();
+
+    invoke("reset_repo", o.into())
         .await
         .map(|_| ())
         .map_err(|_err| "error resetting loaded protos".to_string())
 }
 
+// Makes the backend reload the descriptors whenever any of the files changes,
+// see the protos-reloaded event. Returns the directories that don't exist and
+// so aren't watched.
+pub(crate) async fn watch_protos(paths: &[String], later_overrides: bool) -> Result<Vec<String>, String> {
+    let paths_vec = js_sys::Array::new();
+    for path in paths {
+        paths_vec.push(&js_sys::JsString::from(path.as_str()));
+    }
+
+    let o = Object::new();
+    Reflect::set(&o, &js_sys::JsString::from("paths"), &paths_vec).unwrap();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("laterOverrides"),
+        &wasm_bindgen::JsValue::from(later_overrides),
+    )
+    .unwrap();
+
+    let missing = invoke("watch_protos", o.into())
+        .await
+        .map_err(|err| format!("error watching protobuf descriptors: {err}"))?;
+    serde_json::from_str(&missing.as_string().expect("backend to return a string here"))
+        .map_err(|err| format!("error watching protobuf descriptors: {err}"))
+}
+
+pub(crate) async fn get_sample_input_message(method_full_name: &str) -> Result<String, String> {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("methodFullName"),
+        &wasm_bindgen::JsValue::from(method_full_name),
+    )
+    .unwrap();
+
+    invoke("get_sample_input_message", o.into())
+        .await
+        .map_err(|err| format!("error making sample input message: {err}"))
+        .and_then(|ok| ok.as_string().ok_or_else(|| "error making sample input message".to_string()))
+}
+
 pub(crate) async fn get_empty_input_message(method_full_name: &str) -> Result<String, String> {
     let o = Object::new();
     Reflect::set(
@@ -57,6 +105,273 @@ pub(crate) async fn get_empty_input_message(method_full_name: &str) -> Result<St
         .ok_or_else(|| "error getting empty input message".to_string())
 }
 
+pub(crate) async fn get_message_schema(method_full_name: &str) -> Result<Schema, String> {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("methodFullName"),
+        &wasm_bindgen::JsValue::from(method_full_name),
+    )
+    .unwrap();
+
+    invoke("get_message_schema", o.into())
+        .await
+        .ok()
+        .and_then(|ok| serde_json::from_str(&ok.as_string()?).ok())
+        .ok_or_else(|| "error getting message schema".to_string())
+}
+
+pub(crate) async fn get_method_docs(method_full_name: &str) -> Result<Docs, String> {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("methodFullName"),
+        &wasm_bindgen::JsValue::from(method_full_name),
+    )
+    .unwrap();
+
+    invoke("get_method_docs", o.into())
+        .await
+        .ok()
+        .and_then(|ok| serde_json::from_str(&ok.as_string()?).ok())
+        .ok_or_else(|| "error getting method docs".to_string())
+}
+
+pub(crate) async fn get_type_docs(type_full_name: &str) -> Result<Docs, String> {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("typeFullName"),
+        &wasm_bindgen::JsValue::from(type_full_name),
+    )
+    .unwrap();
+
+    invoke("get_type_docs", o.into())
+        .await
+        .ok()
+        .and_then(|ok| serde_json::from_str(&ok.as_string()?).ok())
+        .ok_or_else(|| format!("no docs for {}", type_full_name))
+}
+
+pub(crate) async fn search(query: &str) -> Result<Vec<SearchHit>, String> {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("query"),
+        &wasm_bindgen::JsValue::from(query),
+    )
+    .unwrap();
+
+    invoke("search", o.into())
+        .await
+        .ok()
+        .and_then(|ok| serde_json::from_str(&ok.as_string()?).ok())
+        .ok_or_else(|| "error searching".to_string())
+}
+
+/// Returns the JSON Schema of the input message of the method, ready to be handed to Monaco
+pub(crate) async fn get_json_schema(method_full_name: &str) -> Result<String, String> {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("methodFullName"),
+        &wasm_bindgen::JsValue::from(method_full_name),
+    )
+    .unwrap();
+
+    invoke("get_json_schema", o.into())
+        .await
+        .ok()
+        .and_then(|ok| ok.as_string())
+        .ok_or_else(|| "error getting JSON schema".to_string())
+}
+
+// Dump of the `index`th message received by the call, which the backend keeps until the call is
+// forgotten
+pub(crate) async fn get_raw_dump(call_id: i32, index: usize) -> Result<RawDump, String> {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("callId"),
+        &js_sys::Number::from(call_id),
+    )
+    .unwrap();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("index"),
+        &js_sys::Number::from(index as u32),
+    )
+    .unwrap();
+
+    invoke("get_raw_dump", o.into())
+        .await
+        .map_err(|err| format!("error dumping the message: {err}"))
+        .and_then(|ok| {
+            serde_json::from_str(&ok.as_string().expect("backend to return a string here"))
+                .map_err(|err| format!("error dumping the message: {err}"))
+        })
+}
+
+pub(crate) async fn forget_call(call_id: i32) {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("callId"),
+        &js_sys::Number::from(call_id),
+    )
+    .unwrap();
+
+    let _ = invoke("forget_call", o.into()).await;
+}
+
+pub(crate) async fn check_health(endpoint: &Endpoint) -> Result<ServingStatus, String> {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("endpointEncoded"),
+        &wasm_bindgen::JsValue::from(to_string(endpoint).unwrap()),
+    )
+    .unwrap();
+
+    let status = invoke("check_health", o.into())
+        .await
+        .map_err(|err| err.as_string().unwrap_or_else(|| "error checking health".to_string()))?;
+    serde_json::from_str(&status.as_string().expect("backend to return a string here"))
+        .map_err(|err| err.to_string())
+}
+
+/// Compares two JSON encoded messages field by field, leaving out the `ignore` paths
+pub(crate) async fn diff_messages(left: &str, right: &str, ignore: &[String]) -> Result<Vec<Difference>, String> {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("left"),
+        &wasm_bindgen::JsValue::from(left),
+    )
+    .unwrap();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("right"),
+        &wasm_bindgen::JsValue::from(right),
+    )
+    .unwrap();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("ignoreEncoded"),
+        &wasm_bindgen::JsValue::from(to_string(ignore).unwrap()),
+    )
+    .unwrap();
+
+    let differences = invoke("diff_messages", o.into())
+        .await
+        .map_err(|err| err.as_string().unwrap_or_else(|| "error diffing messages".to_string()))?;
+    serde_json::from_str(&differences.as_string().expect("backend to return a string here"))
+        .map_err(|err| err.to_string())
+}
+
+pub(crate) async fn export_call(
+    endpoint: &Endpoint,
+    method_full_name: &str,
+    metadata: &[(String, String)],
+    body: &str,
+    descriptor_paths: &[String],
+    format: ExportFormat,
+) -> Result<String, String> {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("endpointEncoded"),
+        &wasm_bindgen::JsValue::from(to_string(endpoint).unwrap()),
+    )
+    .unwrap();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("methodFullName"),
+        &wasm_bindgen::JsValue::from(method_full_name),
+    )
+    .unwrap();
+    let metadata_vec = js_sys::Array::new();
+    for (key, val) in metadata {
+        let pair = js_sys::Array::new_with_length(2);
+        pair.set(0, js_sys::JsString::from(key.as_str()).into());
+        pair.set(1, js_sys::JsString::from(val.as_str()).into());
+        metadata_vec.push(&pair);
+    }
+    Reflect::set(&o, &js_sys::JsString::from("metadata"), &metadata_vec).unwrap();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("body"),
+        &wasm_bindgen::JsValue::from(body),
+    )
+    .unwrap();
+    let paths_vec = js_sys::Array::new();
+    for path in descriptor_paths {
+        paths_vec.push(&js_sys::JsString::from(path.as_str()));
+    }
+    Reflect::set(&o, &js_sys::JsString::from("descriptorPaths"), &paths_vec).unwrap();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("formatEncoded"),
+        &wasm_bindgen::JsValue::from(to_string(&format).unwrap()),
+    )
+    .unwrap();
+
+    let snippet = invoke("export_call", o.into())
+        .await
+        .map_err(|err| err.as_string().unwrap_or_else(|| "error exporting call".to_string()))?;
+    Ok(snippet.as_string().expect("backend to return a string here"))
+}
+
+pub(crate) async fn import_requests(text: &str) -> Result<Import, String> {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("text"),
+        &wasm_bindgen::JsValue::from(text),
+    )
+    .unwrap();
+
+    let import = invoke("import_requests", o.into())
+        .await
+        .map_err(|err| err.as_string().unwrap_or_else(|| "error importing requests".to_string()))?;
+    serde_json::from_str(&import.as_string().expect("backend to return a string here"))
+        .map_err(|err| err.to_string())
+}
+
+pub(crate) async fn detach_tab(request: &SavedRequest, side_by_side: bool) -> Result<(), String> {
+    let request = to_string(request).map_err(|err| err.to_string())?;
+
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("requestEncoded"),
+        &wasm_bindgen::JsValue::from(&request),
+    )
+    .unwrap();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("sideBySide"),
+        &wasm_bindgen::JsValue::from(side_by_side),
+    )
+    .unwrap();
+
+    invoke("detach_tab", o.into())
+        .await
+        .map(|_| ())
+        .map_err(|err| err.as_string().unwrap_or_else(|| "error opening window".to_string()))
+}
+
+// The tab that this window was opened for, if it was opened by detach_tab
+pub(crate) async fn take_detached_tab() -> Result<Option<SavedRequest>, String> {
+    let request = invoke("take_detached_tab", JsValue::NULL)
+        .await
+        .map_err(|err| err.as_string().unwrap_or_else(|| "error reading detached tab".to_string()))?;
+    match request.as_string() {
+        Some(request) => serde_json::from_str(&request).map(Some).map_err(|err| err.to_string()),
+        None => Ok(None),
+    }
+}
+
 pub(crate) async fn save_settings(settings: &Settings) -> Result<(), String> {
     let content = to_string_pretty(settings).map_err(|err| err.to_string())?;
 
@@ -90,3 +405,32 @@ pub(crate) async fn load_settings() -> Result<Option<Settings>, String> {
             settings.map(|settings| Some(settings)).map_err(|err| err.to_string())
         })
 }
+
+// Takes the JSON encoded Workspace so that callers can tell whether it changed
+// since it was last saved
+pub(crate) async fn save_workspace(content: &str) -> Result<(), String> {
+    let o = Object::new();
+    Reflect::set(
+        &o,
+        &js_sys::JsString::from("content"),
+        &wasm_bindgen::JsValue::from(content),
+    )
+    .unwrap();
+
+    invoke("save_workspace", o.into())
+        .await
+        .map(|_| ())
+        .map_err(|err| err.as_string().unwrap_or_else(|| "error saving workspace".to_string()))
+}
+
+pub(crate) async fn load_workspace() -> Result<Option<Workspace>, String> {
+    let content = invoke("load_workspace", JsValue::NULL)
+        .await
+        .map_err(|err| err.as_string().unwrap_or_else(|| "error reading workspace".to_string()))?;
+    let content = content.as_string().unwrap_or_else(|| String::new());
+    if content.is_empty() {
+        return Ok(None);
+    }
+
+    serde_json::from_str(&content).map(Some).map_err(|err| err.to_string())
+}
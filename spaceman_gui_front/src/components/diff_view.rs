@@ -0,0 +1,49 @@
+use yew::prelude::*;
+use spaceman_types::diff::Difference;
+
+#[derive(Properties, PartialEq)]
+pub struct DiffViewProps {
+    pub left_label: String,
+    pub right_label: String,
+    pub differences: Vec<Difference>,
+    pub on_close: Callback<()>,
+}
+
+#[function_component]
+pub fn DiffView(props: &DiffViewProps) -> Html {
+    html! {
+        <div class="diff-view">
+            <div class="header">
+                <span class="left">{ &props.left_label }</span>
+                <span class="arrow">{ "→" }</span>
+                <span class="right">{ &props.right_label }</span>
+                <img class="close" src="img/close.svg" onclick={ props.on_close.reform(|_| ()) }/>
+            </div>
+            <div class="content">
+                if props.differences.is_empty() {
+                    <div class="no-differences">{ "No differences" }</div>
+                }
+                {
+                    for props.differences.iter().map(|difference| {
+                        let class = match (&difference.left, &difference.right) {
+                            (Some(_), Some(_)) => "changed",
+                            (Some(_), None) => "removed",
+                            _ => "added",
+                        };
+                        html! {
+                            <div class={ classes!("difference", class) }>
+                                <div class="path">{ &difference.path }</div>
+                                if let Some(left) = &difference.left {
+                                    <div class="left">{ left }</div>
+                                }
+                                if let Some(right) = &difference.right {
+                                    <div class="right">{ right }</div>
+                                }
+                            </div>
+                        }
+                    })
+                }
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,138 @@
+use yew::prelude::*;
+use spaceman_types::docs::{DefinitionDocs, Docs, FieldDocs};
+
+#[derive(Properties, PartialEq)]
+pub struct DocsPanelProps {
+    pub docs: Docs,
+    // Whether there's a previous page to go back to
+    pub can_go_back: bool,
+    // Called with the full name of a clicked message or enum
+    pub on_navigate: Callback<String>,
+    pub on_back: Callback<()>,
+    pub on_close: Callback<()>,
+}
+
+#[function_component]
+pub fn DocsPanel(props: &DocsPanelProps) -> Html {
+    let type_link = |full_name: &str| {
+        let on_navigate = props.on_navigate.clone();
+        let target = full_name.to_string();
+        html! {
+            <span class="type-link" onclick={ move |_| on_navigate.emit(target.clone()) }>{ full_name }</span>
+        }
+    };
+
+    let (kind, name, definition, body) = match &props.docs {
+        Docs::Method(method) => {
+            let stream = |is_streaming: bool| if is_streaming { "stream " } else { "" };
+            let body = html! {
+                <div class="signature">
+                    <span>{ "(" }{ stream(method.is_client_streaming) }</span>
+                    { type_link(&method.input) }
+                    <span>{ ") returns (" }{ stream(method.is_server_streaming) }</span>
+                    { type_link(&method.output) }
+                    <span>{ ")" }</span>
+                </div>
+            };
+            ("rpc", &method.full_name, &method.definition, body)
+        },
+        Docs::Message(message) => {
+            let body = html! {
+                <div class="members">
+                    {
+                        for message.fields.iter().map(|field| view_field(field, &type_link))
+                    }
+                </div>
+            };
+            ("message", &message.full_name, &message.definition, body)
+        },
+        Docs::Enum(desc) => {
+            let body = html! {
+                <div class="members">
+                    {
+                        for desc.values.iter().map(|value| html! {
+                            <div class="member">
+                                <div class="title">
+                                    <span class="name">{ &value.name }</span>
+                                    <span class="number">{ " = " }{ value.number }</span>
+                                </div>
+                                { view_definition(&value.definition) }
+                            </div>
+                        })
+                    }
+                </div>
+            };
+            ("enum", &desc.full_name, &desc.definition, body)
+        },
+    };
+
+    let location = match definition.line {
+        Some(line) => format!("{}:{}", definition.file, line),
+        None => definition.file.clone(),
+    };
+
+    html! {
+        <div class="docs">
+            <div class="header">
+                if props.can_go_back {
+                    <img class="back" src="img/arrow-left.svg" onclick={ props.on_back.reform(|_| ()) }/>
+                }
+                <span class="kind">{ kind }</span>
+                <span class="full-name">{ name }</span>
+                <img class="close" src="img/close.svg" onclick={ props.on_close.reform(|_| ()) }/>
+            </div>
+            <div class="content">
+                <div class="location">{ location }</div>
+                { view_definition(definition) }
+                { body }
+            </div>
+        </div>
+    }
+}
+
+fn view_field(field: &FieldDocs, type_link: &impl Fn(&str) -> Html) -> Html {
+    // Turn the referenced type into a link, leaving the rest of the type name as it is
+    let type_name = match &field.type_ref {
+        Some(type_ref) => {
+            let (before, after) = field.type_name.rsplit_once(type_ref.as_str()).unwrap_or((&field.type_name, ""));
+            html! { <>{ before }{ type_link(type_ref) }{ after }</> }
+        },
+        None => html! { { &field.type_name } },
+    };
+
+    html! {
+        <div class="member">
+            <div class="title">
+                <span class="type">{ type_name }</span>
+                <span class="name">{ " " }{ &field.name }</span>
+                <span class="number">{ " = " }{ field.number }</span>
+                if let Some(oneof) = &field.oneof {
+                    <span class="oneof">{ "oneof " }{ oneof }</span>
+                }
+            </div>
+            { view_definition(&field.definition) }
+        </div>
+    }
+}
+
+fn view_definition(definition: &DefinitionDocs) -> Html {
+    html! {
+        <>
+            if definition.deprecated {
+                <div class="deprecated">{ "Deprecated" }</div>
+            }
+            if let Some(comments) = &definition.comments {
+                <div class="comments">{ comments }</div>
+            }
+            if !definition.options.is_empty() {
+                <div class="options">
+                    {
+                        for definition.options.iter().map(|option| html! {
+                            <div class="option">{ "(" }{ &option.name }{ ") = " }{ &option.value }</div>
+                        })
+                    }
+                </div>
+            }
+        </>
+    }
+}
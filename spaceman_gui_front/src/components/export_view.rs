@@ -0,0 +1,40 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use spaceman_types::export::ExportFormat;
+
+#[derive(Properties, PartialEq)]
+pub struct ExportViewProps {
+    pub format: ExportFormat,
+    pub snippet: String,
+    pub on_format: Callback<ExportFormat>,
+    pub on_close: Callback<()>,
+}
+
+#[function_component]
+pub fn ExportView(props: &ExportViewProps) -> Html {
+    let on_format = props.on_format.clone();
+    let onchange = Callback::from(move |ev: Event| {
+        let val = ev.target_unchecked_into::<HtmlInputElement>().value();
+        if let Some(format) = ExportFormat::from_name(&val) {
+            on_format.emit(format);
+        }
+    });
+
+    html! {
+        <div class="export-view">
+            <div class="header">
+                <select class="select" {onchange}>
+                    {
+                        for ExportFormat::ALL.iter().map(|format| html! {
+                            <option value={ format.as_str() } selected={ *format == props.format }>
+                                { format.as_str() }
+                            </option>
+                        })
+                    }
+                </select>
+                <img class="close" src="img/close.svg" onclick={ props.on_close.reform(|_| ()) }/>
+            </div>
+            <textarea class="content" readonly={true} spellcheck="false" value={ props.snippet.clone() }/>
+        </div>
+    }
+}
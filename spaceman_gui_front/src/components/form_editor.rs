@@ -0,0 +1,604 @@
+use std::collections::HashMap;
+
+use js_sys::Uint8Array;
+use serde_json::{Map, Value};
+use spaceman_types::schema::{Cardinality, FieldKind, FieldSchema, MessageSchema, Schema};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{File, HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+use super::button::Button;
+
+/// A step from a JSON value to one of its children.
+#[derive(Clone, PartialEq)]
+pub enum PathSeg {
+    Key(String),
+    Index(usize),
+}
+
+type Path = Vec<PathSeg>;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BytesMode {
+    Base64,
+    Hex,
+}
+
+/// Structured editor for the JSON representation of a message. It doesn't hold the message
+/// itself: every edit produces a whole new value that is handed to `on_change`, which is expected
+/// to feed it back through `value`.
+pub struct FormEditor {
+    // How each bytes field is displayed, keyed by the path of the field
+    bytes_modes: HashMap<String, BytesMode>,
+}
+
+#[derive(PartialEq, Properties)]
+pub struct FormEditorProps {
+    pub schema: Schema,
+    pub value: Value,
+    pub on_change: Callback<Value>,
+    pub on_error: Callback<String>,
+}
+
+pub enum FormMsg {
+    Set(Path, Value),
+    Remove(Path),
+    SetBytesMode(String, BytesMode),
+    Upload(Path, File),
+    Error(String),
+}
+
+impl Component for FormEditor {
+    type Message = FormMsg;
+    type Properties = FormEditorProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            bytes_modes: HashMap::new(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            FormMsg::Set(path, new_value) => {
+                let mut value = ctx.props().value.clone();
+                set_at(&mut value, &path, new_value);
+                ctx.props().on_change.emit(value);
+                false
+            },
+            FormMsg::Remove(path) => {
+                let mut value = ctx.props().value.clone();
+                remove_at(&mut value, &path);
+                ctx.props().on_change.emit(value);
+                false
+            },
+            FormMsg::SetBytesMode(key, mode) => {
+                self.bytes_modes.insert(key, mode);
+                true
+            },
+            FormMsg::Upload(path, file) => {
+                ctx.link().send_future(async move {
+                    match JsFuture::from(file.array_buffer()).await {
+                        Ok(buf) => {
+                            let bytes = Uint8Array::new(&buf).to_vec();
+                            FormMsg::Set(path, Value::String(base64::encode(bytes)))
+                        },
+                        Err(_err) => FormMsg::Error(format!("Couldn't read {}", file.name())),
+                    }
+                });
+                false
+            },
+            FormMsg::Error(err) => {
+                ctx.props().on_error.emit(err);
+                false
+            },
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let schema = &ctx.props().schema;
+        html! {
+            <div class="form-editor">
+                if let Some(root) = schema.root() {
+                    { self.view_message(ctx, root, &ctx.props().value, Vec::new()) }
+                }
+            </div>
+        }
+    }
+}
+
+impl FormEditor {
+    fn view_message(&self, ctx: &Context<Self>, msg: &MessageSchema, value: &Value, path: Path) -> Html {
+        let empty = Map::new();
+        let obj = value.as_object().unwrap_or(&empty);
+
+        let plain_fields = msg.fields.iter().filter(|field| field.oneof.is_none()).map(|field| {
+            let key = field_key(obj, field);
+            let path = with(&path, PathSeg::Key(key.clone()));
+            html! {
+                <div class="field">
+                    <label title={ format!("{} = {}", field.name, field.number) }>{ field.name.clone() }</label>
+                    { self.view_field(ctx, field, obj.get(&key), path) }
+                </div>
+            }
+        });
+
+        let oneofs = msg.oneofs.iter().enumerate().map(|(oneof_idx, oneof_name)| {
+            let members: Vec<&FieldSchema> = msg.fields.iter().filter(|field| field.oneof == Some(oneof_idx)).collect();
+            let selected = members.iter().find(|field| obj.contains_key(&field_key(obj, field))).cloned();
+
+            let onchange = {
+                let (obj, path, schema) = (obj.clone(), path.clone(), ctx.props().schema.clone());
+                let members: Vec<FieldSchema> = members.iter().map(|&field| field.clone()).collect();
+                ctx.link().callback(move |ev: Event| {
+                    let chosen = ev.target_unchecked_into::<HtmlSelectElement>().value();
+                    let mut obj = obj.clone();
+                    for field in &members {
+                        obj.remove(&field.json_name);
+                        obj.remove(&field.name);
+                    }
+                    if let Some(field) = members.iter().find(|field| field.name == chosen) {
+                        obj.insert(field.json_name.clone(), default_for_field(&schema, field));
+                    }
+                    FormMsg::Set(path.clone(), Value::Object(obj))
+                })
+            };
+
+            html! {
+                <div class="field oneof">
+                    <label>{ oneof_name.clone() }</label>
+                    <div class="value">
+                        <select class="select" { onchange }>
+                            <option value="" selected={ selected.is_none() }>{ "(none)" }</option>
+                            { for members.iter().map(|field| html! {
+                                <option value={ field.name.clone() } selected={ selected.map(|selected| selected.name == field.name).unwrap_or(false) }>
+                                    { field.name.clone() }
+                                </option>
+                            }) }
+                        </select>
+                        if let Some(field) = selected {
+                            {{
+                                let key = field_key(obj, field);
+                                self.view_field(ctx, field, obj.get(&key), with(&path, PathSeg::Key(key.clone())))
+                            }}
+                        }
+                    </div>
+                </div>
+            }
+        });
+
+        html! {
+            <div class="message">
+                { for plain_fields }
+                { for oneofs }
+            </div>
+        }
+    }
+
+    fn view_field(&self, ctx: &Context<Self>, field: &FieldSchema, value: Option<&Value>, path: Path) -> Html {
+        let schema = &ctx.props().schema;
+        match &field.cardinality {
+            Cardinality::Single => self.view_single(ctx, &field.kind, value, path, true),
+            Cardinality::Repeated => {
+                let items = value.and_then(|value| value.as_array()).cloned().unwrap_or_default();
+                let add = {
+                    let (items, path, default) = (items.clone(), path.clone(), default_for_kind(schema, &field.kind));
+                    ctx.link().callback(move |_| {
+                        let mut items = items.clone();
+                        items.push(default.clone());
+                        FormMsg::Set(path.clone(), Value::Array(items))
+                    })
+                };
+                html! {
+                    <div class="value list">
+                        { for items.iter().enumerate().map(|(idx, item)| {
+                            let item_path = with(&path, PathSeg::Index(idx));
+                            let remove = {
+                                let item_path = item_path.clone();
+                                ctx.link().callback(move |_| FormMsg::Remove(item_path.clone()))
+                            };
+                            html! {
+                                <div class="item">
+                                    { self.view_single(ctx, &field.kind, Some(item), item_path, false) }
+                                    <img class="delete" src="img/trash-can.svg" onclick={ remove }/>
+                                </div>
+                            }
+                        }) }
+                        <Button onclick={ add } icon="img/plus.svg" text="Add"/>
+                    </div>
+                }
+            },
+            Cardinality::Map { key: key_kind } => {
+                let entries = value.and_then(|value| value.as_object()).cloned().unwrap_or_default();
+                let add = {
+                    let (entries, path, default, key_kind) = (entries.clone(), path.clone(), default_for_kind(schema, &field.kind), key_kind.clone());
+                    ctx.link().callback(move |_| {
+                        let mut entries = entries.clone();
+                        // Bool maps are full once both keys are taken
+                        if let Some(key) = unused_map_key(&key_kind, &entries) {
+                            entries.insert(key, default.clone());
+                        }
+                        FormMsg::Set(path.clone(), Value::Object(entries))
+                    })
+                };
+                html! {
+                    <div class="value list">
+                        { for entries.iter().map(|(key, entry)| {
+                            let entry_path = with(&path, PathSeg::Key(key.clone()));
+                            let rename = {
+                                let (entries, path, old_key) = (entries.clone(), path.clone(), key.clone());
+                                ctx.link().callback(move |ev: Event| {
+                                    let new_key = ev.target_unchecked_into::<HtmlInputElement>().value();
+                                    let mut entries = entries.clone();
+                                    if let Some(entry) = entries.remove(&old_key) {
+                                        entries.insert(new_key, entry);
+                                    }
+                                    FormMsg::Set(path.clone(), Value::Object(entries))
+                                })
+                            };
+                            let remove = {
+                                let entry_path = entry_path.clone();
+                                ctx.link().callback(move |_| FormMsg::Remove(entry_path.clone()))
+                            };
+                            html! {
+                                <div class="item">
+                                    <input class="input key" type="text" value={ key.clone() } placeholder="Key" onchange={ rename }/>
+                                    { self.view_single(ctx, &field.kind, Some(entry), entry_path, false) }
+                                    <img class="delete" src="img/trash-can.svg" onclick={ remove }/>
+                                </div>
+                            }
+                        }) }
+                        <Button onclick={ add } icon="img/plus.svg" text="Add"/>
+                    </div>
+                }
+            },
+        }
+    }
+
+    /// Editor for a single value of the given kind. `optional` tells whether the value is a field
+    /// that can be left out of its message, as opposed to an element of a list or map.
+    fn view_single(&self, ctx: &Context<Self>, kind: &FieldKind, value: Option<&Value>, path: Path, optional: bool) -> Html {
+        let schema = &ctx.props().schema;
+        match kind {
+            FieldKind::Bool => {
+                let onchange = {
+                    let path = path.clone();
+                    ctx.link().callback(move |ev: Event| {
+                        let checked = ev.target_unchecked_into::<HtmlInputElement>().checked();
+                        FormMsg::Set(path.clone(), Value::Bool(checked))
+                    })
+                };
+                html! {
+                    <input class="input" type="checkbox" checked={ value.and_then(|value| value.as_bool()).unwrap_or(false) } { onchange }/>
+                }
+            },
+            FieldKind::String => {
+                let oninput = {
+                    let path = path.clone();
+                    ctx.link().callback(move |ev: InputEvent| {
+                        FormMsg::Set(path.clone(), Value::String(ev.target_unchecked_into::<HtmlInputElement>().value()))
+                    })
+                };
+                html! {
+                    <input class="input" type="text" value={ value.and_then(|value| value.as_str()).unwrap_or("").to_string() } { oninput }/>
+                }
+            },
+            FieldKind::Bytes => self.view_bytes(ctx, value, path),
+            FieldKind::Enum(name) => {
+                let values = schema.enums.get(name).map(|desc| desc.values.clone()).unwrap_or_default();
+                // Null is the only value of the NullValue enum
+                if name == "google.protobuf.NullValue" {
+                    return html! { <span class="literal">{ "null" }</span> };
+                }
+                let current = match value {
+                    Some(Value::String(name)) => Some(name.clone()),
+                    Some(Value::Number(number)) => values.iter()
+                        .find(|value| Some(value.number as i64) == number.as_i64())
+                        .map(|value| value.name.clone()),
+                    _ => values.iter().find(|value| value.number == 0).map(|value| value.name.clone()),
+                };
+                let onchange = {
+                    let path = path.clone();
+                    ctx.link().callback(move |ev: Event| {
+                        FormMsg::Set(path.clone(), Value::String(ev.target_unchecked_into::<HtmlSelectElement>().value()))
+                    })
+                };
+                html! {
+                    <select class="select" { onchange }>
+                        { for values.iter().map(|value| html! {
+                            <option value={ value.name.clone() } selected={ current.as_ref() == Some(&value.name) }>{ value.name.clone() }</option>
+                        }) }
+                    </select>
+                }
+            },
+            FieldKind::Message(name) => {
+                if let Some(wrapped) = wrapper_kind(name) {
+                    return self.view_single(ctx, &wrapped, value, path, optional);
+                }
+                match (schema.messages.get(name), value) {
+                    (Some(_), None | Some(Value::Null)) => {
+                        let default = default_for_kind(schema, kind);
+                        html! {
+                            <Button
+                                onclick={ ctx.link().callback(move |_| FormMsg::Set(path.clone(), default.clone())) }
+                                icon="img/plus.svg"
+                                text="Set"/>
+                        }
+                    },
+                    (Some(msg), Some(value)) => {
+                        let unset = {
+                            let path = path.clone();
+                            ctx.link().callback(move |_| FormMsg::Remove(path.clone()))
+                        };
+                        html! {
+                            <div class="value nested">
+                                if optional {
+                                    <img class="delete" title="Unset" src="img/trash-can.svg" onclick={ unset }/>
+                                }
+                                { self.view_message(ctx, msg, value, path) }
+                            </div>
+                        }
+                    },
+                    (None, value) => self.view_well_known(ctx, name, value, path),
+                }
+            },
+            kind => self.view_number(ctx, kind, value, path, optional),
+        }
+    }
+
+    fn view_number(&self, ctx: &Context<Self>, kind: &FieldKind, value: Option<&Value>, path: Path, optional: bool) -> Html {
+        let current = match value {
+            Some(Value::Number(number)) => number.to_string(),
+            Some(Value::String(string)) => string.clone(),
+            _ => String::new(),
+        };
+        let kind = kind.clone();
+        let oninput = ctx.link().batch_callback(move |ev: InputEvent| {
+            let raw = ev.target_unchecked_into::<HtmlInputElement>().value();
+            if raw.is_empty() {
+                // Leave elements of lists and maps alone while the user is still typing
+                return optional.then(|| FormMsg::Remove(path.clone()));
+            }
+            let value = if kind.is_64_bit() {
+                // Keep them as strings, like the JSON mapping does
+                raw.parse::<i128>().ok().map(|_| Value::String(raw))
+            } else if matches!(kind, FieldKind::Double | FieldKind::Float) {
+                raw.parse::<f64>().ok().and_then(serde_json::Number::from_f64).map(Value::Number)
+            } else {
+                raw.parse::<i64>().ok().map(Value::from)
+            };
+            value.map(|value| FormMsg::Set(path.clone(), value))
+        });
+        html! {
+            <input class="input" type="text" inputmode="decimal" value={ current } placeholder="0" { oninput }/>
+        }
+    }
+
+    fn view_bytes(&self, ctx: &Context<Self>, value: Option<&Value>, path: Path) -> Html {
+        let mode_key = path_key(&path);
+        let mode = self.bytes_modes.get(&mode_key).copied().unwrap_or(BytesMode::Base64);
+        let encoded = value.and_then(|value| value.as_str()).unwrap_or("").to_string();
+        let shown = match mode {
+            BytesMode::Base64 => encoded,
+            BytesMode::Hex => base64::decode(&encoded).map(hex::encode).unwrap_or(encoded),
+        };
+
+        let oninput = {
+            let path = path.clone();
+            ctx.link().batch_callback(move |ev: InputEvent| {
+                let raw = ev.target_unchecked_into::<HtmlInputElement>().value();
+                let encoded = match mode {
+                    BytesMode::Base64 => base64::decode(&raw).ok().map(|_| raw),
+                    BytesMode::Hex => hex::decode(raw.trim()).ok().map(base64::encode),
+                };
+                // Wait for the input to be well-formed before updating the message
+                encoded.map(|encoded| FormMsg::Set(path.clone(), Value::String(encoded)))
+            })
+        };
+        let onchange_mode = ctx.link().callback(move |ev: Event| {
+            let mode = if ev.target_unchecked_into::<HtmlSelectElement>().value() == "hex" {
+                BytesMode::Hex
+            } else {
+                BytesMode::Base64
+            };
+            FormMsg::SetBytesMode(mode_key.clone(), mode)
+        });
+        let onchange_file = ctx.link().batch_callback(move |ev: Event| {
+            let input = ev.target_unchecked_into::<HtmlInputElement>();
+            input.files()
+                .and_then(|files| files.get(0))
+                .map(|file| FormMsg::Upload(path.clone(), file))
+        });
+
+        html! {
+            <div class="value bytes">
+                <select class="select" onchange={ onchange_mode }>
+                    <option value="base64" selected={ mode == BytesMode::Base64 }>{ "Base64" }</option>
+                    <option value="hex" selected={ mode == BytesMode::Hex }>{ "Hex" }</option>
+                </select>
+                <input class="input" type="text" value={ shown } { oninput }/>
+                <label class="upload" title="Load from file">
+                    <img src="img/plus.svg"/>
+                    <input type="file" onchange={ onchange_file }/>
+                </label>
+            </div>
+        }
+    }
+
+    /// Well-known types that don't map to a JSON object are either edited as text or, when they
+    /// hold arbitrary JSON, as raw JSON.
+    fn view_well_known(&self, ctx: &Context<Self>, name: &str, value: Option<&Value>, path: Path) -> Html {
+        let placeholder = match name {
+            "google.protobuf.Timestamp" => Some("1970-01-01T00:00:00Z"),
+            "google.protobuf.Duration" => Some("1.5s"),
+            "google.protobuf.FieldMask" => Some("path.to.field,other"),
+            _ => None,
+        };
+        if let Some(placeholder) = placeholder {
+            let oninput = ctx.link().callback(move |ev: InputEvent| {
+                FormMsg::Set(path.clone(), Value::String(ev.target_unchecked_into::<HtmlInputElement>().value()))
+            });
+            return html! {
+                <input class="input" type="text" value={ value.and_then(|value| value.as_str()).unwrap_or("").to_string() } { placeholder } { oninput }/>
+            };
+        }
+        if name == "google.protobuf.Empty" {
+            return html! { <span class="literal">{ "{}" }</span> };
+        }
+
+        let onchange = ctx.link().callback(move |ev: Event| {
+            let raw = ev.target_unchecked_into::<HtmlTextAreaElement>().value();
+            match serde_json::from_str(&raw) {
+                Ok(value) => FormMsg::Set(path.clone(), value),
+                Err(err) => FormMsg::Error(format!("Invalid JSON: {err}")),
+            }
+        });
+        html! {
+            <textarea class="input json" value={ value.map(|value| serde_json::to_string_pretty(value).unwrap()).unwrap_or_default() } { onchange }/>
+        }
+    }
+}
+
+/// JSON key under which the field is stored in `obj`. Parsers accept both the original and the
+/// JSON name so whichever is already used wins, and the JSON name is used for new fields.
+fn field_key(obj: &Map<String, Value>, field: &FieldSchema) -> String {
+    if !obj.contains_key(&field.json_name) && obj.contains_key(&field.name) {
+        field.name.clone()
+    } else {
+        field.json_name.clone()
+    }
+}
+
+fn with(path: &Path, seg: PathSeg) -> Path {
+    let mut path = path.clone();
+    path.push(seg);
+    path
+}
+
+fn path_key(path: &Path) -> String {
+    path.iter()
+        .map(|seg| match seg {
+            PathSeg::Key(key) => format!(".{key}"),
+            PathSeg::Index(idx) => format!("[{idx}]"),
+        })
+        .collect()
+}
+
+fn set_at(value: &mut Value, path: &[PathSeg], new_value: Value) {
+    let (seg, rest) = match path.split_first() {
+        Some(split) => split,
+        None => {
+            *value = new_value;
+            return;
+        }
+    };
+    match seg {
+        PathSeg::Key(key) => {
+            if !value.is_object() {
+                *value = Value::Object(Map::new());
+            }
+            let child = value.as_object_mut().unwrap().entry(key.clone()).or_insert(Value::Null);
+            set_at(child, rest, new_value);
+        },
+        PathSeg::Index(idx) => {
+            if let Some(child) = value.as_array_mut().and_then(|items| items.get_mut(*idx)) {
+                set_at(child, rest, new_value);
+            }
+        },
+    }
+}
+
+fn remove_at(value: &mut Value, path: &[PathSeg]) {
+    match path {
+        [] => *value = Value::Null,
+        [PathSeg::Key(key)] => {
+            if let Some(obj) = value.as_object_mut() {
+                obj.remove(key);
+            }
+        },
+        [PathSeg::Index(idx)] => {
+            if let Some(items) = value.as_array_mut().filter(|items| *idx < items.len()) {
+                items.remove(*idx);
+            }
+        },
+        [seg, rest @ ..] => {
+            let child = match seg {
+                PathSeg::Key(key) => value.get_mut(key.as_str()),
+                PathSeg::Index(idx) => value.get_mut(*idx),
+            };
+            if let Some(child) = child {
+                remove_at(child, rest);
+            }
+        },
+    }
+}
+
+/// Scalar kind wrapped by one of the `google.protobuf` wrapper messages, which are represented in
+/// JSON by the wrapped value alone.
+fn wrapper_kind(name: &str) -> Option<FieldKind> {
+    Some(match name {
+        "google.protobuf.DoubleValue" => FieldKind::Double,
+        "google.protobuf.FloatValue" => FieldKind::Float,
+        "google.protobuf.Int64Value" => FieldKind::Int64,
+        "google.protobuf.UInt64Value" => FieldKind::Uint64,
+        "google.protobuf.Int32Value" => FieldKind::Int32,
+        "google.protobuf.UInt32Value" => FieldKind::Uint32,
+        "google.protobuf.BoolValue" => FieldKind::Bool,
+        "google.protobuf.StringValue" => FieldKind::String,
+        "google.protobuf.BytesValue" => FieldKind::Bytes,
+        _ => return None,
+    })
+}
+
+fn default_for_field(schema: &Schema, field: &FieldSchema) -> Value {
+    match field.cardinality {
+        Cardinality::Single => default_for_kind(schema, &field.kind),
+        Cardinality::Repeated => Value::Array(Vec::new()),
+        Cardinality::Map { .. } => Value::Object(Map::new()),
+    }
+}
+
+/// The first key of kind `kind` that isn't in `entries`, as it's written in JSON. Bool keys are
+/// `"false"` and `"true"`, not numbers.
+fn unused_map_key(kind: &FieldKind, entries: &Map<String, Value>) -> Option<String> {
+    match kind {
+        FieldKind::Bool => ["false", "true"]
+            .into_iter()
+            .map(str::to_string)
+            .find(|key| !entries.contains_key(key)),
+        FieldKind::String => (0..)
+            .map(|len| "_".repeat(len))
+            .find(|key| !entries.contains_key(key)),
+        _ => (0..)
+            .map(|number: u64| number.to_string())
+            .find(|key| !entries.contains_key(key)),
+    }
+}
+
+fn default_for_kind(schema: &Schema, kind: &FieldKind) -> Value {
+    match kind {
+        FieldKind::Bool => Value::Bool(false),
+        FieldKind::String | FieldKind::Bytes => Value::String(String::new()),
+        FieldKind::Enum(name) if name == "google.protobuf.NullValue" => Value::Null,
+        FieldKind::Enum(name) => schema.enums
+            .get(name)
+            .and_then(|desc| desc.values.iter().find(|value| value.number == 0).or(desc.values.first()))
+            .map(|value| Value::String(value.name.clone()))
+            .unwrap_or(Value::Null),
+        FieldKind::Message(name) => {
+            if let Some(wrapped) = wrapper_kind(name) {
+                return default_for_kind(schema, &wrapped);
+            }
+            match name.as_str() {
+                "google.protobuf.Timestamp" => Value::String("1970-01-01T00:00:00Z".to_string()),
+                "google.protobuf.Duration" => Value::String("0s".to_string()),
+                "google.protobuf.FieldMask" => Value::String(String::new()),
+                "google.protobuf.ListValue" => Value::Array(Vec::new()),
+                "google.protobuf.Value" => Value::Null,
+                _ => Value::Object(Map::new()),
+            }
+        },
+        kind if kind.is_64_bit() => Value::String("0".to_string()),
+        _ => Value::from(0),
+    }
+}
@@ -0,0 +1,31 @@
+use yew::prelude::*;
+use spaceman_types::health::ServingStatus;
+
+/// Last known health of a profile's server.
+#[derive(Clone, PartialEq)]
+pub enum Health {
+    Status(ServingStatus),
+    // The check itself failed, the string says why
+    Unreachable(String),
+}
+
+#[derive(PartialEq, Properties)]
+pub struct HealthLedProps {
+    // None while the first check is still in flight
+    pub health: Option<Health>,
+}
+
+#[function_component]
+pub fn HealthLed(props: &HealthLedProps) -> Html {
+    let (class, title) = match &props.health {
+        None => (None, "Checking health...".to_string()),
+        Some(Health::Status(ServingStatus::Serving)) => (Some("serving"), "Serving".to_string()),
+        Some(Health::Status(ServingStatus::NotServing)) => (Some("not-serving"), "Not serving".to_string()),
+        Some(Health::Status(ServingStatus::ServiceUnknown | ServingStatus::Unknown)) => (Some("unknown"), "Unknown status".to_string()),
+        Some(Health::Unreachable(err)) => (Some("unreachable"), format!("Unreachable: {err}")),
+    };
+
+    html! {
+        <div class={ classes!("health-led", class) } { title }></div>
+    }
+}
@@ -3,4 +3,11 @@ pub mod button;
 pub mod repo;
 pub mod metadata_editor;
 pub mod errors;
+pub mod diff_view;
+pub mod export_view;
+pub mod raw_view;
+pub mod docs;
+pub mod form_editor;
+pub mod health;
 pub mod settings;
+pub mod timeline;
@@ -0,0 +1,68 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use spaceman_types::wire::RawDump;
+
+/// How a received message is shown when looking at its encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RawFormat {
+    // One field per line, decoded without the descriptors
+    Wire,
+    Hex,
+}
+
+impl RawFormat {
+    pub const ALL: [RawFormat; 2] = [RawFormat::Wire, RawFormat::Hex];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RawFormat::Wire => "wire",
+            RawFormat::Hex => "hex",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.as_str() == name)
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct RawViewProps {
+    pub raw: RawDump,
+    pub format: RawFormat,
+    pub on_format: Callback<RawFormat>,
+    pub on_close: Callback<()>,
+}
+
+#[function_component]
+pub fn RawView(props: &RawViewProps) -> Html {
+    let on_format = props.on_format.clone();
+    let onchange = Callback::from(move |ev: Event| {
+        let val = ev.target_unchecked_into::<HtmlInputElement>().value();
+        if let Some(format) = RawFormat::from_name(&val) {
+            on_format.emit(format);
+        }
+    });
+
+    let content = match props.format {
+        RawFormat::Wire => props.raw.wire.clone(),
+        RawFormat::Hex => props.raw.hex.clone(),
+    };
+
+    html! {
+        <div class="raw-view">
+            <div class="header">
+                <select class="select" {onchange}>
+                    {
+                        for RawFormat::ALL.iter().map(|format| html! {
+                            <option value={ format.as_str() } selected={ *format == props.format }>
+                                { format.as_str() }
+                            </option>
+                        })
+                    }
+                </select>
+                <img class="close" src="img/close.svg" onclick={ props.on_close.reform(|_| ()) }/>
+            </div>
+            <textarea class="content" readonly={true} spellcheck="false" value={ content }/>
+        </div>
+    }
+}
@@ -1,30 +1,67 @@
-use wasm_bindgen::JsCast;
 use web_sys::HtmlInputElement;
+use yew::platform::spawn_local;
 use yew::prelude::*;
 use spaceman_types::repo::{RepoView, ServiceView, MethodView};
+use spaceman_types::search::{SearchHit, SymbolKind};
+
+use crate::commands::search;
 
 #[derive(Properties, PartialEq)]
 pub struct RepoProps {
     pub repo_view: Option<RepoView>,
     pub on_new_tab: Callback<(usize, usize)>,
+    // Called with the full name of the method whose docs should be shown
+    pub on_show_docs: Callback<String>,
+    // Called with the full name of the message or enum whose docs should be shown
+    pub on_show_type_docs: Callback<String>,
 }
 
 #[function_component]
 pub fn Repo(props: &RepoProps) -> Html {
     let query = use_state_eq::<Option<String>, _>(|| None);
-    
-    let content = props.repo_view.as_ref().map(
-        |repo_view| repo_view.services.clone()
-    ).unwrap_or_else(|| Vec::new()).into_iter().enumerate().map(|(service_idx, service_view)| {
-        html!{ <Service service_view={ service_view } query={ (*query).clone() } on_new_tab={
-            Callback::from({
-                let cb = props.on_new_tab.clone();
-                move |method_idx: usize| {
-                    cb.emit((service_idx, method_idx));
-                }
-            }) 
-        }/> }
-    }).collect::<Html>();
+    let hits = use_state_eq::<Vec<SearchHit>, _>(|| Vec::new());
+    // Bumped for every search so that results arriving out of order are dropped
+    let generation = use_mut_ref(|| 0u32);
+
+    {
+        let hits = hits.clone();
+        // Search again when the protos are reloaded too
+        use_effect_with_deps(move |(query, _)| {
+            *generation.borrow_mut() += 1;
+            let current = *generation.borrow();
+            match query.clone() {
+                None => hits.set(Vec::new()),
+                Some(query) => spawn_local(async move {
+                    if let Ok(result) = search(&query).await {
+                        if *generation.borrow() == current {
+                            hits.set(result);
+                        }
+                    }
+                }),
+            }
+            || ()
+        }, ((*query).clone(), props.repo_view.clone()));
+    }
+
+    let content = if query.is_some() {
+        hits.iter().cloned().map(|hit| {
+            html!{ <Hit { hit } repo_view={ props.repo_view.clone() } on_new_tab={ props.on_new_tab.clone() }
+                on_show_docs={ props.on_show_docs.clone() } on_show_type_docs={ props.on_show_type_docs.clone() }/> }
+        }).collect::<Html>()
+    } else {
+        props.repo_view.as_ref().map(
+            |repo_view| repo_view.services.clone()
+        ).unwrap_or_else(|| Vec::new()).into_iter().enumerate().map(|(service_idx, service_view)| {
+            html!{ <Service service_view={ service_view } on_show_docs={ props.on_show_docs.clone() } on_new_tab={
+                Callback::from({
+                    let cb = props.on_new_tab.clone();
+                    move |method_idx: usize| {
+                        cb.emit((service_idx, method_idx));
+                    }
+                }) 
+            }/> }
+        }).collect::<Html>()
+    };
 
     let oninput = Callback::from(move |ev: InputEvent| {
         let raw = ev.target_unchecked_into::<HtmlInputElement>().value();
@@ -45,31 +82,95 @@ pub fn Repo(props: &RepoProps) -> Html {
     }
 }
 
+/// Indices of the service and of the method in the `RepoView`, as `on_new_tab` wants them.
+fn method_indices(repo_view: &RepoView, method_full_name: &str) -> Option<(usize, usize)> {
+    repo_view.services.iter().enumerate().find_map(|(service_idx, service)| {
+        let method_idx = service.methods.iter().position(|method| method.full_name == method_full_name)?;
+        Some((service_idx, method_idx))
+    })
+}
+
+#[derive(PartialEq, Properties)]
+struct HitProps {
+    hit: SearchHit,
+    repo_view: Option<RepoView>,
+    on_new_tab: Callback<(usize, usize)>,
+    on_show_docs: Callback<String>,
+    on_show_type_docs: Callback<String>,
+}
+
+#[function_component]
+fn Hit(props: &HitProps) -> Html {
+    let hit = &props.hit;
+
+    let open_method = |method_full_name: &str| {
+        let cb = props.on_new_tab.clone();
+        let indices = props.repo_view.as_ref().and_then(|repo_view| method_indices(repo_view, method_full_name));
+        Callback::from(move |_: MouseEvent| {
+            if let Some(indices) = indices {
+                cb.emit(indices);
+            }
+        })
+    };
+
+    // Clicking the name opens the method or the docs of the type the symbol lives in
+    let onclick = match hit.kind {
+        SymbolKind::Method => Some(open_method(&hit.full_name)),
+        SymbolKind::Message | SymbolKind::Enum => {
+            Some(props.on_show_type_docs.reform({
+                let full_name = hit.full_name.clone();
+                move |_: MouseEvent| full_name.clone()
+            }))
+        },
+        SymbolKind::Field | SymbolKind::EnumValue => {
+            hit.parent.clone().map(|parent| props.on_show_type_docs.reform(move |_: MouseEvent| parent.clone()))
+        },
+        SymbolKind::Service => None,
+    };
+
+    let name_class = classes!("name", onclick.is_some().then(|| "clickable"));
+
+    let show_docs = props.on_show_docs.reform({
+        let full_name = hit.full_name.clone();
+        move |_: MouseEvent| full_name.clone()
+    });
+
+    html! {
+        <div class="hit">
+            <div class="title">
+                <span class="kind">{ hit.kind.as_str() }</span>
+                <span class={ name_class } { onclick }>{ &hit.full_name }</span>
+                if hit.kind == SymbolKind::Method {
+                    <img class="show-docs" src="img/information.svg" title="Documentation" onclick={ show_docs }/>
+                }
+            </div>
+            if let Some(comment) = &hit.comment {
+                <div class="comment">{ comment }</div>
+            }
+            if !matches!(hit.kind, SymbolKind::Method | SymbolKind::Service) {
+                <div class="used-by">
+                    {
+                        for hit.methods.iter().map(|method| html! {
+                            <span class="method" onclick={ open_method(method) }>{ method }</span>
+                        })
+                    }
+                </div>
+            }
+        </div>
+    }
+}
+
 #[derive(PartialEq, Properties)]
 struct ServiceProps {
     service_view: ServiceView,
-    query: Option<String>,
     on_new_tab: Callback<usize>,
+    on_show_docs: Callback<String>,
 }
 
 #[function_component]
 fn Service(props: &ServiceProps) -> Html {
     let methods_n = props.service_view.methods.len();
-    let mut methods: Vec<_> = props.service_view.methods.iter().enumerate().filter_map(|(method_idx, method_view)| {
-        match &props.query {
-            Some(query) if !method_view.name.to_lowercase().contains(&query.to_lowercase()) => {
-                None
-            },
-            _ => {
-                Some((method_view.clone(), method_idx, false))
-            }
-        }
-    }).collect();
-
-    if let Some((_, _, is_last)) = methods.last_mut() {
-        *is_last = true;
-    } else {
-        // There are no methods
+    if methods_n == 0 {
         return Html::default();
     }
 
@@ -77,8 +178,10 @@ fn Service(props: &ServiceProps) -> Html {
         <div class="service">
             <div class="name">{ props.service_view.full_name.clone() }</div>
             {
-                for methods.into_iter().map(|(method_view, method_idx, is_last)| {
-                    html!{ <Method {method_view} {is_last} on_new_tab={
+                for props.service_view.methods.iter().enumerate().map(|(method_idx, method_view)| {
+                    let method_view = method_view.clone();
+                    let is_last = method_idx == methods_n - 1;
+                    html!{ <Method {method_view} {is_last} on_show_docs={ props.on_show_docs.clone() } on_new_tab={
                         Callback::from({
                             let cb = props.on_new_tab.clone() ;
                             move |_| {
@@ -97,6 +200,7 @@ struct MethodProps {
     is_last: bool,
     method_view: MethodView,
     on_new_tab: Callback<()>,
+    on_show_docs: Callback<String>,
 }
 
 #[function_component]
@@ -108,6 +212,14 @@ fn Method(props: &MethodProps) -> Html {
         }
     });
 
+    let show_docs = Callback::from({
+        let cb = props.on_show_docs.clone();
+        let full_name = props.method_view.full_name.clone();
+        move |_| {
+            cb.emit(full_name.clone())
+        }
+    });
+
     html! {
         <div class="method">
             {
@@ -118,6 +230,7 @@ fn Method(props: &MethodProps) -> Html {
                 }
             }
             <div class="name" { onclick }>{ props.method_view.name.clone() }</div>
+            <img class="show-docs" src="img/information.svg" title="Documentation" onclick={ show_docs }/>
         </div>
     }
 }
@@ -1,17 +1,27 @@
-use web_sys::HtmlInputElement;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
 use yew::prelude::*;
-use spaceman_types::{settings::{Settings, Profile}, endpoint::Endpoint};
+use spaceman_types::{settings::{Settings, Profile}, endpoint::{ConnectionOptions, Endpoint, LbPolicy, ProxyAuth, ProxyKind}};
 use uuid::Uuid;
 
 use crate::components::button::Button;
+use crate::components::health::{Health, HealthLed};
 
-pub struct SettingsEditor {}
+pub struct SettingsEditor {
+    import_text: NodeRef,
+}
 
 #[derive(PartialEq, Properties)]
 pub struct SettingsProps {
     pub settings: Settings,
+    pub health: HashMap<Uuid, Health>,
     pub set_settings: Callback<Settings>,
     pub leave_settings: Callback<()>,
+    // Called with grpcurl command lines or a Postman collection to import
+    pub on_import: Callback<String>,
 }
 
 impl Component for SettingsEditor {
@@ -19,7 +29,9 @@ impl Component for SettingsEditor {
     type Properties = SettingsProps;
 
     fn create(ctx: &Context<Self>) -> Self {
-        Self {}
+        Self {
+            import_text: NodeRef::default(),
+        }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
@@ -43,7 +55,7 @@ impl Component for SettingsEditor {
                                             settings
                                         })
                                     }
-                                    placeholder="Path to the proto descriptor"
+                                    placeholder="Path to the proto descriptor, a directory or a glob"
                                     class="input"
                                     type="text"/>
                                 <img class="delete" src="img/trash-can.svg" onclick={{
@@ -68,6 +80,38 @@ impl Component for SettingsEditor {
                         }
                         icon="img/plus.svg"
                         text="Add proto"/>
+                    <div class="row">
+                        <input
+                            checked={ ctx.props().settings.later_descriptors_override }
+                            onclick={
+                                let settings = ctx.props().settings.clone();
+                                ctx.props().set_settings.clone().reform(move |ev: MouseEvent| {
+                                    let later_overrides = ev.target_unchecked_into::<HtmlInputElement>().checked();
+                                    let mut settings = settings.clone();
+                                    settings.later_descriptors_override = later_overrides;
+                                    settings
+                                })
+                            }
+                            class="input"
+                            type="checkbox"/>
+                        <span>{ "Later protos override files defined differently by earlier ones" }</span>
+                    </div>
+                    <div class="row">
+                        <input
+                            checked={ ctx.props().settings.sample_new_tabs }
+                            onclick={
+                                let settings = ctx.props().settings.clone();
+                                ctx.props().set_settings.clone().reform(move |ev: MouseEvent| {
+                                    let sample_new_tabs = ev.target_unchecked_into::<HtmlInputElement>().checked();
+                                    let mut settings = settings.clone();
+                                    settings.sample_new_tabs = sample_new_tabs;
+                                    settings
+                                })
+                            }
+                            class="input"
+                            type="checkbox"/>
+                        <span>{ "Fill new tabs with made up requests" }</span>
+                    </div>
 
                     <span class="subtitle">{ "Profiles" }</span>
                     {{
@@ -78,6 +122,7 @@ impl Component for SettingsEditor {
                         profiles.into_iter().enumerate().map(|(index, (id, profile))| {
                             html!{<>
                                 <div class="row">
+                                    <HealthLed health={ ctx.props().health.get(&id).cloned() }/>
                                     <input 
                                         value={ profile.name.clone() }
                                         oninput={
@@ -109,6 +154,26 @@ impl Component for SettingsEditor {
                                         placeholder="Authority"
                                         class="input"
                                         type="text"/>
+                                    <select
+                                        class="select"
+                                        title="Load balancing policy"
+                                        onchange={
+                                            let id = id.clone();
+                                            let settings = ctx.props().settings.clone();
+                                            ctx.props().set_settings.clone().reform(move |ev: Event| {
+                                                let lb_policy = ev.target_unchecked_into::<HtmlInputElement>().value();
+                                                let mut settings = settings.clone();
+                                                settings.profiles.get_mut(&id).unwrap().endpoint.lb_policy = if lb_policy == "round_robin" {
+                                                    LbPolicy::RoundRobin
+                                                } else {
+                                                    LbPolicy::PickFirst
+                                                };
+                                                settings
+                                            })
+                                        }>
+                                        <option value="pick_first" selected={ profile.endpoint.lb_policy == LbPolicy::PickFirst }>{ "Pick first" }</option>
+                                        <option value="round_robin" selected={ profile.endpoint.lb_policy == LbPolicy::RoundRobin }>{ "Round robin" }</option>
+                                    </select>
                                     <img class="delete" src="img/trash-can.svg" onclick={{
                                         let id = id.clone();
                                         let settings = ctx.props().settings.clone();
@@ -180,6 +245,298 @@ impl Component for SettingsEditor {
                                         <div class="ghost"></div>
                                     }
                                 </div>
+                                <div class="row">
+                                    <input
+                                        checked={ profile.endpoint.proxy.is_some() }
+                                        onclick={
+                                            let id = id.clone();
+                                            let settings = ctx.props().settings.clone();
+                                            ctx.props().set_settings.clone().reform(move |ev: MouseEvent| {
+                                                let use_proxy = ev.target_unchecked_into::<HtmlInputElement>().checked();
+                                                let mut settings = settings.clone();
+                                                settings.profiles.get_mut(&id).unwrap().endpoint.proxy = if use_proxy {
+                                                    Some(Default::default())
+                                                } else {
+                                                    None
+                                                };
+                                                settings
+                                            })
+                                        }
+                                        class="input"
+                                        type="checkbox"/>
+                                    <span>{ "Use proxy" }</span>
+
+                                    if let Some(proxy) = &profile.endpoint.proxy {
+                                        <select
+                                            class="select"
+                                            onchange={
+                                                let id = id.clone();
+                                                let settings = ctx.props().settings.clone();
+                                                ctx.props().set_settings.clone().reform(move |ev: Event| {
+                                                    let kind = ev.target_unchecked_into::<HtmlInputElement>().value();
+                                                    let mut settings = settings.clone();
+                                                    settings.profiles.get_mut(&id).unwrap().endpoint.proxy.as_mut().unwrap().kind = if kind == "socks5" {
+                                                        ProxyKind::Socks5
+                                                    } else {
+                                                        ProxyKind::HttpConnect
+                                                    };
+                                                    settings
+                                                })
+                                            }>
+                                            <option value="http" selected={ proxy.kind == ProxyKind::HttpConnect }>{ "HTTP" }</option>
+                                            <option value="socks5" selected={ proxy.kind == ProxyKind::Socks5 }>{ "SOCKS5" }</option>
+                                        </select>
+
+                                        <input
+                                            value={ proxy.authority.clone() }
+                                            oninput={
+                                                let id = id.clone();
+                                                let settings = ctx.props().settings.clone();
+                                                ctx.props().set_settings.clone().reform(move |ev: InputEvent| {
+                                                    let authority = ev.target_unchecked_into::<HtmlInputElement>().value();
+                                                    let mut settings = settings.clone();
+                                                    settings.profiles.get_mut(&id).unwrap().endpoint.proxy.as_mut().unwrap().authority = authority;
+                                                    settings
+                                                })
+                                            }
+                                            style="flex: 2"
+                                            placeholder="Proxy authority"
+                                            class="input"
+                                            type="text"/>
+
+                                        <input
+                                            value={ proxy.auth.as_ref().map(|auth| auth.username.clone()).unwrap_or_else(|| String::new()) }
+                                            oninput={
+                                                let id = id.clone();
+                                                let settings = ctx.props().settings.clone();
+                                                ctx.props().set_settings.clone().reform(move |ev: InputEvent| {
+                                                    let username = ev.target_unchecked_into::<HtmlInputElement>().value();
+                                                    let mut settings = settings.clone();
+                                                    let proxy = settings.profiles.get_mut(&id).unwrap().endpoint.proxy.as_mut().unwrap();
+                                                    let password = proxy.auth.take().map(|auth| auth.password).unwrap_or_else(|| String::new());
+                                                    proxy.auth = if !username.is_empty() || !password.is_empty() {
+                                                        Some(ProxyAuth { username, password })
+                                                    } else {
+                                                        None
+                                                    };
+                                                    settings
+                                                })
+                                            }
+                                            placeholder="Username"
+                                            class="input"
+                                            type="text"/>
+
+                                        <input
+                                            value={ proxy.auth.as_ref().map(|auth| auth.password.clone()).unwrap_or_else(|| String::new()) }
+                                            oninput={
+                                                let id = id.clone();
+                                                let settings = ctx.props().settings.clone();
+                                                ctx.props().set_settings.clone().reform(move |ev: InputEvent| {
+                                                    let password = ev.target_unchecked_into::<HtmlInputElement>().value();
+                                                    let mut settings = settings.clone();
+                                                    let proxy = settings.profiles.get_mut(&id).unwrap().endpoint.proxy.as_mut().unwrap();
+                                                    let username = proxy.auth.take().map(|auth| auth.username).unwrap_or_else(|| String::new());
+                                                    proxy.auth = if !username.is_empty() || !password.is_empty() {
+                                                        Some(ProxyAuth { username, password })
+                                                    } else {
+                                                        None
+                                                    };
+                                                    settings
+                                                })
+                                            }
+                                            placeholder="Password"
+                                            class="input"
+                                            type="password"/>
+                                    } else {
+                                        <div class="ghost"></div>
+                                    }
+                                </div>
+                                <div class="row">
+                                    <input
+                                        checked={ profile.call_options.retry_policy.is_some() }
+                                        onclick={
+                                            let id = id.clone();
+                                            let settings = ctx.props().settings.clone();
+                                            ctx.props().set_settings.clone().reform(move |ev: MouseEvent| {
+                                                let use_retries = ev.target_unchecked_into::<HtmlInputElement>().checked();
+                                                let mut settings = settings.clone();
+                                                settings.profiles.get_mut(&id).unwrap().call_options.retry_policy = if use_retries {
+                                                    Some(Default::default())
+                                                } else {
+                                                    None
+                                                };
+                                                settings
+                                            })
+                                        }
+                                        class="input"
+                                        type="checkbox"/>
+                                    <span>{ "Retry" }</span>
+
+                                    if let Some(retry_policy) = &profile.call_options.retry_policy {
+                                        <input
+                                            value={ retry_policy.max_attempts.to_string() }
+                                            oninput={
+                                                let id = id.clone();
+                                                let settings = ctx.props().settings.clone();
+                                                ctx.props().set_settings.clone().reform(move |ev: InputEvent| {
+                                                    let raw = ev.target_unchecked_into::<HtmlInputElement>().value();
+                                                    let mut settings = settings.clone();
+                                                    if let Ok(max_attempts) = raw.parse() {
+                                                        settings.profiles.get_mut(&id).unwrap().call_options.retry_policy.as_mut().unwrap().max_attempts = max_attempts;
+                                                    }
+                                                    settings
+                                                })
+                                            }
+                                            title="Max attempts"
+                                            placeholder="Attempts"
+                                            class="input"
+                                            type="number"/>
+                                        <input
+                                            value={ retry_policy.initial_backoff_ms.to_string() }
+                                            oninput={
+                                                let id = id.clone();
+                                                let settings = ctx.props().settings.clone();
+                                                ctx.props().set_settings.clone().reform(move |ev: InputEvent| {
+                                                    let raw = ev.target_unchecked_into::<HtmlInputElement>().value();
+                                                    let mut settings = settings.clone();
+                                                    if let Ok(initial_backoff_ms) = raw.parse() {
+                                                        settings.profiles.get_mut(&id).unwrap().call_options.retry_policy.as_mut().unwrap().initial_backoff_ms = initial_backoff_ms;
+                                                    }
+                                                    settings
+                                                })
+                                            }
+                                            title="Initial backoff (ms)"
+                                            placeholder="Initial backoff"
+                                            class="input"
+                                            type="number"/>
+                                        <input
+                                            value={ retry_policy.max_backoff_ms.to_string() }
+                                            oninput={
+                                                let id = id.clone();
+                                                let settings = ctx.props().settings.clone();
+                                                ctx.props().set_settings.clone().reform(move |ev: InputEvent| {
+                                                    let raw = ev.target_unchecked_into::<HtmlInputElement>().value();
+                                                    let mut settings = settings.clone();
+                                                    if let Ok(max_backoff_ms) = raw.parse() {
+                                                        settings.profiles.get_mut(&id).unwrap().call_options.retry_policy.as_mut().unwrap().max_backoff_ms = max_backoff_ms;
+                                                    }
+                                                    settings
+                                                })
+                                            }
+                                            title="Max backoff (ms)"
+                                            placeholder="Max backoff"
+                                            class="input"
+                                            type="number"/>
+                                        <input
+                                            value={ retry_policy.backoff_multiplier.to_string() }
+                                            oninput={
+                                                let id = id.clone();
+                                                let settings = ctx.props().settings.clone();
+                                                ctx.props().set_settings.clone().reform(move |ev: InputEvent| {
+                                                    let raw = ev.target_unchecked_into::<HtmlInputElement>().value();
+                                                    let mut settings = settings.clone();
+                                                    if let Ok(backoff_multiplier) = raw.parse() {
+                                                        settings.profiles.get_mut(&id).unwrap().call_options.retry_policy.as_mut().unwrap().backoff_multiplier = backoff_multiplier;
+                                                    }
+                                                    settings
+                                                })
+                                            }
+                                            title="Backoff multiplier"
+                                            placeholder="Multiplier"
+                                            class="input"
+                                            type="number"/>
+                                        <input
+                                            value={ retry_policy.retryable_status_codes.join(", ") }
+                                            oninput={
+                                                let id = id.clone();
+                                                let settings = ctx.props().settings.clone();
+                                                ctx.props().set_settings.clone().reform(move |ev: InputEvent| {
+                                                    let raw = ev.target_unchecked_into::<HtmlInputElement>().value();
+                                                    let mut settings = settings.clone();
+                                                    settings.profiles.get_mut(&id).unwrap().call_options.retry_policy.as_mut().unwrap().retryable_status_codes = raw
+                                                        .split(',')
+                                                        .map(|code| code.trim().to_uppercase())
+                                                        .filter(|code| !code.is_empty())
+                                                        .collect();
+                                                    settings
+                                                })
+                                            }
+                                            style="flex: 3"
+                                            title="Retryable status codes"
+                                            placeholder="UNAVAILABLE, ..."
+                                            class="input"
+                                            type="text"/>
+                                    } else {
+                                        <div class="ghost"></div>
+                                    }
+                                </div>
+                                <div class="row">
+                                    <input
+                                        checked={ profile.endpoint.connection.is_some() }
+                                        onclick={
+                                            let id = id.clone();
+                                            let settings = ctx.props().settings.clone();
+                                            ctx.props().set_settings.clone().reform(move |ev: MouseEvent| {
+                                                let tune = ev.target_unchecked_into::<HtmlInputElement>().checked();
+                                                let mut settings = settings.clone();
+                                                settings.profiles.get_mut(&id).unwrap().endpoint.connection = if tune {
+                                                    Some(Default::default())
+                                                } else {
+                                                    None
+                                                };
+                                                settings
+                                            })
+                                        }
+                                        class="input"
+                                        type="checkbox"/>
+                                    <span>{ "Tune connection" }</span>
+
+                                    if let Some(connection) = &profile.endpoint.connection {
+                                        { connection_input(ctx, id, connection.keepalive_interval_ms, |c, v| c.keepalive_interval_ms = v, None, "Keepalive interval (ms)", "Keepalive") }
+                                        { connection_input(ctx, id, connection.keepalive_timeout_ms, |c, v| c.keepalive_timeout_ms = v, None, "Keepalive timeout (ms)", "Timeout") }
+                                        { connection_input(ctx, id, connection.initial_stream_window_size, |c, v| c.initial_stream_window_size = v, None, "Initial stream window (bytes)", "Stream window") }
+                                        { connection_input(ctx, id, connection.initial_connection_window_size, |c, v| c.initial_connection_window_size = v, None, "Initial connection window (bytes)", "Conn. window") }
+                                        { connection_input(ctx, id, connection.max_frame_size, |c, v| c.max_frame_size = v, Some(ConnectionOptions::MAX_FRAME_SIZE_RANGE), "Max frame size (bytes)", "Frame size") }
+                                        { connection_input(ctx, id, connection.connect_timeout_ms, |c, v| c.connect_timeout_ms = v, None, "Connect timeout (ms)", "Connect timeout") }
+                                    } else {
+                                        <div class="ghost"></div>
+                                    }
+                                </div>
+                                if let Some(connection) = &profile.endpoint.connection {
+                                    <div class="row">
+                                        <input
+                                            checked={ connection.keepalive_while_idle }
+                                            onclick={
+                                                let id = id.clone();
+                                                let settings = ctx.props().settings.clone();
+                                                ctx.props().set_settings.clone().reform(move |ev: MouseEvent| {
+                                                    let keepalive_while_idle = ev.target_unchecked_into::<HtmlInputElement>().checked();
+                                                    let mut settings = settings.clone();
+                                                    settings.profiles.get_mut(&id).unwrap().endpoint.connection.as_mut().unwrap().keepalive_while_idle = keepalive_while_idle;
+                                                    settings
+                                                })
+                                            }
+                                            class="input"
+                                            type="checkbox"/>
+                                        <span>{ "Keepalive while idle" }</span>
+                                        <input
+                                            checked={ connection.tcp_nodelay }
+                                            onclick={
+                                                let id = id.clone();
+                                                let settings = ctx.props().settings.clone();
+                                                ctx.props().set_settings.clone().reform(move |ev: MouseEvent| {
+                                                    let tcp_nodelay = ev.target_unchecked_into::<HtmlInputElement>().checked();
+                                                    let mut settings = settings.clone();
+                                                    settings.profiles.get_mut(&id).unwrap().endpoint.connection.as_mut().unwrap().tcp_nodelay = tcp_nodelay;
+                                                    settings
+                                                })
+                                            }
+                                            class="input"
+                                            type="checkbox"/>
+                                        <span>{ "TCP nodelay" }</span>
+                                        <div class="ghost"></div>
+                                    </div>
+                                }
                                 if index < ctx.props().settings.profiles.len() - 1 {
                                     <div class="profile-spacer"></div>
                                 }
@@ -199,8 +556,163 @@ impl Component for SettingsEditor {
                         }
                         icon="img/plus.svg"
                         text="Add profile"/>
+
+                    <span class="subtitle">{ "Diff" }</span>
+                    {
+                        ctx.props().settings.diff_ignore_paths.iter().enumerate().map(|(idx, path)| html!{
+                            <div class="row">
+                                <input 
+                                    value={ path.clone() }
+                                    oninput={
+                                        let settings = ctx.props().settings.clone();
+                                        ctx.props().set_settings.clone().reform(move |ev: InputEvent| {
+                                            let path = ev.target_unchecked_into::<HtmlInputElement>().value();
+                                            let mut settings = settings.clone();
+                                            settings.diff_ignore_paths[idx] = path;
+                                            settings
+                                        })
+                                    }
+                                    placeholder="Path to leave out of diffs, like items[*].updateTime"
+                                    class="input"
+                                    type="text"/>
+                                <img class="delete" src="img/trash-can.svg" onclick={{
+                                    let settings = ctx.props().settings.clone();
+                                    ctx.props().set_settings.clone().reform(move |_| {
+                                        let mut settings = settings.clone();
+                                        settings.diff_ignore_paths.remove(idx);
+                                        settings
+                                    })
+                                }}/>
+                            </div>
+                        }).collect::<Html>()
+                    }
+                    <Button
+                        onclick={ 
+                            let settings = ctx.props().settings.clone();
+                            ctx.props().set_settings.clone().reform(move |_| {
+                                let mut settings = settings.clone();
+                                settings.diff_ignore_paths.push(String::new());
+                                settings
+                            })
+                        }
+                        icon="img/plus.svg"
+                        text="Add ignored path"/>
+
+                    <span class="subtitle">{ "Saved requests" }</span>
+                    {
+                        ctx.props().settings.saved_requests.iter().enumerate().map(|(idx, request)| html!{
+                            <div class="row">
+                                <input 
+                                    value={ request.name.clone() }
+                                    oninput={
+                                        let settings = ctx.props().settings.clone();
+                                        ctx.props().set_settings.clone().reform(move |ev: InputEvent| {
+                                            let name = ev.target_unchecked_into::<HtmlInputElement>().value();
+                                            let mut settings = settings.clone();
+                                            settings.saved_requests[idx].name = name;
+                                            settings
+                                        })
+                                    }
+                                    placeholder="Request name"
+                                    class="input"
+                                    type="text"/>
+                                <span class="method">{ request.method_full_name.clone() }</span>
+                                <img class="delete" src="img/trash-can.svg" onclick={{
+                                    let settings = ctx.props().settings.clone();
+                                    ctx.props().set_settings.clone().reform(move |_| {
+                                        let mut settings = settings.clone();
+                                        settings.saved_requests.remove(idx);
+                                        settings
+                                    })
+                                }}/>
+                            </div>
+                        }).collect::<Html>()
+                    }
+                    <div class="row">
+                        <textarea
+                            ref={ self.import_text.clone() }
+                            placeholder="grpcurl command lines or a Postman collection"
+                            spellcheck="false"
+                            class="input import"/>
+                    </div>
+                    <Button
+                        onclick={
+                            let import_text = self.import_text.clone();
+                            ctx.props().on_import.clone().reform(move |_| {
+                                import_text.cast::<HtmlTextAreaElement>().unwrap().value()
+                            })
+                        }
+                        icon="img/plus.svg"
+                        text="Import"/>
                 </div>
             </div>
         }
     }
 }
+
+/// Number input for one of the optional fields of a profile's `ConnectionOptions`. Clearing the
+/// input resets the field to `None`.
+fn connection_input<T>(
+    ctx: &Context<SettingsEditor>,
+    id: Uuid,
+    value: Option<T>,
+    set: fn(&mut ConnectionOptions, Option<T>),
+    // Values outside of it are clamped once the input loses focus
+    range: Option<RangeInclusive<T>>,
+    title: &'static str,
+    placeholder: &'static str,
+) -> Html
+where
+    T: ToString + FromStr + PartialOrd + Copy + 'static,
+{
+    let onchange = {
+        let settings = ctx.props().settings.clone();
+        let range = range.clone();
+        ctx.props().set_settings.clone().reform(move |ev: Event| {
+            let raw = ev.target_unchecked_into::<HtmlInputElement>().value();
+            let mut settings = settings.clone();
+            let connection = settings.profiles.get_mut(&id).unwrap().endpoint.connection.as_mut().unwrap();
+            if let (Ok(value), Some(range)) = (raw.parse::<T>(), &range) {
+                let clamped = if value < *range.start() {
+                    *range.start()
+                } else if value > *range.end() {
+                    *range.end()
+                } else {
+                    value
+                };
+                set(connection, Some(clamped));
+            }
+            settings
+        })
+    };
+    let (min, max) = match &range {
+        Some(range) => (Some(range.start().to_string()), Some(range.end().to_string())),
+        None => (None, None),
+    };
+
+    let settings = ctx.props().settings.clone();
+    let oninput = ctx.props().set_settings.clone().reform(move |ev: InputEvent| {
+        let raw = ev.target_unchecked_into::<HtmlInputElement>().value();
+        let mut settings = settings.clone();
+        let connection = settings.profiles.get_mut(&id).unwrap().endpoint.connection.as_mut().unwrap();
+        if raw.is_empty() {
+            set(connection, None);
+        } else if let Ok(value) = raw.parse() {
+            set(connection, Some(value));
+        }
+        settings
+    });
+
+    html! {
+        <input
+            value={ value.map(|value| value.to_string()).unwrap_or_default() }
+            { oninput }
+            { onchange }
+            { min }
+            { max }
+            { title }
+            { placeholder }
+            class="input"
+            type="number"/>
+    }
+}
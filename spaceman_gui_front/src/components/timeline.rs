@@ -0,0 +1,141 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Something that happened during a call, in the order it happened.
+#[derive(Clone, PartialEq)]
+pub struct TimelineEvent {
+    // Milliseconds since the Unix epoch
+    pub at: f64,
+    pub entry: TimelineEntry,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum TimelineEntry {
+    Sent(String),
+    // The client closed its side of the stream
+    Committed,
+    Received {
+        msg: String,
+        seq: u32,
+        size: usize,
+        // How many fields of the message the descriptors don't define
+        unknown_fields: usize,
+        // Index of the message in the tab's outputs
+        output: usize,
+    },
+    Headers(Vec<(String, String)>),
+    Trailers(Vec<(String, String)>),
+    // The server closed the call
+    Closed,
+    Error(String),
+}
+
+impl TimelineEntry {
+    fn is_metadata(&self) -> bool {
+        matches!(self, TimelineEntry::Headers(_) | TimelineEntry::Trailers(_))
+    }
+
+    fn text(&self) -> String {
+        match self {
+            TimelineEntry::Sent(msg) | TimelineEntry::Received { msg, .. } => compact(msg),
+            TimelineEntry::Headers(pairs) | TimelineEntry::Trailers(pairs) => pairs
+                .iter()
+                .map(|(key, value)| format!("{key}: {value}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            TimelineEntry::Error(err) => err.clone(),
+            TimelineEntry::Committed | TimelineEntry::Closed => String::new(),
+        }
+    }
+}
+
+// Reformats pretty printed JSON on a single line for previews
+fn compact(msg: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(msg)
+        .map(|value| value.to_string())
+        .unwrap_or_else(|_| msg.to_string())
+}
+
+#[derive(Properties, PartialEq)]
+pub struct TimelineProps {
+    pub events: Vec<TimelineEvent>,
+    // Timestamps are shown relative to this
+    pub started_at: f64,
+    pub on_select_output: Callback<usize>,
+    pub on_close: Callback<()>,
+}
+
+#[function_component]
+pub fn Timeline(props: &TimelineProps) -> Html {
+    let query = use_state_eq(|| String::new());
+    let show_sent = use_state_eq(|| true);
+    let show_received = use_state_eq(|| true);
+    let show_metadata = use_state_eq(|| true);
+
+    let toggle = |state: &UseStateHandle<bool>| {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| state.set(!*state))
+    };
+
+    let oninput = {
+        let query = query.clone();
+        Callback::from(move |ev: InputEvent| {
+            query.set(ev.target_unchecked_into::<HtmlInputElement>().value());
+        })
+    };
+
+    let lowercase_query = query.to_lowercase();
+    let events = props.events.iter().filter(|event| {
+        let shown = match &event.entry {
+            TimelineEntry::Sent(_) | TimelineEntry::Committed => *show_sent,
+            TimelineEntry::Received { .. } => *show_received,
+            entry if entry.is_metadata() => *show_metadata,
+            _ => true,
+        };
+        shown && (lowercase_query.is_empty() || event.entry.text().to_lowercase().contains(&lowercase_query))
+    }).map(|event| {
+        let elapsed = format!("+{:.0} ms", event.at - props.started_at);
+        let (class, direction, label) = match &event.entry {
+            TimelineEntry::Sent(_) => ("sent", "↑", "Sent".to_string()),
+            TimelineEntry::Committed => ("sent", "↑", "End of stream".to_string()),
+            TimelineEntry::Received { seq, size, unknown_fields: 0, .. } => ("received", "↓", format!("#{} · {} B", seq + 1, size)),
+            TimelineEntry::Received { seq, size, unknown_fields, .. } => ("received", "↓", format!("#{} · {} B · {} unknown", seq + 1, size, unknown_fields)),
+            TimelineEntry::Headers(_) => ("metadata", "↓", "Headers".to_string()),
+            TimelineEntry::Trailers(_) => ("metadata", "↓", "Trailers".to_string()),
+            TimelineEntry::Closed => ("received", "↓", "End of stream".to_string()),
+            TimelineEntry::Error(_) => ("error", "✕", "Error".to_string()),
+        };
+        let onclick = match &event.entry {
+            TimelineEntry::Received { output, .. } => {
+                let output = *output;
+                Some(props.on_select_output.reform(move |_: MouseEvent| output))
+            },
+            _ => None,
+        };
+        let clickable = onclick.is_some().then_some("clickable");
+        let text = event.entry.text();
+        html! {
+            <div class={ classes!("event", class, clickable) } { onclick }>
+                <span class="elapsed">{ elapsed }</span>
+                <span class="direction">{ direction }</span>
+                <span class="label">{ label }</span>
+                <span class="text" title={ text.clone() }>{ text }</span>
+            </div>
+        }
+    }).collect::<Html>();
+
+    html! {
+        <div class="timeline">
+            <div class="header">
+                <input class="filter" type="text" placeholder="Filter" value={ (*query).clone() } { oninput }/>
+                <label><input type="checkbox" checked={ *show_sent } onclick={ toggle(&show_sent) }/>{ "Sent" }</label>
+                <label><input type="checkbox" checked={ *show_received } onclick={ toggle(&show_received) }/>{ "Received" }</label>
+                <label><input type="checkbox" checked={ *show_metadata } onclick={ toggle(&show_metadata) }/>{ "Metadata" }</label>
+                <img class="close" src="img/close.svg" onclick={ props.on_close.reform(|_| ()) }/>
+            </div>
+            <div class="events">
+                { events }
+            </div>
+        </div>
+    }
+}
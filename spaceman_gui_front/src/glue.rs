@@ -6,16 +6,22 @@ extern "C" {
     #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "tauri"], catch)]
     pub async fn invoke(cmd: &str, args: JsValue) -> Result<JsValue, JsString>;
 
-    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "event"])]
+    // Events are scoped to the current window so that calls started from
+    // different windows don't see each other's events
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "window", "appWindow"])]
     pub fn emit(chan: &str, payload: JsValue);
 
     // The returned valhe is the function that unregisters the handler
-    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "event"])]
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "window", "appWindow"])]
     pub async fn listen(chan: &str, callback: &Closure<dyn FnMut(JsValue)>) -> JsValue;
 
     #[wasm_bindgen]
     pub async fn initMonaco(element: JsValue, editorName: &str, readOnly: bool);
 
+    // Resolves once initMonaco has created the editor
+    #[wasm_bindgen]
+    pub async fn monacoWhenReady(editorName: &str);
+
     #[wasm_bindgen]
     pub fn monacoAddTab(editorName: &str) -> i32;
 
@@ -36,4 +42,8 @@ extern "C" {
 
     #[wasm_bindgen]
     pub fn monacoLayout(editorName: &str);
+
+    // Validates the model against the JSON Schema and uses it for completion and hovers
+    #[wasm_bindgen]
+    pub fn monacoSetSchema(editorName: &str, idx: i32, schema: &str);
 }
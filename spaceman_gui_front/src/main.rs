@@ -1,11 +1,19 @@
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 use std::thread::spawn;
 
 use spaceman_types::endpoint::Endpoint;
-use spaceman_types::repo::{RepoView, MethodView, ServiceView};
+use spaceman_types::repo::{RepoView, MethodView, ReloadSummary, ServiceView};
 use spaceman_types::callopout::CallOpOut;
-use spaceman_types::settings::{Settings, Profile};
+use spaceman_types::settings::{Settings, Profile, SavedRequest};
+use spaceman_types::import::Import;
+use spaceman_types::schema::Schema;
+use spaceman_types::docs::Docs;
+use spaceman_types::diff::Difference;
+use spaceman_types::export::ExportFormat;
+use spaceman_types::wire::{RawDump, UnknownField};
+use spaceman_types::workspace::{Workspace, WorkspaceTab};
 
 use futures::{SinkExt, StreamExt};
 use serde_json::to_string;
@@ -16,7 +24,7 @@ use js_sys::{JsString, Reflect};
 use futures::channel::mpsc;
 use yew::platform::spawn_local;
 use yew::prelude::*;
-use gloo_timers::callback::Timeout;
+use gloo_timers::callback::{Interval, Timeout};
 use slab::Slab;
 use uuid::Uuid;
 
@@ -30,6 +38,13 @@ use components::button::{Button, ButtonKind};
 use components::repo::Repo;
 use components::metadata_editor::MetadataEditor;
 use components::errors::Errors;
+use components::docs::DocsPanel;
+use components::diff_view::DiffView;
+use components::export_view::ExportView;
+use components::raw_view::{RawFormat, RawView};
+use components::timeline::{Timeline, TimelineEntry, TimelineEvent};
+use components::form_editor::FormEditor;
+use components::health::{Health, HealthLed};
 
 use commands::*;
 use call::*;
@@ -39,6 +54,10 @@ use crate::components::settings::SettingsEditor;
 #[derive(PartialEq, Properties)]
 struct SidebarProps {
     repo_view: Option<RepoView>,
+    // Docs being shown, last is the current page
+    docs_history: Vec<Docs>,
+    saved_requests: Vec<SavedRequest>,
+    reload_summary: Option<ReloadSummary>,
     send_msg: Callback<UiMsg>,
 }
 
@@ -50,9 +69,45 @@ fn Sidebar(props: &SidebarProps) -> Html {
                 onclick={ props.send_msg.clone().reform(|_| UiMsg::GoToSettings) }
                 text="Settings"
                 icon="img/cog.svg"/>
+            if let Some(summary) = props.reload_summary.as_ref() {
+                <div
+                    class="reload-summary"
+                    title="Dismiss"
+                    onclick={ props.send_msg.clone().reform(|_| UiMsg::DismissReloadSummary) }>
+                    <span class="title">{ "Protos reloaded" }</span>
+                    { for summary.added.iter().map(|full_name| html! { <div class="added">{ format!("+ {full_name}") }</div> }) }
+                    { for summary.removed.iter().map(|full_name| html! { <div class="removed">{ format!("- {full_name}") }</div> }) }
+                    { for summary.changed.iter().map(|full_name| html! { <div class="changed">{ format!("~ {full_name}") }</div> }) }
+                </div>
+            }
             <Repo
                 repo_view={ props.repo_view.clone() }
-                on_new_tab={ props.send_msg.clone().reform(|(service_idx, method_idx)| UiMsg::RequestNewTab { service_idx, method_idx }) }/>
+                on_new_tab={ props.send_msg.clone().reform(|(service_idx, method_idx)| UiMsg::RequestNewTab { service_idx, method_idx }) }
+                on_show_docs={ props.send_msg.clone().reform(UiMsg::ShowMethodDocs) }
+                on_show_type_docs={ props.send_msg.clone().reform(UiMsg::ShowTypeDocs) }/>
+            if !props.saved_requests.is_empty() {
+                <div class="saved-requests">
+                    <span class="title">{ "Saved requests" }</span>
+                    {
+                        for props.saved_requests.iter().enumerate().map(|(idx, request)| html! {
+                            <div
+                                class="saved-request"
+                                title={ request.method_full_name.clone() }
+                                onclick={ props.send_msg.clone().reform(move |_| UiMsg::OpenSavedRequest(idx)) }>
+                                { request.name.clone() }
+                            </div>
+                        })
+                    }
+                </div>
+            }
+            if let Some(docs) = props.docs_history.last() {
+                <DocsPanel
+                    docs={ docs.clone() }
+                    can_go_back={ props.docs_history.len() > 1 }
+                    on_navigate={ props.send_msg.clone().reform(UiMsg::ShowTypeDocs) }
+                    on_back={ props.send_msg.clone().reform(|_| UiMsg::DocsBack) }
+                    on_close={ props.send_msg.clone().reform(|_| UiMsg::CloseDocs) }/>
+            }
         </div>
     }
 }
@@ -63,6 +118,9 @@ struct MainProps {
     active_tab: Option<usize>,
     send_msg: Callback<UiMsg>,
     profiles: Vec<(Uuid, String)>,
+    health: HashMap<Uuid, Health>,
+    // Label of the output pinned for diffing, if any
+    pinned_label: Option<String>,
 }
 
 enum MainMsg {
@@ -119,7 +177,7 @@ impl Component for Main {
             <div class="main">
                 <div class="tabs">
                     {for ctx.props().tabs.iter().enumerate().map(|(idx, tab)| html! {
-                        <div class={ classes!("tab", ctx.props().active_tab.filter(|active_tab| *active_tab == idx).and(Some("active"))) }>
+                        <div class={ classes!("tab", ctx.props().active_tab.filter(|active_tab| *active_tab == idx).and(Some("active")), tab.vanished.then_some("vanished")) }>
                             <div class="name" onclick={ ctx.link().callback(move |_| MainMsg::SelectTab(idx)) }>{ tab.method.name.clone() }</div>
                             <div class="close" onclick={ ctx.link().callback(move |_| MainMsg::DestroyTab(idx)) }>
                                 <img src="img/close.svg"/>
@@ -131,6 +189,9 @@ impl Component for Main {
                     if let Some(active_tab) = ctx.props().active_tab.clone() {
                         <div class="header">
                             // BEGIN PROFILES
+                            if let Some(profile_id) = ctx.props().tabs[active_tab].profile_id {
+                                <HealthLed health={ ctx.props().health.get(&profile_id).cloned() }/>
+                            }
                             <select
                                 class="select"
                                 key={active_tab}
@@ -232,7 +293,41 @@ impl Component for Main {
                                     }
                                 }
                             }}
+                            <Button
+                                text="Export"
+                                has_led={true}
+                                is_led_lit={ctx.props().tabs[active_tab].export.is_some()}
+                                class={classes!("export-button")}
+                                onclick={ctx.props().send_msg.clone().reform(move |_| {
+                                    UiMsg::ToggleExport(active_tab)
+                                })}/>
+                            <Button
+                                text="Detach"
+                                onclick={ctx.props().send_msg.clone().reform(move |_| {
+                                    UiMsg::DetachTab { tab_index: active_tab, side_by_side: false }
+                                })}/>
+                            <Button
+                                text="Compare"
+                                onclick={ctx.props().send_msg.clone().reform(move |_| {
+                                    UiMsg::DetachTab { tab_index: active_tab, side_by_side: true }
+                                })}/>
                             // END COMMANDS
+
+                            // BEGIN STATUS
+                            <div class="status">
+                                if ctx.props().tabs[active_tab].vanished {
+                                    <span class="vanished">{ "Method not in the loaded protos" }</span>
+                                }
+                                if let Some(attempts) = ctx.props().tabs[active_tab].attempts {
+                                    <span class={ classes!("attempts", (attempts > 1).then_some("retried")) }>
+                                        { if attempts == 1 { "1 attempt".to_string() } else { format!("{attempts} attempts") } }
+                                    </span>
+                                }
+                                if let Some(peer) = ctx.props().tabs[active_tab].peer.clone() {
+                                    <span class="peer">{ peer }</span>
+                                }
+                            </div>
+                            // END STATUS
                         </div>
                     }
                     <Pane initial_left={ 0.5 }>
@@ -242,7 +337,7 @@ impl Component for Main {
                                     classes!(
                                         "editor-wrapper",
                                         ctx.props().active_tab
-                                            .and_then(|active_tab| if ctx.props().tabs[active_tab].editing_metadata {
+                                            .and_then(|active_tab| if ctx.props().tabs[active_tab].editing_metadata || ctx.props().tabs[active_tab].editing_form {
                                                 Some("hidden")
                                             } else {
                                                 None
@@ -252,6 +347,19 @@ impl Component for Main {
                                 <div ref={ self.input_textarea.clone() } class="editor"></div>
                             </div>
                             if let Some(active_tab) = ctx.props().active_tab.clone() {
+                                if ctx.props().tabs[active_tab].editing_form && !ctx.props().tabs[active_tab].editing_metadata {
+                                    if let Some(schema) = ctx.props().tabs[active_tab].schema.clone() {
+                                        <FormEditor
+                                            { schema }
+                                            value={ ctx.props().tabs[active_tab].form_value.clone() }
+                                            on_change={ctx.props().send_msg.clone().reform(move |value| {
+                                                UiMsg::UpdateForm((active_tab, value))
+                                            })}
+                                            on_error={ctx.props().send_msg.clone().reform(UiMsg::ReportError)}/>
+                                    } else {
+                                        <div class="form-editor loading">{ "Loading..." }</div>
+                                    }
+                                }
                                 if ctx.props().tabs[active_tab].editing_metadata {
                                     <MetadataEditor
                                         rows={ ctx.props().tabs[active_tab].metadata.clone() }
@@ -266,6 +374,14 @@ impl Component for Main {
                                         })}/>
                                 }
                                 <div class="bottom-line">
+                                    <Button
+                                        onclick={ctx.props().send_msg.clone().reform(move |_| {
+                                            UiMsg::ToggleEditingForm(active_tab)
+                                        })}
+                                        has_led={true}
+                                        is_led_lit={ctx.props().tabs[active_tab].editing_form}
+                                        class={classes!("form-button")}
+                                        text="Form"/>
                                     <Button
                                         onclick={ctx.props().send_msg.clone().reform(move |_| {
                                             UiMsg::ToggleEditingMetadata(active_tab)
@@ -279,39 +395,142 @@ impl Component for Main {
                             }
                         </div>
                         <div class="main-pane-col">
-                            <div class="editor-wrapper">
+                            <div 
+                                class={
+                                    classes!(
+                                        "editor-wrapper",
+                                        ctx.props().active_tab
+                                            .and_then(|active_tab| {
+                                                let tab = &ctx.props().tabs[active_tab];
+                                                (tab.diff.is_some() || tab.export.is_some() || tab.raw_view.is_some() || tab.showing_timeline).then_some("hidden")
+                                            })
+                                    )
+                                }>
                                 <div ref={ self.output_textarea.clone() } class="editor"></div>
                             </div>
                             if let Some(active_tab) = ctx.props().active_tab.clone() {
-                                if ctx.props().tabs[active_tab].method.is_server_streaming && ctx.props().tabs[active_tab].output_monaco_ids.len() > 0 {
+                                if let Some(unknown_fields) = unknown_fields_shown(&ctx.props().tabs[active_tab]) {
+                                    <div class="unknown-fields">
+                                        <span class="title">
+                                            { if unknown_fields.len() == 1 { "1 field not in the loaded protos".to_string() } else { format!("{} fields not in the loaded protos", unknown_fields.len()) } }
+                                        </span>
+                                        { for unknown_fields.iter().map(|field| html! { <span class="field">{ field.to_string() }</span> }) }
+                                    </div>
+                                }
+                                if ctx.props().tabs[active_tab].showing_timeline {
+                                    <Timeline
+                                        events={ ctx.props().tabs[active_tab].timeline.clone() }
+                                        started_at={ ctx.props().tabs[active_tab].call_started_at }
+                                        on_select_output={ctx.props().send_msg.clone().reform(move |output| {
+                                            UiMsg::SelectOutput((active_tab, output))
+                                        })}
+                                        on_close={ctx.props().send_msg.clone().reform(move |_| {
+                                            UiMsg::ToggleTimeline(active_tab)
+                                        })}/>
+                                } else if let Some(export) = ctx.props().tabs[active_tab].export.clone() {
+                                    <ExportView
+                                        format={ export.format }
+                                        snippet={ export.snippet }
+                                        on_format={ctx.props().send_msg.clone().reform(move |format| {
+                                            UiMsg::ExportCall { tab_index: active_tab, format }
+                                        })}
+                                        on_close={ctx.props().send_msg.clone().reform(move |_| {
+                                            UiMsg::ToggleExport(active_tab)
+                                        })}/>
+                                } else if let Some(diff) = ctx.props().tabs[active_tab].diff.clone() {
+                                    <DiffView
+                                        left_label={ diff.left_label }
+                                        right_label={ diff.right_label }
+                                        differences={ diff.differences }
+                                        on_close={ctx.props().send_msg.clone().reform(move |_| {
+                                            UiMsg::CloseDiff(active_tab)
+                                        })}/>
+                                } else if let (Some(format), Some(selected_output)) = (
+                                    ctx.props().tabs[active_tab].raw_view,
+                                    ctx.props().tabs[active_tab].selected_output,
+                                ) {
+                                    <RawView
+                                        // Empty until the backend has dumped the output
+                                        raw={
+                                            ctx.props().tabs[active_tab].raw_dump
+                                                .as_ref()
+                                                .filter(|(output, _)| *output == selected_output)
+                                                .map(|(_, raw)| raw.clone())
+                                                .unwrap_or_default()
+                                        }
+                                        { format }
+                                        on_format={ctx.props().send_msg.clone().reform(move |format| {
+                                            UiMsg::SetRawFormat { tab_index: active_tab, format }
+                                        })}
+                                        on_close={ctx.props().send_msg.clone().reform(move |_| {
+                                            UiMsg::ToggleRawView(active_tab)
+                                        })}/>
+                                }
+                                if !ctx.props().tabs[active_tab].timeline.is_empty() {
                                     <div class="bottom-line">
                                         <Button
                                             onclick={ctx.props().send_msg.clone().reform(move |_| {
-                                                UiMsg::ToggleFollowOutput(active_tab)
+                                                UiMsg::ToggleTimeline(active_tab)
                                             })}
                                             has_led={true}
-                                            is_led_lit={ctx.props().tabs[active_tab].follow_output}
-                                            class={classes!("follow")}
-                                            text="Follow"/>
-                                        <Button
-                                            onclick={ctx.props().send_msg.clone().reform(move |_| {
-                                                UiMsg::NavigateOutput((active_tab, -1))
-                                            })}
-                                            class={classes!("prev")}
-                                            text="Prev"
-                                            kind={ButtonKind::Cyan}/>
-                                        <div class="counter">
-                                            <span class="current">{ctx.props().tabs[active_tab].selected_output.map(|i| i + 1).unwrap_or(0)}</span>
-                                            <img class="line" src="img/line.svg"/>
-                                            <span class="of">{ctx.props().tabs[active_tab].output_monaco_ids.len()}</span>
-                                        </div>
-                                        <Button
-                                            onclick={ctx.props().send_msg.clone().reform(move |_| {
-                                                UiMsg::NavigateOutput((active_tab,1))
-                                            })}
-                                            class={classes!("next")}
-                                            text="Next"
-                                            kind={ButtonKind::Cyan}/>
+                                            is_led_lit={ctx.props().tabs[active_tab].showing_timeline}
+                                            class={classes!("timeline-button")}
+                                            text="Timeline"/>
+                                        if ctx.props().tabs[active_tab].output_monaco_ids.len() > 0 {
+                                            <Button
+                                                onclick={ctx.props().send_msg.clone().reform(move |_| {
+                                                    UiMsg::PinOutput(active_tab)
+                                                })}
+                                                class={classes!("pin")}
+                                                text="Pin"/>
+                                            <Button
+                                                onclick={ctx.props().send_msg.clone().reform(move |_| {
+                                                    UiMsg::ToggleRawView(active_tab)
+                                                })}
+                                                has_led={true}
+                                                is_led_lit={ctx.props().tabs[active_tab].raw_view.is_some()}
+                                                class={classes!("raw")}
+                                                text="Raw"/>
+                                        }
+                                        if ctx.props().pinned_label.is_some() && ctx.props().tabs[active_tab].output_monaco_ids.len() > 0 {
+                                            <Button
+                                                onclick={ctx.props().send_msg.clone().reform(move |_| {
+                                                    UiMsg::DiffWithPinned(active_tab)
+                                                })}
+                                                has_led={true}
+                                                is_led_lit={ctx.props().tabs[active_tab].diff.is_some()}
+                                                class={classes!("diff")}
+                                                text="Diff"/>
+                                        }
+                                        if ctx.props().tabs[active_tab].method.is_server_streaming && ctx.props().tabs[active_tab].output_monaco_ids.len() > 0 {
+                                            <Button
+                                                onclick={ctx.props().send_msg.clone().reform(move |_| {
+                                                    UiMsg::ToggleFollowOutput(active_tab)
+                                                })}
+                                                has_led={true}
+                                                is_led_lit={ctx.props().tabs[active_tab].follow_output}
+                                                class={classes!("follow")}
+                                                text="Follow"/>
+                                            <Button
+                                                onclick={ctx.props().send_msg.clone().reform(move |_| {
+                                                    UiMsg::NavigateOutput((active_tab, -1))
+                                                })}
+                                                class={classes!("prev")}
+                                                text="Prev"
+                                                kind={ButtonKind::Cyan}/>
+                                            <div class="counter">
+                                                <span class="current">{ctx.props().tabs[active_tab].selected_output.map(|i| i + 1).unwrap_or(0)}</span>
+                                                <img class="line" src="img/line.svg"/>
+                                                <span class="of">{ctx.props().tabs[active_tab].output_monaco_ids.len()}</span>
+                                            </div>
+                                            <Button
+                                                onclick={ctx.props().send_msg.clone().reform(move |_| {
+                                                    UiMsg::NavigateOutput((active_tab,1))
+                                                })}
+                                                class={classes!("next")}
+                                                text="Next"
+                                                kind={ButtonKind::Cyan}/>
+                                        }
                                     </div>
                                 }
                             }
@@ -323,6 +542,45 @@ impl Component for Main {
     }
 }
 
+// What else is known about an output of a tab besides its JSON
+#[derive(Clone, PartialEq)]
+struct OutputDetails {
+    // Fields that the descriptors don't define
+    unknown_fields: Vec<UnknownField>,
+}
+
+// Unknown fields of the output being shown, if it has any
+fn unknown_fields_shown(tab: &Tab) -> Option<&Vec<UnknownField>> {
+    if tab.diff.is_some() || tab.export.is_some() || tab.raw_view.is_some() || tab.showing_timeline {
+        return None;
+    }
+    tab.selected_output
+        .and_then(|selected_output| tab.output_details.get(selected_output))
+        .map(|details| &details.unknown_fields)
+        .filter(|unknown_fields| !unknown_fields.is_empty())
+}
+
+// Differences between the pinned output and one of the outputs of a tab
+#[derive(Clone, PartialEq)]
+struct TabDiff {
+    left_label: String,
+    right_label: String,
+    differences: Vec<Difference>,
+}
+
+// The call of a tab exported as a command line or a client snippet
+#[derive(Clone, PartialEq)]
+struct TabExport {
+    format: ExportFormat,
+    snippet: String,
+}
+
+// An output message set aside to be compared with others, possibly from other tabs
+struct PinnedOutput {
+    label: String,
+    content: String,
+}
+
 #[derive(Default, Clone, PartialEq)]
 pub struct MetadataRow {
     pub key: String,
@@ -335,12 +593,15 @@ struct Tab {
     // linked to the respective method even if the repo changes (files are
     // added, removed, or it is simply refreshed). In that scenario, the
     // MethodView of all tabs must be reloaded with the full_name acting as key.
-    // Tabs whose method no longer exists in the repo should disappear from
-    // the UI.
+    // Tabs whose method no longer exists in the repo are kept but marked as
+    // vanished, in case the method comes back after the protos are fixed.
     method: MethodView,
+    vanished: bool,
 
     input_monaco_id: i32,
     output_monaco_ids: Vec<i32>,
+    // Same order as `output_monaco_ids`
+    output_details: Vec<OutputDetails>,
     selected_output: Option<usize>,
 
     follow_output: bool,
@@ -348,7 +609,37 @@ struct Tab {
     metadata: Vec<MetadataRow>,
     editing_metadata: bool,
 
+    // When editing the input with the form instead of Monaco. The form works on `form_value`,
+    // which is parsed out of the Monaco model when the form is opened and written back to it on
+    // every change.
+    editing_form: bool,
+    form_value: serde_json::Value,
+    // Fetched the first time the form is opened
+    schema: Option<Schema>,
+
+    // Shown instead of the output while open
+    diff: Option<TabDiff>,
+    // Shown instead of the output while open
+    export: Option<TabExport>,
+    // Encoding of the output, shown instead of it while open
+    raw_view: Option<RawFormat>,
+    // Encoding of the output at that index, fetched from the backend when the raw view shows it
+    raw_dump: Option<(usize, RawDump)>,
+
+    // What was sent and received during the last call
+    timeline: Vec<TimelineEvent>,
+    // When the last call was started, in milliseconds since the Unix epoch
+    call_started_at: f64,
+    // Shown instead of the output while true
+    showing_timeline: bool,
+
     call_id: Option<i32>,
+    // Call that received the outputs, the backend keeps their encoding until told to forget it
+    outputs_call_id: Option<i32>,
+    // How many attempts the last call took, as reported by the backend
+    attempts: Option<u32>,
+    // Address of the server that served the last call
+    peer: Option<String>,
 
     profile_id: Option<Uuid>,
 }
@@ -357,13 +648,28 @@ impl Tab {
     pub fn new(method: MethodView, input_monaco_id: i32) -> Self {
         Self {
             method,
+            vanished: false,
             input_monaco_id,
             output_monaco_ids: Vec::new(),
+            output_details: Vec::new(),
             selected_output: None,
             follow_output: true,
             metadata: Vec::new(),
             editing_metadata: false,
+            editing_form: false,
+            form_value: serde_json::Value::Null,
+            schema: None,
+            diff: None,
+            export: None,
+            raw_view: None,
+            raw_dump: None,
+            timeline: Vec::new(),
+            call_started_at: 0.0,
+            showing_timeline: false,
             call_id: None,
+            outputs_call_id: None,
+            attempts: None,
+            peer: None,
             profile_id: None,
         }
     }
@@ -375,8 +681,19 @@ enum UiMsg {
     // For changing the loaded RepoView, should be the result of a
     // UiMsg::SetProtoFiles
     SetRepoView(RepoView),
+    // The backend reloaded the protos because they changed on disk
+    ProtosReloaded(ReloadSummary),
+    DismissReloadSummary,
+    StoreReloadListeners(Vec<Listener>),
     ReportError(String),
 
+    ShowMethodDocs(String),
+    // Argument is the full name of a message or enum
+    ShowTypeDocs(String),
+    PushDocs(Docs),
+    DocsBack,
+    CloseDocs,
+
     RequestNewTab{
         // Index of service in RepoView
         service_idx: usize,
@@ -386,13 +703,76 @@ enum UiMsg {
     NewTab{
         method_view: MethodView,
         input: String,
+        metadata: Vec<MetadataRow>,
+        // The first profile is used when not given
+        profile_id: Option<Uuid>,
     },
+    OpenSavedRequest(usize),
+    OpenRequest(SavedRequest),
     SelectTab(usize),
     DestroyTab(usize),
+    // Moves the tab to a new window or, when side_by_side, opens a copy of it in a
+    // new window next to this one
+    DetachTab {
+        tab_index: usize,
+        side_by_side: bool,
+    },
+    TabDetached {
+        input_monaco_id: i32,
+    },
     
     NavigateOutput((usize, i32)),
     ToggleFollowOutput(usize),
     ToggleEditingMetadata(usize),
+    ToggleEditingForm(usize),
+    // Input message changed through the form
+    UpdateForm((usize, serde_json::Value)),
+    SetSchema {
+        method_full_name: String,
+        schema: Schema,
+    },
+    // Registers the JSON Schema of the input message with Monaco
+    SetJsonSchema {
+        method_full_name: String,
+        json_schema: String,
+    },
+
+    // Sets the selected output of the tab aside for diffing
+    PinOutput(usize),
+    // Compares the pinned output with the selected output of the tab
+    DiffWithPinned(usize),
+    SetDiff {
+        input_monaco_id: i32,
+        diff: TabDiff,
+    },
+    CloseDiff(usize),
+
+    // Shows the call of the tab exported in the last format used, or hides it
+    ToggleExport(usize),
+    ExportCall {
+        tab_index: usize,
+        format: ExportFormat,
+    },
+    SetExport {
+        input_monaco_id: i32,
+        export: TabExport,
+    },
+
+    ToggleTimeline(usize),
+    // Shows the encoding of the selected output instead of its JSON, or hides it
+    ToggleRawView(usize),
+    SetRawFormat {
+        tab_index: usize,
+        format: RawFormat,
+    },
+    // Encoding of an output of the call, once the backend has dumped it
+    SetRawDump {
+        call_id: i32,
+        output: usize,
+        raw: RawDump,
+    },
+    // Shows the output at the given index of the tab
+    SelectOutput((usize, usize)),
 
     NewMetadataRow(usize),
     UpdateMetadataRow((usize, usize, MetadataRow)),
@@ -443,8 +823,20 @@ enum UiMsg {
     SetSettings(Settings),
     GoToSettings,
     LeaveSettings,
+    // Imports grpcurl command lines or a Postman collection
+    ImportRequests(String),
+    MergeImport(Import),
 
     UseProfile(usize, Option<Uuid>),
+
+    // Tabs to reopen once the protos are loaded
+    SetPendingWorkspace(Workspace),
+    // Saves the open tabs if they changed since the last time
+    SaveWorkspace,
+
+    // Checks the health of the servers of all profiles
+    PollHealth,
+    SetHealth(Uuid, Health),
 }
 
 struct Error {
@@ -457,6 +849,16 @@ struct Error {
 struct Ui {
     // Shown on the sidebar
     repo_view: Option<RepoView>,
+    // What changed the last time the backend reloaded the protos by itself
+    reload_summary: Option<ReloadSummary>,
+    // For the events sent by the backend when it reloads the protos
+    _reload_listeners: Vec<Listener>,
+    // Pages of the docs panel, the last one is being shown
+    docs_history: Vec<Docs>,
+
+    pinned_output: Option<PinnedOutput>,
+    // Format that calls are exported in when the export is opened
+    last_export_format: ExportFormat,
 
     tabs: Vec<(Tab, Option<Listener>)>,
     active_tab: Option<usize>,
@@ -467,6 +869,19 @@ struct Ui {
 
     settings: Settings,
     is_in_settings: bool,
+
+    // Last known health of each profile's server, refreshed by `_health_poll`
+    health: HashMap<Uuid, Health>,
+    _health_poll: Interval,
+
+    // Restored by the first SetRepoView
+    pending_workspace: Option<Workspace>,
+    // Nothing is saved until the workspace has been restored, lest it be
+    // overwritten by an empty one. Detached windows never restore it.
+    is_workspace_restored: bool,
+    // JSON encoded Workspace as last saved by `_workspace_autosave`
+    saved_workspace: String,
+    _workspace_autosave: Interval,
 }
 
 impl Component for Ui {
@@ -475,7 +890,7 @@ impl Component for Ui {
 
     fn create(ctx: &Context<Self>) -> Self {
         ctx.link().send_future_batch(async {
-            let mut messages = vec![UiMsg::ReloadProtos];
+            let mut messages = vec![UiMsg::PollHealth];
             let settings = load_settings().await;
             match settings {
                 Ok(Some(settings)) => {
@@ -486,10 +901,66 @@ impl Component for Ui {
                     messages.push(UiMsg::ReportError(err));
                 }
             };
+            match take_detached_tab().await {
+                // The protos are shared with the window that the tab was
+                // detached from, reloading them would disrupt its calls
+                Ok(Some(request)) => {
+                    match get_repo_view().await {
+                        Ok(repo_view) => messages.push(UiMsg::SetRepoView(repo_view)),
+                        Err(err) => messages.push(UiMsg::ReportError(err)),
+                    }
+                    // The tab goes into the editors, which are created after
+                    // the first render
+                    glue::monacoWhenReady("input").await;
+                    glue::monacoWhenReady("output").await;
+                    messages.push(UiMsg::OpenRequest(request));
+                },
+                Ok(None) => {
+                    match load_workspace().await {
+                        Ok(Some(workspace)) => {
+                            // The tabs are restored by SetRepoView and go into
+                            // the editors, which are created after the first
+                            // render
+                            glue::monacoWhenReady("input").await;
+                            glue::monacoWhenReady("output").await;
+                            messages.push(UiMsg::SetPendingWorkspace(workspace));
+                        },
+                        Ok(None) => messages.push(UiMsg::SetPendingWorkspace(Workspace::default())),
+                        Err(err) => messages.push(UiMsg::ReportError(err)),
+                    }
+                    messages.push(UiMsg::ReloadProtos);
+                },
+                Err(err) => {
+                    messages.push(UiMsg::ReloadProtos);
+                    messages.push(UiMsg::ReportError(err));
+                },
+            }
             messages
         });
+        let poll_health = ctx.link().callback(|_: ()| UiMsg::PollHealth);
+        let save_workspace = ctx.link().callback(|_: ()| UiMsg::SaveWorkspace);
+        let on_reloaded = ctx.link().callback(UiMsg::ProtosReloaded);
+        let on_reload_failed = ctx.link().callback(|err: String| {
+            UiMsg::ReportError(format!("error reloading protos: {err}"))
+        });
+        ctx.link().send_future(async move {
+            UiMsg::StoreReloadListeners(vec![
+                listen_event("protos-reloaded", Box::new(move |payload| {
+                    let summary = serde_json::from_str(&payload).expect("payload to be deserializable");
+                    on_reloaded.emit(summary);
+                })).await,
+                listen_event("protos-reload-failed", Box::new(move |payload| {
+                    on_reload_failed.emit(payload);
+                })).await,
+            ])
+        });
         Self {
             repo_view: None,
+            reload_summary: None,
+            _reload_listeners: Vec::new(),
+            docs_history: Vec::new(),
+            pinned_output: None,
+            last_export_format: ExportFormat::Grpcurl,
             tabs: Vec::new(),
             active_tab: None,
 
@@ -499,6 +970,14 @@ impl Component for Ui {
 
             settings: Settings::default(),
             is_in_settings: false,
+
+            health: HashMap::new(),
+            _health_poll: Interval::new(15_000, move || poll_health.emit(())),
+
+            pending_workspace: None,
+            is_workspace_restored: false,
+            saved_workspace: String::new(),
+            _workspace_autosave: Interval::new(5_000, move || save_workspace.emit(())),
         }
     }
 
@@ -506,42 +985,84 @@ impl Component for Ui {
         match msg {
             UiMsg::ReloadProtos => {
                 let paths = self.settings.proto_paths.clone();
-                ctx.link().send_future(async move {'fut: {
-                    if let Err(err) = reset_repo().await {
-                        break 'fut UiMsg::ReportError(err);
+                let later_overrides = self.settings.later_descriptors_override;
+                ctx.link().send_future_batch(async move {
+                    let mut messages = Vec::new();
+                    // Watched even when they fail to load so that fixing them
+                    // is picked up
+                    match watch_protos(&paths, later_overrides).await {
+                        Ok(missing) => {
+                            for dir in missing {
+                                messages.push(UiMsg::ReportError(format!("not watching {dir}: no such directory")));
+                            }
+                        }
+                        Err(err) => messages.push(UiMsg::ReportError(err)),
                     }
-                    for path in &paths {
-                        if let Err(err) = add_protobuf_descriptor(path).await {
-                            break 'fut UiMsg::ReportError(err);
+                    let loaded = async {
+                        reset_repo(later_overrides).await?;
+                        for path in &paths {
+                            add_protobuf_descriptor(path).await?;
                         }
+                        Ok::<_, String>(())
+                    }.await;
+                    if let Err(err) = loaded {
+                        messages.push(UiMsg::ReportError(err));
+                    }
+                    // Whatever did load is shown even if some protos failed, which
+                    // also restores the workspace, with the tabs whose method is
+                    // missing marked as vanished
+                    match get_repo_view().await {
+                        Ok(repo_view) => messages.push(UiMsg::SetRepoView(repo_view)),
+                        Err(err) => messages.push(UiMsg::ReportError(err)),
                     }
+                    messages
+                });
+                false
+            },
+            UiMsg::ProtosReloaded(summary) => {
+                // Tabs are refreshed by SetRepoView, their input is left untouched
+                ctx.link().send_future(async {
                     match get_repo_view().await {
                         Ok(repo_view) => UiMsg::SetRepoView(repo_view),
                         Err(err) => UiMsg::ReportError(err),
                     }
-                }});
+                });
+                self.reload_summary = Some(summary).filter(|summary| !summary.is_empty());
+                true
+            },
+            UiMsg::DismissReloadSummary => {
+                self.reload_summary = None;
+                true
+            },
+            UiMsg::StoreReloadListeners(listeners) => {
+                self._reload_listeners = listeners;
                 false
             },
             UiMsg::SetRepoView(repo_view) => {
-                self.tabs.retain_mut(|(tab, _)| {
-                    // Only keep tabs that are running and those whose method
-                    // still exists in the new loaded proto repo
+                for (tab, _) in &mut self.tabs {
+                    // Running tabs are left alone
                     if tab.call_id.is_some() {
-                        return true;
+                        continue;
                     }
                     if let Some(updated_method_view) = repo_view.find_method_desc(&tab.method.full_name) {
                         tab.method = updated_method_view;
-                        true
+                        tab.vanished = false;
+                        // The message might have changed shape
+                        tab.schema = None;
+                        if tab.editing_form {
+                            fetch_schema(ctx, tab.method.full_name.clone());
+                        }
+                        fetch_json_schema(ctx, tab.method.full_name.clone());
                     } else {
-                        false
-                    }
-                });
-                if let Some(active_tab) = self.active_tab {
-                    if active_tab >= self.tabs.len() {
-                        self.active_tab = Some(self.tabs.len() - 1);
+                        tab.vanished = true;
                     }
                 }
+                if let Some(workspace) = self.pending_workspace.take() {
+                    self.restore_workspace(ctx, &repo_view, workspace);
+                }
                 self.repo_view = Some(repo_view);
+                // Could be describing definitions that changed or are gone
+                self.docs_history.clear();
                 true
             },
             UiMsg::ReportError(err) => {
@@ -560,28 +1081,76 @@ impl Component for Ui {
                 self.errors[err_idx].timeout_for_dismiss = Some(timeout);
                 true
             },
+            UiMsg::ShowMethodDocs(method_full_name) => {
+                ctx.link().send_future(async move {
+                    match get_method_docs(&method_full_name).await {
+                        Ok(docs) => UiMsg::PushDocs(docs),
+                        Err(err) => UiMsg::ReportError(err),
+                    }
+                });
+                false
+            },
+            UiMsg::ShowTypeDocs(type_full_name) => {
+                ctx.link().send_future(async move {
+                    match get_type_docs(&type_full_name).await {
+                        Ok(docs) => UiMsg::PushDocs(docs),
+                        Err(err) => UiMsg::ReportError(err),
+                    }
+                });
+                false
+            },
+            UiMsg::PushDocs(docs) => {
+                // Showing the page that's already open shouldn't grow the history
+                if self.docs_history.last().map(|last| last.full_name()) != Some(docs.full_name()) {
+                    self.docs_history.push(docs);
+                }
+                true
+            },
+            UiMsg::DocsBack => {
+                self.docs_history.pop();
+                true
+            },
+            UiMsg::CloseDocs => {
+                self.docs_history.clear();
+                true
+            },
             UiMsg::RequestNewTab { service_idx, method_idx } => {
                 let repo_view = self.repo_view.as_ref().expect("to have a repo view, since a method button was pressed");
                 let method_view = repo_view.services.get(service_idx).and_then(|service| service.methods.get(method_idx));
                 if let Some(method_view) = method_view {
                     let method_view = method_view.clone();
-                    ctx.link().send_future(async {
-                        let input = get_empty_input_message(&method_view.full_name).await;
-                        UiMsg::NewTab { method_view, input: input.ok().unwrap_or_else(|| String::new()) }
+                    let is_sample = self.settings.sample_new_tabs;
+                    ctx.link().send_future(async move {
+                        let input = if is_sample {
+                            get_sample_input_message(&method_view.full_name).await
+                        } else {
+                            get_empty_input_message(&method_view.full_name).await
+                        };
+                        UiMsg::NewTab {
+                            method_view,
+                            input: input.ok().unwrap_or_else(|| String::new()),
+                            metadata: Vec::new(),
+                            profile_id: None,
+                        }
                     });
                 }
                 false
             },
-            UiMsg::NewTab{method_view, input} => {
+            UiMsg::NewTab{method_view, input, metadata, profile_id} => {
                 let monaco_input = glue::monacoAddTab("input");
                 glue::monacoGoToTab("input", monaco_input);
                 glue::monacoWrite("input", monaco_input, &input);
 
                 glue::monacoDeselect("output");
 
+                fetch_json_schema(ctx, method_view.full_name.clone());
+
                 let mut tab = Tab::new(method_view, monaco_input);
+                tab.metadata = metadata;
 
-                if let Some(initial_profile_id) = 
+                if let Some(profile_id) = profile_id.filter(|profile_id| self.settings.profiles.contains_key(profile_id)) {
+                    tab.profile_id = Some(profile_id);
+                } else if let Some(initial_profile_id) = 
                     self.settings.profiles
                         .iter()
                         .min_by_key(|(_, profile)| profile.ordinal)
@@ -594,6 +1163,34 @@ impl Component for Ui {
                 self.active_tab = Some(self.tabs.len() - 1);
                 true
             },
+            UiMsg::OpenSavedRequest(idx) => {
+                if let Some(request) = self.settings.saved_requests.get(idx) {
+                    ctx.link().send_message(UiMsg::OpenRequest(request.clone()));
+                }
+                false
+            },
+            UiMsg::OpenRequest(request) => {
+                let method_view = self.repo_view
+                    .as_ref()
+                    .and_then(|repo_view| repo_view.find_method_desc(&request.method_full_name));
+                if let Some(method_view) = method_view {
+                    ctx.link().send_message(UiMsg::NewTab {
+                        method_view,
+                        input: request.body,
+                        metadata: request.metadata
+                            .into_iter()
+                            .map(|(key, val)| MetadataRow { key, val })
+                            .collect(),
+                        profile_id: request.profile_id,
+                    });
+                } else {
+                    ctx.link().send_message(UiMsg::ReportError(format!(
+                        "Method {} is not in the loaded protos",
+                        request.method_full_name
+                    )));
+                }
+                false
+            },
             UiMsg::SelectTab(tab_index) => {
                 let (tab, _) = &self.tabs[tab_index];
                 glue::monacoGoToTab("input", tab.input_monaco_id);
@@ -644,10 +1241,47 @@ impl Component for Ui {
                 for monaco_id in &tab.output_monaco_ids {
                     glue::monacoDelTab("output", *monaco_id);
                 }
+                if let Some(outputs_call_id) = tab.outputs_call_id {
+                    spawn_local(forget_call(outputs_call_id));
+                }
 
                 self.tabs.remove(tab_index);
                 true
             },
+            UiMsg::DetachTab { tab_index, side_by_side } => {
+                let (tab, _) = &self.tabs[tab_index];
+                if !side_by_side && tab.call_id.is_some() {
+                    ctx.link().send_message(UiMsg::ReportError("Stop the call before detaching the tab".to_string()));
+                    return false;
+                }
+                let request = SavedRequest {
+                    name: tab.method.name.clone(),
+                    method_full_name: tab.method.full_name.clone(),
+                    body: String::from(glue::monacoRead("input", tab.input_monaco_id)),
+                    metadata: tab.metadata
+                        .iter()
+                        .map(|row| (row.key.clone(), row.val.clone()))
+                        .collect(),
+                    profile_id: tab.profile_id,
+                };
+                let input_monaco_id = tab.input_monaco_id;
+                ctx.link().send_future_batch(async move {
+                    match detach_tab(&request, side_by_side).await {
+                        // When comparing, the tab stays here as well
+                        Ok(()) if side_by_side => None,
+                        Ok(()) => Some(UiMsg::TabDetached { input_monaco_id }),
+                        Err(err) => Some(UiMsg::ReportError(err)),
+                    }
+                });
+                false
+            },
+            UiMsg::TabDetached { input_monaco_id } => {
+                let tab_index = self.tabs.iter().position(|(tab, _)| tab.input_monaco_id == input_monaco_id);
+                if let Some(tab_index) = tab_index {
+                    ctx.link().send_message(UiMsg::DestroyTab(tab_index));
+                }
+                false
+            },
             UiMsg::NavigateOutput((tab_index, move_by)) => {
                 let (tab, _) = &mut self.tabs[tab_index];
                 let n_outputs = tab.output_monaco_ids.len();
@@ -675,9 +1309,60 @@ impl Component for Ui {
                 tab.editing_metadata = !tab.editing_metadata;
                 true
             },
+            UiMsg::ToggleEditingForm(tab_index) => {
+                let (tab, _) = &mut self.tabs[tab_index];
+                if tab.editing_form {
+                    tab.editing_form = false;
+                    return true;
+                }
+
+                let input = String::from(glue::monacoRead("input", tab.input_monaco_id));
+                let input = if input.trim().is_empty() { "{}" } else { input.as_str() };
+                match serde_json::from_str(input) {
+                    Ok(value) => tab.form_value = value,
+                    Err(err) => {
+                        ctx.link().send_message(UiMsg::ReportError(format!("Fix the input message before opening the form: {err}")));
+                        return false;
+                    }
+                }
+                tab.editing_form = true;
+
+                if tab.schema.is_none() {
+                    fetch_schema(ctx, tab.method.full_name.clone());
+                }
+                true
+            },
+            UiMsg::UpdateForm((tab_index, value)) => {
+                let (tab, _) = &mut self.tabs[tab_index];
+                let input = serde_json::to_string_pretty(&value).expect("JSON values to serialize");
+                glue::monacoWrite("input", tab.input_monaco_id, &input);
+                tab.form_value = value;
+                true
+            },
+            UiMsg::SetSchema { method_full_name, schema } => {
+                for (tab, _) in self.tabs.iter_mut().filter(|(tab, _)| tab.method.full_name == method_full_name) {
+                    tab.schema = Some(schema.clone());
+                }
+                true
+            },
+            UiMsg::SetJsonSchema { method_full_name, json_schema } => {
+                // Tabs might have been closed while the schema was being generated
+                for (tab, _) in self.tabs.iter().filter(|(tab, _)| tab.method.full_name == method_full_name) {
+                    glue::monacoSetSchema("input", tab.input_monaco_id, &json_schema);
+                }
+                false
+            },
             UiMsg::CallStart { tab_index, method_full_name, initial_message } => {
                 let (tab, _) = &mut self.tabs[tab_index];
 
+                if tab.vanished {
+                    ctx.link().send_message(UiMsg::ReportError(format!(
+                        "Method {} is not in the loaded protos",
+                        tab.method.full_name
+                    )));
+                    return true;
+                }
+
                 let profile_id = if let Some(profile_id) = tab.profile_id.clone() {
                     profile_id
                 } else {
@@ -696,13 +1381,30 @@ impl Component for Ui {
                 self.next_call_id += 1;
 
                 tab.call_id = Some(call_id);
+                tab.attempts = None;
+                tab.peer = None;
+
+                tab.call_started_at = js_sys::Date::now();
+                tab.timeline.clear();
+                if let Some(initial_message) = &initial_message {
+                    tab.timeline.push(TimelineEvent {
+                        at: tab.call_started_at,
+                        entry: TimelineEntry::Sent(initial_message.clone()),
+                    });
+                }
 
                 for monaco_id in &tab.output_monaco_ids {
                     glue::monacoDeselect("output");
                     glue::monacoDelTab("output", *monaco_id);
                 }
                 tab.output_monaco_ids.clear();
+                tab.output_details.clear();
+                tab.raw_view = None;
+                tab.raw_dump = None;
                 tab.selected_output = None;
+                if let Some(outputs_call_id) = tab.outputs_call_id.replace(call_id) {
+                    spawn_local(forget_call(outputs_call_id));
+                }
 
                 let metadata = tab.metadata.clone();
 
@@ -714,7 +1416,7 @@ impl Component for Ui {
                     let listener = listen(call_id, Box::new(move |op_out| {
                         recv.emit(op_out);
                     })).await;
-                    start_call(call_id, &profile.endpoint, &method_full_name, &metadata[..]).await.unwrap();
+                    start_call(call_id, &profile.endpoint, &profile.call_options, &method_full_name, &metadata[..]).await.unwrap();
                     if let Some(initial_message) = initial_message {
                         message(call_id, &initial_message);
                     }
@@ -731,6 +1433,10 @@ impl Component for Ui {
                 if let Some((tab, _)) = self.tabs.iter_mut().find(move |(tab, _)| tab.call_id == Some(call_id)) {
                     if let Some(call_id) = tab.call_id {
                         message(call_id, &body);
+                        tab.timeline.push(TimelineEvent {
+                            at: js_sys::Date::now(),
+                            entry: TimelineEntry::Sent(body),
+                        });
                         true
                     } else {
                         false
@@ -746,16 +1452,28 @@ impl Component for Ui {
                     .find(move |(_, (tab, _))| tab.call_id == Some(call_id))
                     .unwrap();
                 match op_out {
-                    CallOpOut::Msg(output) => {
+                    CallOpOut::Msg { msg: output, seq, received_at, size, unknown_fields } => {
                         let monaco_id = glue::monacoAddTab("output");
                         glue::monacoWrite("output", monaco_id, &output);
                         tab.output_monaco_ids.push(monaco_id);
+                        tab.timeline.push(TimelineEvent {
+                            at: received_at as f64,
+                            entry: TimelineEntry::Received {
+                                msg: output,
+                                seq,
+                                size,
+                                unknown_fields: unknown_fields.len(),
+                                output: tab.output_monaco_ids.len() - 1,
+                            },
+                        });
+                        tab.output_details.push(OutputDetails { unknown_fields });
 
                         if !tab.method.is_server_streaming {
                             if self.active_tab == Some(tab_index) {
                                 glue::monacoGoToTab("output", monaco_id);
                             }
                             tab.selected_output = Some(0);
+                            fetch_raw_dump(ctx, tab);
 
                             terminate_call((tab, tab_listener));
                         } else if tab.follow_output || tab.output_monaco_ids.len() == 1 {
@@ -763,9 +1481,26 @@ impl Component for Ui {
                                 glue::monacoGoToTab("output", monaco_id);
                             }
                             tab.selected_output = Some(tab.output_monaco_ids.len() - 1);
+                            fetch_raw_dump(ctx, tab);
                         }
                     },
+                    CallOpOut::Headers(headers) => {
+                        tab.timeline.push(TimelineEvent {
+                            at: js_sys::Date::now(),
+                            entry: TimelineEntry::Headers(headers),
+                        });
+                    },
+                    CallOpOut::Trailers(trailers) => {
+                        tab.timeline.push(TimelineEvent {
+                            at: js_sys::Date::now(),
+                            entry: TimelineEntry::Trailers(trailers),
+                        });
+                    },
                     CallOpOut::Err(err) => {
+                        tab.timeline.push(TimelineEvent {
+                            at: js_sys::Date::now(),
+                            entry: TimelineEntry::Error(err.clone()),
+                        });
                         ctx.link().send_message(UiMsg::ReportError(err));
                         // Abort the request as soon as we encounter an error
                         terminate_call((tab, tab_listener));
@@ -789,7 +1524,17 @@ impl Component for Ui {
                         }
                     },
                     CallOpOut::Commit => {
+                        tab.timeline.push(TimelineEvent {
+                            at: js_sys::Date::now(),
+                            entry: TimelineEntry::Closed,
+                        });
                         terminate_call((tab, tab_listener));
+                    },
+                    CallOpOut::Attempts(attempts) => {
+                        tab.attempts = Some(attempts);
+                    },
+                    CallOpOut::Peer(peer) => {
+                        tab.peer = Some(peer);
                     }
                 }
                 true
@@ -811,6 +1556,10 @@ impl Component for Ui {
                 if let Some((tab, _)) = self.tabs.iter_mut().find(move |(tab, _)| tab.call_id == Some(call_id)) {
                     if let Some(call_id) = tab.call_id {
                         commit(call_id);
+                        tab.timeline.push(TimelineEvent {
+                            at: js_sys::Date::now(),
+                            entry: TimelineEntry::Committed,
+                        });
                         true
                     } else {
                         false
@@ -819,6 +1568,144 @@ impl Component for Ui {
                     false
                 }
             },
+            UiMsg::PinOutput(tab_index) => {
+                let (tab, _) = &self.tabs[tab_index];
+                if let Some(selected_output) = tab.selected_output {
+                    let content = String::from(glue::monacoRead("output", tab.output_monaco_ids[selected_output]));
+                    self.pinned_output = Some(PinnedOutput {
+                        label: output_label(tab, selected_output),
+                        content,
+                    });
+                }
+                true
+            },
+            UiMsg::DiffWithPinned(tab_index) => {
+                let (tab, _) = &mut self.tabs[tab_index];
+                // Pressing the button again closes the diff
+                if tab.diff.take().is_some() {
+                    return true;
+                }
+                let (pinned, selected_output) = match (&self.pinned_output, tab.selected_output) {
+                    (Some(pinned), Some(selected_output)) => (pinned, selected_output),
+                    _ => return false,
+                };
+
+                let left_label = pinned.label.clone();
+                let left = pinned.content.clone();
+                let right_label = output_label(tab, selected_output);
+                let right = String::from(glue::monacoRead("output", tab.output_monaco_ids[selected_output]));
+                let input_monaco_id = tab.input_monaco_id;
+                let ignore = self.settings.diff_ignore_paths.clone();
+                ctx.link().send_future(async move {
+                    match diff_messages(&left, &right, &ignore).await {
+                        Ok(differences) => UiMsg::SetDiff {
+                            input_monaco_id,
+                            diff: TabDiff { left_label, right_label, differences },
+                        },
+                        Err(err) => UiMsg::ReportError(err),
+                    }
+                });
+                false
+            },
+            UiMsg::SetDiff { input_monaco_id, diff } => {
+                // The tab might have been closed in the meantime
+                if let Some((tab, _)) = self.tabs.iter_mut().find(|(tab, _)| tab.input_monaco_id == input_monaco_id) {
+                    tab.diff = Some(diff);
+                }
+                true
+            },
+            UiMsg::ToggleTimeline(tab_index) => {
+                let (tab, _) = &mut self.tabs[tab_index];
+                tab.showing_timeline = !tab.showing_timeline;
+                true
+            },
+            UiMsg::ToggleRawView(tab_index) => {
+                let (tab, _) = &mut self.tabs[tab_index];
+                tab.raw_view = match tab.raw_view {
+                    Some(_) => None,
+                    None => Some(RawFormat::Wire),
+                };
+                fetch_raw_dump(ctx, tab);
+                true
+            },
+            UiMsg::SetRawFormat { tab_index, format } => {
+                let (tab, _) = &mut self.tabs[tab_index];
+                tab.raw_view = Some(format);
+                true
+            },
+            UiMsg::SetRawDump { call_id, output, raw } => {
+                // The tab might have been closed or started another call in the meantime
+                if let Some((tab, _)) = self.tabs.iter_mut().find(|(tab, _)| tab.outputs_call_id == Some(call_id)) {
+                    tab.raw_dump = Some((output, raw));
+                    true
+                } else {
+                    false
+                }
+            },
+            UiMsg::SelectOutput((tab_index, output)) => {
+                let (tab, _) = &mut self.tabs[tab_index];
+                tab.showing_timeline = false;
+                tab.diff = None;
+                tab.export = None;
+                // Don't jump away from the chosen message when the next one arrives
+                tab.follow_output = false;
+                tab.selected_output = Some(output);
+                glue::monacoGoToTab("output", tab.output_monaco_ids[output]);
+                fetch_raw_dump(ctx, tab);
+                true
+            },
+            UiMsg::CloseDiff(tab_index) => {
+                let (tab, _) = &mut self.tabs[tab_index];
+                tab.diff = None;
+                true
+            },
+            UiMsg::ToggleExport(tab_index) => {
+                let (tab, _) = &mut self.tabs[tab_index];
+                if tab.export.take().is_some() {
+                    return true;
+                }
+                ctx.link().send_message(UiMsg::ExportCall { tab_index, format: self.last_export_format });
+                false
+            },
+            UiMsg::ExportCall { tab_index, format } => {
+                let (tab, _) = &self.tabs[tab_index];
+                let profile = match tab.profile_id.and_then(|profile_id| self.settings.profiles.get(&profile_id)) {
+                    Some(profile) => profile,
+                    None => {
+                        ctx.link().send_message(UiMsg::ReportError(String::from("You have to select a profile first")));
+                        return false;
+                    }
+                };
+                self.last_export_format = format;
+
+                let endpoint = profile.endpoint.clone();
+                let method_full_name = tab.method.full_name.clone();
+                let metadata: Vec<(String, String)> = tab.metadata
+                    .iter()
+                    .map(|row| (row.key.clone(), row.val.clone()))
+                    .collect();
+                let body = String::from(glue::monacoRead("input", tab.input_monaco_id));
+                let descriptor_paths = self.settings.proto_paths.clone();
+                let input_monaco_id = tab.input_monaco_id;
+                ctx.link().send_future(async move {
+                    match export_call(&endpoint, &method_full_name, &metadata, &body, &descriptor_paths, format).await {
+                        Ok(snippet) => UiMsg::SetExport {
+                            input_monaco_id,
+                            export: TabExport { format, snippet },
+                        },
+                        Err(err) => UiMsg::ReportError(err),
+                    }
+                });
+                false
+            },
+            UiMsg::SetExport { input_monaco_id, export } => {
+                // The tab might have been closed in the meantime
+                if let Some((tab, _)) = self.tabs.iter_mut().find(|(tab, _)| tab.input_monaco_id == input_monaco_id) {
+                    tab.showing_timeline = false;
+                    tab.export = Some(export);
+                }
+                true
+            },
             UiMsg::NewMetadataRow(tab_index) => {
                 let (tab, _) = &mut self.tabs[tab_index];
                 tab.metadata.push(Default::default());
@@ -847,6 +1734,44 @@ impl Component for Ui {
                 self.errors.remove(idx);
                 true
             },
+            UiMsg::SetPendingWorkspace(workspace) => {
+                self.pending_workspace = Some(workspace);
+                false
+            },
+            UiMsg::SaveWorkspace => {
+                if !self.is_workspace_restored {
+                    return false;
+                }
+                let workspace = Workspace {
+                    tabs: self.tabs
+                        .iter()
+                        .map(|(tab, _)| WorkspaceTab {
+                            method: tab.method.clone(),
+                            input: String::from(glue::monacoRead("input", tab.input_monaco_id)),
+                            metadata: tab.metadata
+                                .iter()
+                                .map(|row| (row.key.clone(), row.val.clone()))
+                                .collect(),
+                            profile_id: tab.profile_id,
+                            follow_output: tab.follow_output,
+                        })
+                        .collect(),
+                    active_tab: self.active_tab,
+                };
+                let content = to_string(&workspace).expect("no error encoding workspace");
+                if content == self.saved_workspace {
+                    return false;
+                }
+                self.saved_workspace = content.clone();
+                ctx.link().send_future_batch(async move {
+                    if let Err(err) = save_workspace(&content).await {
+                        Some(UiMsg::ReportError(err))
+                    } else {
+                        None
+                    }
+                });
+                false
+            },
             UiMsg::SetSettings(settings) => {
                 self.settings = settings;
                 true
@@ -858,6 +1783,7 @@ impl Component for Ui {
             UiMsg::LeaveSettings => {
                 self.is_in_settings = false;
                 ctx.link().send_message(UiMsg::ReloadProtos);
+                ctx.link().send_message(UiMsg::PollHealth);
 
                 let settings = self.settings.clone();
                 ctx.link().send_future_batch(async move {
@@ -871,11 +1797,55 @@ impl Component for Ui {
                 true
             },
 
+            UiMsg::ImportRequests(text) => {
+                ctx.link().send_future(async move {
+                    match import_requests(&text).await {
+                        Ok(import) => UiMsg::MergeImport(import),
+                        Err(err) => UiMsg::ReportError(err),
+                    }
+                });
+                false
+            },
+            UiMsg::MergeImport(mut import) => {
+                for warning in import.warnings.drain(..) {
+                    ctx.link().send_message(UiMsg::ReportError(warning));
+                }
+                if self.settings.merge_import(import) == 0 {
+                    ctx.link().send_message(UiMsg::ReportError(String::from("Found no requests to import")));
+                }
+                true
+            },
+
             UiMsg::UseProfile(tab_index, profile_id) => {
                 let (tab, _) = &mut self.tabs[tab_index];
                 tab.profile_id = profile_id;
                 true
             },
+
+            UiMsg::PollHealth => {
+                // Forget about profiles that have been deleted
+                self.health.retain(|id, _| self.settings.profiles.contains_key(id));
+                for (id, profile) in &self.settings.profiles {
+                    if profile.endpoint.authority.is_empty() {
+                        continue;
+                    }
+                    let (id, endpoint) = (id.clone(), profile.endpoint.clone());
+                    ctx.link().send_future(async move {
+                        let health = match check_health(&endpoint).await {
+                            Ok(status) => Health::Status(status),
+                            Err(err) => Health::Unreachable(err),
+                        };
+                        UiMsg::SetHealth(id, health)
+                    });
+                }
+                true
+            },
+            UiMsg::SetHealth(id, health) => {
+                if self.settings.profiles.contains_key(&id) {
+                    self.health.insert(id, health);
+                }
+                true
+            },
         }
     }
 
@@ -891,12 +1861,15 @@ impl Component for Ui {
                 if self.is_in_settings {
                     <SettingsEditor
                         settings={ self.settings.clone() }
+                        health={ self.health.clone() }
                         leave_settings={ send_msg.clone().reform(|_| UiMsg::LeaveSettings) }
-                        set_settings={ send_msg.clone().reform(|settings| UiMsg::SetSettings(settings)) }/>
+                        set_settings={ send_msg.clone().reform(|settings| UiMsg::SetSettings(settings)) }
+                        on_import={ send_msg.clone().reform(UiMsg::ImportRequests) }/>
                 } else {
                     <Pane initial_left={ 0.2 }>
-                        <Sidebar repo_view={ self.repo_view.clone() } send_msg={ send_msg.clone() }/>
-                        <Main { tabs } active_tab={ self.active_tab } send_msg={ send_msg.clone() } profiles={{
+                        <Sidebar repo_view={ self.repo_view.clone() } docs_history={ self.docs_history.clone() } saved_requests={ self.settings.saved_requests.clone() } reload_summary={ self.reload_summary.clone() } send_msg={ send_msg.clone() }/>
+                        <Main { tabs } active_tab={ self.active_tab } send_msg={ send_msg.clone() } health={ self.health.clone() }
+                            pinned_label={ self.pinned_output.as_ref().map(|pinned| pinned.label.clone()) } profiles={{
                             let mut profiles = self.settings.profiles.iter().map(|(id, profile)| {
                                 (id.clone(), profile.clone())
                             }).collect::<Vec<_>>();
@@ -913,6 +1886,88 @@ impl Component for Ui {
     }
 }
 
+impl Ui {
+    // Reopens the tabs of the workspace, matching them with the loaded protos by the
+    // full name of their method
+    fn restore_workspace(&mut self, ctx: &Context<Self>, repo_view: &RepoView, workspace: Workspace) {
+        let first_restored = self.tabs.len();
+        for saved_tab in workspace.tabs {
+            let input_monaco_id = glue::monacoAddTab("input");
+            glue::monacoWrite("input", input_monaco_id, &saved_tab.input);
+
+            let mut tab = match repo_view.find_method_desc(&saved_tab.method.full_name) {
+                Some(method_view) => {
+                    fetch_json_schema(ctx, method_view.full_name.clone());
+                    Tab::new(method_view, input_monaco_id)
+                },
+                None => {
+                    let mut tab = Tab::new(saved_tab.method, input_monaco_id);
+                    tab.vanished = true;
+                    tab
+                },
+            };
+            tab.metadata = saved_tab.metadata
+                .into_iter()
+                .map(|(key, val)| MetadataRow { key, val })
+                .collect();
+            tab.profile_id = saved_tab.profile_id.filter(|profile_id| self.settings.profiles.contains_key(profile_id));
+            tab.follow_output = saved_tab.follow_output;
+
+            self.tabs.push((tab, None));
+        }
+
+        let active_tab = workspace.active_tab
+            .map(|active_tab| first_restored + active_tab)
+            .filter(|active_tab| *active_tab < self.tabs.len());
+        if let Some(active_tab) = active_tab {
+            ctx.link().send_message(UiMsg::SelectTab(active_tab));
+        }
+
+        self.is_workspace_restored = true;
+    }
+}
+
+// Names an output for the diff view, like `GetUser #2`
+fn output_label(tab: &Tab, output: usize) -> String {
+    format!("{} #{}", tab.method.name, output + 1)
+}
+
+fn fetch_schema(ctx: &Context<Ui>, method_full_name: String) {
+    ctx.link().send_future(async move {
+        match get_message_schema(&method_full_name).await {
+            Ok(schema) => UiMsg::SetSchema { method_full_name, schema },
+            Err(err) => UiMsg::ReportError(err),
+        }
+    });
+}
+
+fn fetch_json_schema(ctx: &Context<Ui>, method_full_name: String) {
+    ctx.link().send_future(async move {
+        match get_json_schema(&method_full_name).await {
+            Ok(json_schema) => UiMsg::SetJsonSchema { method_full_name, json_schema },
+            Err(err) => UiMsg::ReportError(err),
+        }
+    });
+}
+
+// Asks the backend for the encoding of the output shown in the raw view of the tab, unless it's
+// not open or already has it
+fn fetch_raw_dump(ctx: &Context<Ui>, tab: &Tab) {
+    let (output, call_id) = match (tab.raw_view, tab.selected_output, tab.outputs_call_id) {
+        (Some(_), Some(output), Some(call_id)) => (output, call_id),
+        _ => return,
+    };
+    if matches!(&tab.raw_dump, Some((dumped, _)) if *dumped == output) {
+        return;
+    }
+    ctx.link().send_future(async move {
+        match get_raw_dump(call_id, output).await {
+            Ok(raw) => UiMsg::SetRawDump { call_id, output, raw },
+            Err(err) => UiMsg::ReportError(err),
+        }
+    });
+}
+
 fn terminate_call(tab: (&mut Tab, &mut Option<Listener>)) {
     let (tab, listener) = tab;
     tab.call_id = None;
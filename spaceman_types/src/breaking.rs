@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// How bad a change between two versions of an API is for existing clients and servers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    /// Breaks neither encoding but is worth a look, like a removed message that might still be
+    /// packed into an `Any`.
+    Info,
+    /// Breaks the JSON mapping but not the binary encoding, like a renamed field.
+    Warning,
+    /// Breaks the binary encoding.
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A change between two versions of an API.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BreakingChange {
+    pub severity: Severity,
+    /// Full name of what changed, like `package.Message.field` or `package.Service.Method`.
+    pub subject: String,
+    pub description: String,
+}
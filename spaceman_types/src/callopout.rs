@@ -1,10 +1,33 @@
 use serde::{Deserialize, Serialize};
 
+use crate::wire::UnknownField;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum CallOpOut {
-    Msg(String),
+    Msg {
+        msg: String,
+        /// Position of the message in the response, starting from 0.
+        seq: u32,
+        /// When the backend received the message, in milliseconds since the Unix epoch.
+        received_at: u64,
+        /// Size of the encoded message, without the gRPC framing.
+        size: usize,
+        /// Fields of the message that the descriptors don't define.
+        unknown_fields: Vec<UnknownField>,
+    },
+    /// Response headers, sent before the first message. For unary and client-streaming calls
+    /// they include the trailers too, since tonic merges the two.
+    Headers(Vec<(String, String)>),
+    /// Response trailers of server-streaming and bidi calls, sent before `Commit`.
+    Trailers(Vec<(String, String)>),
     Commit,
     InvalidInput(String),
+    /// A received message that couldn't be turned into JSON, with a dump of its encoding.
     InvalidOutput(String),
     Err(String),
+    /// How many attempts the call took, retries included. Sent before the outcome of unary and
+    /// server-streaming calls.
+    Attempts(u32),
+    /// Address of the server that the call was sent to. Sent before the outcome of the call.
+    Peer(String),
 }
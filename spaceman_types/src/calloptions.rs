@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+/// Options that shape how calls are carried out, regardless of the server they are sent to.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct CallOptions {
+    /// When missing, calls are attempted only once.
+    pub retry_policy: Option<RetryPolicy>,
+}
+
+/// Policy for retrying failed calls, modeled after the `retryPolicy` of gRPC's service config.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the original one.
+    pub max_attempts: u32,
+    /// Upper bound of the randomized delay before the first retry, in milliseconds.
+    pub initial_backoff_ms: u64,
+    /// Cap on the upper bound of the randomized delay between retries, in milliseconds.
+    pub max_backoff_ms: u64,
+    /// Growth factor of the delay upper bound after each retry.
+    pub backoff_multiplier: f64,
+    /// Status codes that make a call eligible for a retry, spelled like `UNAVAILABLE`.
+    pub retryable_status_codes: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1_000,
+            backoff_multiplier: 2.0,
+            retryable_status_codes: vec!["UNAVAILABLE".to_string()],
+        }
+    }
+}
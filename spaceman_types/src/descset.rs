@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// What a descriptor set can be written out as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DescSetFormat {
+    /// An encoded `FileDescriptorSet`, like the output of `protoc -o`.
+    Binary,
+    /// The `FileDescriptorSet` in the JSON mapping of Protobuf.
+    Json,
+    /// A `.proto` source file for each file of the set.
+    Proto,
+}
+
+impl DescSetFormat {
+    pub const ALL: [DescSetFormat; 3] = [
+        DescSetFormat::Binary,
+        DescSetFormat::Json,
+        DescSetFormat::Proto,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DescSetFormat::Binary => "binary",
+            DescSetFormat::Json => "json",
+            DescSetFormat::Proto => "proto",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.as_str() == name)
+    }
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// A field that differs between two messages.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Difference {
+    /// Path of the field, like `items[2].name` or `labels["env"]`.
+    pub path: String,
+    /// Compact JSON of the value on the left side, `None` if the left side doesn't have it.
+    pub left: Option<String>,
+    /// Compact JSON of the value on the right side, `None` if the right side doesn't have it.
+    pub right: Option<String>,
+}
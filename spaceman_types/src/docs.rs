@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+/// Documentation of a method or of a type, as shown in the docs panel.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Docs {
+    Method(MethodDocs),
+    Message(MessageDocs),
+    Enum(EnumDocs),
+}
+
+impl Docs {
+    pub fn full_name(&self) -> &str {
+        match self {
+            Docs::Method(docs) => &docs.full_name,
+            Docs::Message(docs) => &docs.full_name,
+            Docs::Enum(docs) => &docs.full_name,
+        }
+    }
+}
+
+/// What every documented definition has in common.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DefinitionDocs {
+    /// File the definition comes from.
+    pub file: String,
+    /// One-based line of the definition, if the descriptor carries source info.
+    pub line: Option<i32>,
+    /// Leading and trailing comments.
+    pub comments: Option<String>,
+    pub deprecated: bool,
+    /// Custom options that are set on the definition.
+    pub options: Vec<OptionDocs>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OptionDocs {
+    /// Full name of the extension that defines the option.
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MethodDocs {
+    pub name: String,
+    pub full_name: String,
+    pub definition: DefinitionDocs,
+    pub input: String,
+    pub output: String,
+    pub is_client_streaming: bool,
+    pub is_server_streaming: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MessageDocs {
+    pub name: String,
+    pub full_name: String,
+    pub definition: DefinitionDocs,
+    pub fields: Vec<FieldDocs>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldDocs {
+    pub name: String,
+    pub number: u32,
+    /// Type as it would be written in a .proto file, like `repeated string` or
+    /// `map<string, foo.Bar>`.
+    pub type_name: String,
+    /// Full name of the message or enum the field refers to, for maps it's the type of values.
+    pub type_ref: Option<String>,
+    /// Name of the oneof the field is part of.
+    pub oneof: Option<String>,
+    pub definition: DefinitionDocs,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EnumDocs {
+    pub name: String,
+    pub full_name: String,
+    pub definition: DefinitionDocs,
+    pub values: Vec<EnumValueDocs>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EnumValueDocs {
+    pub name: String,
+    pub number: i32,
+    pub definition: DefinitionDocs,
+}
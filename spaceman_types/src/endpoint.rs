@@ -3,10 +3,28 @@ use serde::{Deserialize, Serialize};
 /// Descriptor for a gRPC server.
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
 pub struct Endpoint {
-    /// Host name plus port.
+    /// Host name plus port. Can also be a comma separated list of them or a `dns:///host:port`
+    /// target, in which case calls are spread across all the addresses they resolve to.
     pub authority: String,
     /// TLS options.
     pub tls: Option<TlsOptions>,
+    /// Proxy to tunnel the connection through.
+    pub proxy: Option<ProxyOptions>,
+    /// How to pick the address to connect to when the authority resolves to more than one.
+    #[serde(default)]
+    pub lb_policy: LbPolicy,
+    /// Tuning of the HTTP/2 connection and of the TCP socket underneath. Defaults are used when
+    /// not given.
+    pub connection: Option<ConnectionOptions>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum LbPolicy {
+    /// Try addresses in order and use the first that accepts the connection.
+    #[default]
+    PickFirst,
+    /// Start from a different address on each connection, cycling through all of them.
+    RoundRobin,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
@@ -16,3 +34,56 @@ pub struct TlsOptions {
     /// Path to trusted CA certificate.
     pub ca_cert: Option<String>,
 }
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ProxyOptions {
+    /// Protocol spoken with the proxy.
+    pub kind: ProxyKind,
+    /// Host name plus port of the proxy.
+    pub authority: String,
+    /// Credentials to present to the proxy, if it requires any.
+    pub auth: Option<ProxyAuth>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum ProxyKind {
+    /// Tunnel through an HTTP proxy with the `CONNECT` method.
+    #[default]
+    HttpConnect,
+    /// Tunnel through a SOCKS5 proxy.
+    Socks5,
+}
+
+/// Any option left to `None` keeps hyper's or the OS's default.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ConnectionOptions {
+    /// Interval between HTTP/2 pings that keep the connection alive. Pings are disabled when not
+    /// given.
+    pub keepalive_interval_ms: Option<u64>,
+    /// How long to wait for a ping to be acknowledged before closing the connection.
+    pub keepalive_timeout_ms: Option<u64>,
+    /// Keep sending pings even when there are no open streams.
+    pub keepalive_while_idle: bool,
+    /// Initial HTTP/2 flow control window of each stream, in bytes.
+    pub initial_stream_window_size: Option<u32>,
+    /// Initial HTTP/2 flow control window of the whole connection, in bytes.
+    pub initial_connection_window_size: Option<u32>,
+    /// Largest HTTP/2 frame payload the client is willing to receive, in bytes. Must be within
+    /// `MAX_FRAME_SIZE_RANGE`.
+    pub max_frame_size: Option<u32>,
+    /// Disable Nagle's algorithm on the TCP socket.
+    pub tcp_nodelay: bool,
+    /// How long to wait for each TCP connection to be established, proxy handshake included.
+    pub connect_timeout_ms: Option<u64>,
+}
+
+impl ConnectionOptions {
+    /// Values that HTTP/2 allows for the largest frame size.
+    pub const MAX_FRAME_SIZE_RANGE: std::ops::RangeInclusive<u32> = 16_384..=16_777_215;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ProxyAuth {
+    pub username: String,
+    pub password: String,
+}
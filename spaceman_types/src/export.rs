@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// What a call can be exported as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    Grpcurl,
+    Spaceman,
+    Rust,
+    Go,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Grpcurl,
+        ExportFormat::Spaceman,
+        ExportFormat::Rust,
+        ExportFormat::Go,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Grpcurl => "grpcurl",
+            ExportFormat::Spaceman => "spaceman",
+            ExportFormat::Rust => "rust",
+            ExportFormat::Go => "go",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.as_str() == name)
+    }
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+/// Serving status of a service as reported by the standard `grpc.health.v1.Health` service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ServingStatus {
+    Unknown,
+    Serving,
+    NotServing,
+    /// Only ever reported while watching a service that the server doesn't know about.
+    ServiceUnknown,
+}
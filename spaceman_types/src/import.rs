@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::endpoint::Endpoint;
+use crate::settings::{Profile, SavedRequest, Settings};
+
+/// Requests read from another tool, together with the servers they're sent to.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Import {
+    /// Servers the requests are sent to.
+    pub endpoints: Vec<Endpoint>,
+    pub requests: Vec<ImportedRequest>,
+    /// Descriptor files the requests need.
+    pub proto_paths: Vec<String>,
+    /// What couldn't be carried over, like options that Spaceman has no equivalent for.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportedRequest {
+    pub name: String,
+    pub method_full_name: String,
+    /// Request message in its JSON mapping.
+    pub body: String,
+    pub metadata: Vec<(String, String)>,
+    /// Index of the request's server in `Import::endpoints`.
+    pub endpoint: Option<usize>,
+}
+
+impl Settings {
+    /// Adds the imported requests to the saved ones and a profile for each of their servers,
+    /// unless there's one with the same endpoint already. Returns how many requests were added.
+    pub fn merge_import(&mut self, import: Import) -> usize {
+        let profile_ids: Vec<Uuid> = import
+            .endpoints
+            .into_iter()
+            .map(|endpoint| {
+                if let Some((id, _)) = self
+                    .profiles
+                    .iter()
+                    .find(|(_, profile)| profile.endpoint == endpoint)
+                {
+                    return *id;
+                }
+                let ordinal = 1 + self
+                    .profiles
+                    .values()
+                    .map(|profile| profile.ordinal)
+                    .max()
+                    .unwrap_or(0);
+                let mut profile = Profile::new(ordinal);
+                profile.name = endpoint.authority.clone();
+                profile.endpoint = endpoint;
+                let id = Uuid::new_v4();
+                self.profiles.insert(id, profile);
+                id
+            })
+            .collect();
+
+        for path in import.proto_paths {
+            if !self.proto_paths.contains(&path) {
+                self.proto_paths.push(path);
+            }
+        }
+
+        let count = import.requests.len();
+        self.saved_requests
+            .extend(import.requests.into_iter().map(|request| {
+                SavedRequest {
+                    name: request.name,
+                    method_full_name: request.method_full_name,
+                    body: request.body,
+                    metadata: request.metadata,
+                    profile_id: request
+                        .endpoint
+                        .and_then(|idx| profile_ids.get(idx).copied()),
+                }
+            }));
+        count
+    }
+}
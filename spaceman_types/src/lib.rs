@@ -1,4 +1,18 @@
 pub mod repo;
 pub mod endpoint;
+pub mod breaking;
 pub mod callopout;
+pub mod calloptions;
+pub mod descset;
+pub mod diff;
+pub mod docs;
+pub mod export;
+pub mod health;
+pub mod import;
+pub mod lint;
+pub mod schema;
+pub mod sample;
+pub mod search;
 pub mod settings;
+pub mod wire;
+pub mod workspace;
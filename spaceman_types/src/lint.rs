@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+
+/// A check that protos are tidy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    /// Packages in lower_snake_case, messages, enums, services and methods in PascalCase, fields
+    /// in lower_snake_case and enum values in UPPER_SNAKE_CASE.
+    Naming,
+    /// The input of method `Foo` is called `FooRequest` and the output `FooResponse`.
+    RpcMessageNames,
+    /// Services, methods, messages and enums have a comment.
+    Comments,
+    /// The zero value of enum `Foo` is called `FOO_UNSPECIFIED`.
+    EnumZeroValue,
+    /// The package matches the directory of the file, `foo/bar/baz.proto` is in `foo.bar`.
+    PackageDirectory,
+    /// Every message is used by a method or a field.
+    UnusedMessages,
+}
+
+impl LintRule {
+    pub const ALL: [LintRule; 6] = [
+        LintRule::Naming,
+        LintRule::RpcMessageNames,
+        LintRule::Comments,
+        LintRule::EnumZeroValue,
+        LintRule::PackageDirectory,
+        LintRule::UnusedMessages,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LintRule::Naming => "naming",
+            LintRule::RpcMessageNames => "rpc-message-names",
+            LintRule::Comments => "comments",
+            LintRule::EnumZeroValue => "enum-zero-value",
+            LintRule::PackageDirectory => "package-directory",
+            LintRule::UnusedMessages => "unused-messages",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| rule.as_str() == name)
+    }
+}
+
+/// Which rules to run and how, usually read from a JSON file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LintConfig {
+    /// Rules to run, all of them if empty.
+    pub rules: Vec<LintRule>,
+    /// Rules to skip.
+    pub except: Vec<LintRule>,
+    /// Files whose name starts with any of these are only looked at to find out which messages
+    /// are used, they aren't linted. Well-known types and Google's APIs by default.
+    pub ignore: Vec<String>,
+    /// Whether `google.protobuf.Empty` is fine as the input or output of any method.
+    pub allow_empty: bool,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            except: Vec::new(),
+            ignore: vec!["google/".to_string()],
+            allow_empty: true,
+        }
+    }
+}
+
+impl LintConfig {
+    pub fn runs(&self, rule: LintRule) -> bool {
+        (self.rules.is_empty() || self.rules.contains(&rule)) && !self.except.contains(&rule)
+    }
+}
+
+/// Something that a lint rule found wrong.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LintFinding {
+    pub rule: LintRule,
+    /// Name of the file, relative to the compiler's root.
+    pub file: String,
+    /// One-based line, only known if the descriptors were compiled with source info.
+    pub line: Option<u32>,
+    /// Full name of the definition.
+    pub subject: String,
+    pub message: String,
+}
@@ -1,8 +1,25 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RepoViewServices")]
 pub struct RepoView {
     pub services: Vec<ServiceView>,
+    // Position of each method in `services` by full name, rebuilt when deserializing
+    #[serde(skip)]
+    methods: HashMap<String, (usize, usize)>,
+}
+
+#[derive(Deserialize)]
+struct RepoViewServices {
+    services: Vec<ServiceView>,
+}
+
+impl From<RepoViewServices> for RepoView {
+    fn from(from: RepoViewServices) -> Self {
+        RepoView::new(from.services)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -24,22 +41,51 @@ pub struct MethodView {
 }
 
 impl RepoView {
-    pub fn find_method_desc(&self, target: &str) -> Option<MethodView> {
-        // TODO Someday in the future, I should probably find a way to make this
-        // lookup faster cause right now it takes
-        // O(|services| + |methods per service|)
-        let service = self
-            .services
+    pub fn new(services: Vec<ServiceView>) -> Self {
+        let methods = services
             .iter()
-            .find(|&service| target.starts_with(&service.full_name));
-        if let Some(service) = service {
-            service
-                .methods
-                .iter()
-                .find(|&method| method.full_name == target)
-                .cloned()
-        } else {
-            None
-        }
+            .enumerate()
+            .flat_map(|(service_idx, service)| {
+                service
+                    .methods
+                    .iter()
+                    .enumerate()
+                    .map(move |(method_idx, method)| {
+                        (method.full_name.clone(), (service_idx, method_idx))
+                    })
+            })
+            .collect();
+        RepoView { services, methods }
+    }
+
+    /// The method called `target`, in any of the forms accepted by [`method_full_name`].
+    pub fn find_method_desc(&self, target: &str) -> Option<MethodView> {
+        let &(service_idx, method_idx) = self.methods.get(&method_full_name(target))?;
+        Some(self.services[service_idx].methods[method_idx].clone())
+    }
+}
+
+/// Turns the name of a method into the `package.Service.Method` form. Also accepts the form of
+/// HTTP/2 paths, `/package.Service/Method`, and a leading dot as in descriptors.
+pub fn method_full_name(target: &str) -> String {
+    let target = target.trim().trim_start_matches('/').trim_start_matches('.');
+    match target.rsplit_once('/') {
+        Some((service, method)) => format!("{service}.{method}"),
+        None => target.to_string(),
+    }
+}
+
+/// Methods that differ between two loads of the descriptors, by full name.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReloadSummary {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Methods whose signature, or the shape of their messages, changed.
+    pub changed: Vec<String>,
+}
+
+impl ReloadSummary {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
     }
 }
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+/// How to make up sample messages.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SampleOptions {
+    /// Seed of the random generator, the same seed always makes the same message. A random one is
+    /// used if not given.
+    pub seed: Option<u64>,
+    /// How many levels of nested messages to fill in.
+    pub max_depth: u32,
+    /// Most items put in repeated and map fields, 0 leaves them empty unless constraints ask for
+    /// items.
+    pub max_items: usize,
+    /// Whether to keep to the field constraints of protoc-gen-validate and protovalidate.
+    pub constraints: bool,
+}
+
+impl Default for SampleOptions {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            max_depth: 3,
+            max_items: 3,
+            constraints: true,
+        }
+    }
+}
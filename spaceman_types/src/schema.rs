@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Shape of a message and of every message and enum reachable from it, flattened so that
+/// recursive messages can be described.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Schema {
+    /// Full name of the message this schema was built for.
+    pub root: String,
+    /// Messages by full name. Well-known types are left out since they have a special JSON
+    /// representation.
+    pub messages: BTreeMap<String, MessageSchema>,
+    /// Enums by full name.
+    pub enums: BTreeMap<String, EnumSchema>,
+}
+
+impl Schema {
+    pub fn root(&self) -> Option<&MessageSchema> {
+        self.messages.get(&self.root)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MessageSchema {
+    pub name: String,
+    pub full_name: String,
+    pub fields: Vec<FieldSchema>,
+    /// Names of the oneofs declared in the message, not counting the synthetic ones that wrap
+    /// proto3 `optional` fields.
+    pub oneofs: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldSchema {
+    pub name: String,
+    /// Name of the field in the JSON mapping.
+    pub json_name: String,
+    pub number: u32,
+    /// Kind of the field or, for maps, of their values.
+    pub kind: FieldKind,
+    pub cardinality: Cardinality,
+    /// Index in `MessageSchema::oneofs` of the oneof this field belongs to.
+    pub oneof: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Cardinality {
+    Single,
+    Repeated,
+    Map { key: FieldKind },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FieldKind {
+    Double,
+    Float,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    Bool,
+    String,
+    Bytes,
+    /// Full name of the enum.
+    Enum(String),
+    /// Full name of the message.
+    Message(String),
+}
+
+impl FieldKind {
+    /// Whether values are represented as strings in JSON because they might not fit in a double.
+    pub fn is_64_bit(&self) -> bool {
+        matches!(
+            self,
+            FieldKind::Int64
+                | FieldKind::Uint64
+                | FieldKind::Sint64
+                | FieldKind::Fixed64
+                | FieldKind::Sfixed64
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EnumSchema {
+    pub name: String,
+    pub full_name: String,
+    pub values: Vec<EnumValueSchema>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EnumValueSchema {
+    pub name: String,
+    pub number: i32,
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SymbolKind {
+    Service,
+    Method,
+    Message,
+    Field,
+    Enum,
+    EnumValue,
+}
+
+impl SymbolKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymbolKind::Service => "service",
+            SymbolKind::Method => "method",
+            SymbolKind::Message => "message",
+            SymbolKind::Field => "field",
+            SymbolKind::Enum => "enum",
+            SymbolKind::EnumValue => "enum value",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub kind: SymbolKind,
+    pub full_name: String,
+    /// Message or enum the symbol is declared in, for fields and enum values.
+    pub parent: Option<String>,
+    /// The comment line that matched, when the query wasn't found in the name.
+    pub comment: Option<String>,
+    /// Methods whose input or output message contains the symbol, possibly through nested
+    /// messages. For methods it's just the method itself.
+    pub methods: Vec<String>,
+    pub score: i64,
+}
@@ -1,3 +1,4 @@
+use crate::calloptions::CallOptions;
 use crate::endpoint::Endpoint;
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
@@ -8,6 +9,8 @@ pub struct Profile {
     pub name: String,
     pub endpoint: Endpoint,
     pub ordinal: i64,
+    #[serde(default)]
+    pub call_options: CallOptions,
 }
 
 impl Profile {
@@ -16,21 +19,51 @@ impl Profile {
             name: String::new(),
             endpoint: Endpoint::default(),
             ordinal,
+            call_options: CallOptions::default(),
         }
     }
 }
 
 #[derive(Clone, Serialize, Deserialize, PartialEq)]
 pub struct Settings {
+    /// Descriptor files, directories of them or glob patterns matching them.
     pub proto_paths: Vec<String>,
+    /// Whether descriptors loaded later replace files that earlier ones defined differently,
+    /// rather than being rejected.
+    #[serde(default)]
+    pub later_descriptors_override: bool,
+    /// Whether new tabs start with a made up request rather than an empty one.
+    #[serde(default)]
+    pub sample_new_tabs: bool,
     pub profiles: HashMap<Uuid, Profile>,
+    /// Paths left out when diffing responses, see `spaceman_core::diff`.
+    #[serde(default)]
+    pub diff_ignore_paths: Vec<String>,
+    #[serde(default)]
+    pub saved_requests: Vec<SavedRequest>,
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
             proto_paths: Vec::new(),
+            later_descriptors_override: false,
+            sample_new_tabs: false,
             profiles: HashMap::new(),
+            diff_ignore_paths: Vec::new(),
+            saved_requests: Vec::new(),
         }
     }
 }
+
+/// A request kept around to be opened again in a new tab.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SavedRequest {
+    pub name: String,
+    pub method_full_name: String,
+    /// Request message in its JSON mapping.
+    pub body: String,
+    pub metadata: Vec<(String, String)>,
+    /// Profile to send the request with.
+    pub profile_id: Option<Uuid>,
+}
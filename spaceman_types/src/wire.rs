@@ -0,0 +1,137 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// How a field is encoded on the wire, which is all there is to know about it without a schema.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WireType {
+    Varint,
+    Fixed64,
+    LengthDelimited,
+    StartGroup,
+    EndGroup,
+    Fixed32,
+}
+
+impl WireType {
+    pub const ALL: [WireType; 6] = [
+        WireType::Varint,
+        WireType::Fixed64,
+        WireType::LengthDelimited,
+        WireType::StartGroup,
+        WireType::EndGroup,
+        WireType::Fixed32,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WireType::Varint => "varint",
+            WireType::Fixed64 => "i64",
+            WireType::LengthDelimited => "len",
+            WireType::StartGroup => "sgroup",
+            WireType::EndGroup => "egroup",
+            WireType::Fixed32 => "i32",
+        }
+    }
+
+    /// The wire type stored in the low 3 bits of a tag.
+    pub fn from_number(number: u64) -> Option<Self> {
+        Self::ALL.get(number as usize).copied()
+    }
+}
+
+/// A field decoded without knowing its type.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WireField {
+    pub number: u32,
+    pub value: WireValue,
+}
+
+/// Value of a field decoded without knowing its type. Length-delimited values are guessed to be
+/// a string if they are printable UTF-8, otherwise a message if they parse as one, otherwise
+/// bytes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum WireValue {
+    Varint(u64),
+    Fixed64(u64),
+    Fixed32(u32),
+    String(String),
+    Message(Vec<WireField>),
+    Bytes(Vec<u8>),
+    Group(Vec<WireField>),
+}
+
+impl WireValue {
+    pub fn wire_type(&self) -> WireType {
+        match self {
+            WireValue::Varint(_) => WireType::Varint,
+            WireValue::Fixed64(_) => WireType::Fixed64,
+            WireValue::Fixed32(_) => WireType::Fixed32,
+            WireValue::String(_) | WireValue::Message(_) | WireValue::Bytes(_) => {
+                WireType::LengthDelimited
+            }
+            WireValue::Group(_) => WireType::StartGroup,
+        }
+    }
+}
+
+/// A field of a received message that its descriptor doesn't know about, usually because the
+/// server has a newer version of the protos.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnknownField {
+    /// Where the field was found, like `items[2].price`, or empty if at the top level.
+    pub path: String,
+    pub field: WireField,
+}
+
+/// A message as it came over the wire, rendered for inspecting it without a schema. Only made
+/// when asked for, see `get_raw_dump` of the backend.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RawDump {
+    /// One field per line, with guesses of what length-delimited values are.
+    pub wire: String,
+    /// The bytes in hex, 16 per line.
+    pub hex: String,
+}
+
+impl fmt::Display for WireField {
+    /// Like text format, with numbers in place of field names.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.number)?;
+        match &self.value {
+            WireValue::Varint(value) => write!(f, "{value}"),
+            WireValue::Fixed64(value) => write!(f, "0x{value:016x}"),
+            WireValue::Fixed32(value) => write!(f, "0x{value:08x}"),
+            WireValue::String(value) => write!(f, "{value:?}"),
+            WireValue::Bytes(value) => {
+                let escaped: String = value
+                    .iter()
+                    .flat_map(|byte| std::ascii::escape_default(*byte))
+                    .map(char::from)
+                    .collect();
+                write!(f, "b\"{escaped}\"")
+            }
+            WireValue::Message(fields) | WireValue::Group(fields) => {
+                write!(f, "{{")?;
+                for (i, field) in fields.iter().enumerate() {
+                    write!(f, "{}{field}", if i == 0 { " " } else { ", " })?;
+                }
+                write!(f, " }}")
+            }
+        }
+    }
+}
+
+impl fmt::Display for UnknownField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}.", self.path)?;
+        }
+        write!(
+            f,
+            "{} ({})",
+            self.field,
+            self.field.value.wire_type().as_str()
+        )
+    }
+}
@@ -0,0 +1,22 @@
+use crate::repo::MethodView;
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+
+/// The tabs open in the GUI, saved so that they survive restarts.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Workspace {
+    pub tabs: Vec<WorkspaceTab>,
+    pub active_tab: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct WorkspaceTab {
+    /// The method as it was when the workspace was saved. Tabs are matched
+    /// against the loaded protos by `full_name` when restored.
+    pub method: MethodView,
+    /// Content of the input editor.
+    pub input: String,
+    pub metadata: Vec<(String, String)>,
+    pub profile_id: Option<Uuid>,
+    pub follow_output: bool,
+}